@@ -0,0 +1,35 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+use exo_class_file::item::file::ClassFile;
+
+const TEST_CLASS: &[u8] = include_bytes!("../../local/Test.class");
+
+fn parse(c: &mut Criterion) {
+    c.bench_function("parse Test.class", |b| {
+        b.iter(|| ClassFile::read_from_bytes(black_box(TEST_CLASS)).unwrap())
+    });
+}
+
+fn verify(c: &mut Criterion) {
+    let class_file = ClassFile::read_from_bytes(TEST_CLASS).unwrap();
+    c.bench_function("verify_all Test.class", |b| {
+        b.iter(|| black_box(&class_file).verify_all().unwrap())
+    });
+}
+
+fn utf8_lookup(c: &mut Criterion) {
+    let class_file = ClassFile::read_from_bytes(TEST_CLASS).unwrap();
+    let cp = &class_file.constant_pool;
+    let indices: Vec<usize> = (1..=cp.entries.len())
+        .filter(|i| cp.get_utf8_constant(*i).is_ok())
+        .collect();
+    c.bench_function("get_utf8_constant over the pool", |b| {
+        b.iter(|| {
+            for i in &indices {
+                black_box(cp.get_utf8_constant(*i).unwrap());
+            }
+        })
+    });
+}
+
+criterion_group!(benches, parse, verify, utf8_lookup);
+criterion_main!(benches);
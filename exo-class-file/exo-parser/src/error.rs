@@ -1,8 +1,10 @@
-use std::{error::Error, fmt::{Display, Debug}};
+use alloc::{boxed::Box, string::String, vec::Vec};
+use core::fmt::{Display, Debug};
 
 
-pub type Result<T> = std::result::Result<T, ParsingError>;
-impl Error for ParsingError {}
+pub type Result<T> = core::result::Result<T, ParsingError>;
+#[cfg(feature = "std")]
+impl std::error::Error for ParsingError {}
 
 #[derive(Debug)]
 pub struct ParsingError {
@@ -16,8 +18,8 @@ impl ParsingError {
     }
 }
 impl Display for ParsingError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "ParsingError({}, position({}))", self.error_type, self.position)
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        write!(f, "{} at position {}", self.error_type, self.position)
     }
 }
 
@@ -26,15 +28,17 @@ pub enum ParsingErrorType {
     EOSError,
     GenericError(Box<dyn Debug>),
     TokenizerError(String),
+    /// A character which can't appear where it was found.
+    UnexpectedChar(char),
 }
 impl ParsingErrorType {
     pub fn to(self, lexer: &crate::LexerStream) -> ParsingError {
-        ParsingError::new(self, lexer.lexer().currently_parsing_list(), lexer.position)
+        ParsingError::new(self, lexer.lexer().currently_parsing_list(), lexer.absolute_position())
     }
 }
 
 impl Display for ParsingErrorType {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
         match self {
             Self::EOSError => {
                 write!(f, "parsing error: end of stream")
@@ -43,6 +47,7 @@ impl Display for ParsingErrorType {
             Self::TokenizerError(detail) => {
                 write!(f, "parsing error: {}", detail)
             }
+            Self::UnexpectedChar(c) => write!(f, "unexpected {:?}", c),
         }
     }
 }
@@ -50,6 +55,6 @@ impl Display for ParsingErrorType {
 #[macro_export]
 macro_rules! parse_err {
     ($position:expr, $details:expr) => {
-        ParsingErrorType::TokenizerError($details.to_string()).to(&$position)
+        ParsingErrorType::TokenizerError($crate::alloc::string::ToString::to_string(&$details)).to(&$position)
     };
 }
\ No newline at end of file
@@ -1,3 +1,4 @@
+
 use crate::{error::{ParsingErrorType}, tokens::{Parseable}, parse_err, LexerStream};
 
 use super::error::Result;
@@ -55,10 +56,9 @@ impl<const C: char> Parseable for Char<C> {
     fn parse(s: &mut LexerStream) -> Result<Self> {
         let c = s.char()?;
         if c != C {
-            return Err(parse_err!(s, format!("incorrect character, expected {} but got {}", C, match c {
-                '\n' => "newline".to_string(),
-                c => c.to_string()
-            })));
+            // report the position of the character itself
+            s.position -= 1;
+            return Err(ParsingErrorType::UnexpectedChar(c).to(s));
         }
         Ok(Self)
     }
@@ -1,6 +1,7 @@
 use super::error::Result;
 use crate::{error::{ParsingError, ParsingErrorType}, parse_err};
-use std::{cell::{RefCell, Ref}, fmt::Debug, rc::Rc, ops::{Range, Deref}, any::type_name};
+use alloc::{rc::Rc, string::{String, ToString}, vec::Vec};
+use core::{cell::{RefCell, Ref}, fmt::Debug, ops::{Range, Deref}, any::type_name};
 
 /// Generic `Tokenable` trait.
 /// Implemented by all tokens.
@@ -111,12 +112,21 @@ impl Lexer {
 pub struct LexerStream {
     pub chars: Vec<char>,
     pub position: usize,
+    /// Position of the first character of this stream in
+    /// the string it was split from.
+    offset: usize,
     lexer: LexerRef,
 }
 
 impl LexerStream {
-    fn new(chars: Vec<char>, position: usize, lexer: LexerRef) -> Self {
-        Self { chars, position, lexer }
+    fn new(chars: Vec<char>, offset: usize, lexer: LexerRef) -> Self {
+        Self { chars, position: 0, offset, lexer }
+    }
+
+    /// The current position in the original string,
+    /// rather than in this stream.
+    pub fn absolute_position(&self) -> usize {
+        self.offset + self.position
     }
 
     pub fn split(&mut self, range: Range<usize>) -> Result<LexerStream> {
@@ -124,8 +134,9 @@ impl LexerStream {
             parse_err!(self, "range outside of eaten characters");
         }
 
+        let offset = self.offset + range.start;
         let chars = self.chars.drain(range).collect();
-        Ok(Self::new(chars, 0, self.lexer.clone()))        
+        Ok(Self::new(chars, offset, self.lexer.clone()))        
     }
 
 
@@ -156,7 +167,7 @@ impl LexerStream {
     /// Attempts to parse a parseable item. On failure,
     /// returns the error and how many tokens were successfully
     /// parsed.
-    pub fn token<T: Parseable>(&mut self) -> std::result::Result<Token<T>, (ParsingError, usize)> {
+    pub fn token<T: Parseable>(&mut self) -> core::result::Result<Token<T>, (ParsingError, usize)> {
         self.lexer.borrow_mut().currently_parsing.push(T::name().to_string());
         let start = self.position;
         self.lexer.borrow_mut().successful_parsed_tokens.push(0);
@@ -232,7 +243,7 @@ pub fn enclosed<A: Parseable, B: Parseable>(stream: &mut LexerStream) -> Result<
             v.pop();
             // println!("VCHAR Z {:?}", v);
             //stream.chars.remove(0);
-            return Ok(LexerStream::new(v, 0, stream.lexer.clone()));
+            return Ok(LexerStream::new(v, stream.offset + start_pos + 1, stream.lexer.clone()));
             
         }
 
@@ -1,11 +1,16 @@
-use std::string::FromUtf8Error;
+use alloc::{boxed::Box, string::{FromUtf8Error, String}};
+use core::fmt;
+
+use exo_parser::error::ParsingError;
+
+use crate::{item::{attribute_info::AttributeContext, constant_pool::ConstantPoolVerificationError, file::ClassFlagConflict, opcodes::CodeVerificationError}, stream::ParseDiagnostic};
 
 
 /// An error which can occur on deserialization of a class file.
 #[derive(Debug)]
 pub enum ClassFileError {
-    /// A generic I/O error.
-    IoError(std::io::Error),
+    /// A generic I/O error, such as the input ending early.
+    IoError(crate::io::Error),
 
     /// Returned when a class file has a bad magic number.
     BadMagicNumber(u32),
@@ -16,12 +21,24 @@ pub enum ClassFileError {
     /// Returned when invalid UTF-8 is found.
     InvalidUTF8Error(FromUtf8Error),
 
+    /// Returned when bytes are not valid modified UTF-8,
+    /// holding the offset of the offending byte.
+    InvalidModifiedUTF8(usize),
+
     /// Returned when an unknown reference kind is found.
     UnknownReferenceKind(u8),
 
     /// Returned when bad class access flags are found.
     BadClassAccessFlags,
 
+    /// Returned when class access flags contain bits with no assigned meaning,
+    /// holding the unrecognised bits.
+    UnknownClassAccessFlags(u16),
+
+    /// Returned when class access flags break one of the rules of §4.1
+    /// on which flags can be combined.
+    BadClassFlagCombination(ClassFlagConflict),
+
     /// Returned when an unknown verification type info tag is found.
     UnknownVerificationTypeInfo,
     
@@ -34,6 +51,10 @@ pub enum ClassFileError {
     /// Returned when an unknown target type value is found.
     UnknownTargetTypeValue(u8),
 
+    /// Returned when a type annotation's target type is not
+    /// permitted in the structure the annotation appears in.
+    TypeAnnotationWrongContext(u8, AttributeContext),
+
     /// Returned when an unknown type path kind value is found.
     UnknownTypePathKind(u8),
     
@@ -43,6 +64,9 @@ pub enum ClassFileError {
     /// Returned when a string constant was expected.
     ExpectedString,
 
+    /// Returned when a constant at this index has a different type than expected.
+    UnexpectedConstantType(usize),
+
     /// Returned when an unknown attribute is found.
     UnknownAttribute(String),
 
@@ -64,11 +88,139 @@ pub enum ClassFileError {
     /// Returned when an invalid constant index is encountered.
     InvalidConstant(usize),
 
+    /// Returned when a field or method descriptor is malformed,
+    /// holding where parsing failed.
+    MalformedDescriptor(ParsingError),
+
+    /// Returned when an instruction has an operand byte with a value the
+    /// format doesn't allow, holding the instruction's byte offset.
+    BadInstructionOperand(usize),
+
+    /// Returned when a `Code` attribute's code_length exceeds the limit.
+    CodeTooLong(u32),
+
+    /// Returned when annotations or element values nest
+    /// more deeply than the configured limit.
+    RecursionLimitExceeded,
+
+    /// Returned when reading would take a stream past its limit
+    /// on the total number of bytes read, holding the limit.
+    ByteLimitExceeded(usize),
+
+    /// Returned when a bootstrap method index doesn't refer to
+    /// an entry of the `BootstrapMethods` attribute.
+    BadBootstrapMethodIndex(u16),
+
+    /// Returned when the generic signature in the `UTF8` entry at this index is malformed.
+    MalformedSignature(u16),
+
+    /// Returned when an attribute appears in a structure it is not allowed in.
+    AttributeInWrongContext(String, AttributeContext),
+
+    /// Returned when the attribute table of a `Code` attribute contains
+    /// an attribute which may not appear there, such as another `Code`.
+    IllegalNestedAttribute(String),
+
+    /// Returned when attributes are parsed without a constant pool.
+    MissingConstantPool,
+
     /// Returned when a bad constant pool length is found.
     BadConstantPoolLength,
 
+    /// Returned when an entry can't be added to a constant pool which
+    /// already has the most entries a class file can hold.
+    ConstantPoolFull,
+
+    /// Returned when constant pool entries reference each other in a cycle,
+    /// starting from the entry at this index.
+    ConstantPoolCycle(usize),
+
+    /// Returned when a class file's version is older than any the
+    /// specification describes.
+    UnsupportedVersion(u16, u16),
+
+    /// Returned when the constant pool fails verification.
+    ConstantPoolVerification(Box<ConstantPoolVerificationError>),
+
+    /// Returned when the code of the method at this index fails verification.
+    CodeVerification(usize, Box<CodeVerificationError>),
+
+    /// Returned when a diagnostic is found while parsing with
+    /// [ParseOptions::diagnostics_as_errors](crate::stream::ParseOptions::diagnostics_as_errors) set.
+    Diagnostic(ParseDiagnostic),
+
     /// Returned in the case of a generic arithmetic error.
     ArithmeticError
 }
 
-pub type Result<T> = std::result::Result<T, ClassFileError>;
+pub type Result<T> = core::result::Result<T, ClassFileError>;
+
+impl From<crate::io::Error> for ClassFileError {
+    fn from(e: crate::io::Error) -> Self {
+        Self::IoError(e)
+    }
+}
+
+impl fmt::Display for ClassFileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::IoError(e) => write!(f, "I/O error: {}", e),
+            Self::BadMagicNumber(v) => write!(f, "bad magic number {:#010x}", v),
+            Self::UnknownConstantPoolTag(v) => write!(f, "unknown constant pool tag {}", v),
+            Self::InvalidUTF8Error(e) => write!(f, "invalid UTF-8: {}", e),
+            Self::InvalidModifiedUTF8(v) => write!(f, "invalid modified UTF-8 at byte {}", v),
+            Self::UnknownReferenceKind(v) => write!(f, "unknown method handle reference kind {}", v),
+            Self::BadClassAccessFlags => f.write_str("bad class access flags"),
+            Self::UnknownClassAccessFlags(v) => write!(f, "unknown class access flags {:#06x}", v),
+            Self::BadClassFlagCombination(v) => write!(f, "bad class access flags: {}", v),
+            Self::UnknownVerificationTypeInfo => f.write_str("unknown verification type info tag"),
+            Self::UnknownStackMapFrameTag(v) => write!(f, "unknown stack map frame tag {}", v),
+            Self::UnknownElementValueType(v) => write!(f, "unknown element value type {:?}", v),
+            Self::UnknownTargetTypeValue(v) => write!(f, "unknown type annotation target type {:#04x}", v),
+            Self::TypeAnnotationWrongContext(v, context) => {
+                write!(f, "type annotation target type {:#04x} is not allowed in {:?}", v, context)
+            }
+            Self::UnknownTypePathKind(v) => write!(f, "unknown type path kind {}", v),
+            Self::BadFormalParameterAccessFlags => f.write_str("bad formal parameter access flags"),
+            Self::ExpectedString => f.write_str("expected a Utf8 constant"),
+            Self::UnexpectedConstantType(v) => write!(f, "constant #{} has an unexpected type", v),
+            Self::UnknownAttribute(v) => write!(f, "unknown attribute {}", v),
+            Self::BadFieldAccessFlags => f.write_str("bad field access flags"),
+            Self::BadMethodAccessFlags => f.write_str("bad method access flags"),
+            Self::UnknownOpcodeError(v) => write!(f, "unknown opcode {:#04x}", v),
+            Self::UnknownEnumVariant(name, v) => write!(f, "unknown {} value {}", name, v),
+            Self::ConstantNotPresent(v) => write!(f, "no constant at index {}", v),
+            Self::InvalidConstant(v) => write!(f, "invalid constant index {}", v),
+            Self::MalformedDescriptor(e) => write!(f, "{} in descriptor", e),
+            Self::BadInstructionOperand(v) => write!(f, "bad operand in instruction at byte {}", v),
+            Self::CodeTooLong(v) => write!(f, "code length {} exceeds the limit", v),
+            Self::RecursionLimitExceeded => f.write_str("annotations nest too deeply"),
+            Self::ByteLimitExceeded(v) => write!(f, "read past the limit of {} bytes", v),
+            Self::BadBootstrapMethodIndex(v) => write!(f, "no bootstrap method at index {}", v),
+            Self::MalformedSignature(v) => write!(f, "malformed signature in constant #{}", v),
+            Self::AttributeInWrongContext(name, context) => write!(f, "attribute {} is not allowed in {:?}", name, context),
+            Self::IllegalNestedAttribute(v) => write!(f, "attribute {} is not allowed in a Code attribute", v),
+            Self::MissingConstantPool => f.write_str("attributes read without a constant pool"),
+            Self::BadConstantPoolLength => f.write_str("bad constant pool length"),
+            Self::ConstantPoolFull => f.write_str("constant pool is full"),
+            Self::ConstantPoolCycle(v) => write!(f, "constant pool cycle starting at #{}", v),
+            Self::UnsupportedVersion(major, minor) => write!(f, "unsupported class file version {}.{}", major, minor),
+            Self::ConstantPoolVerification(e) => write!(f, "constant pool verification failed: {:?}", e),
+            Self::CodeVerification(index, e) => write!(f, "code of method {} failed verification: {:?}", index, e),
+            Self::Diagnostic(v) => write!(f, "{:?}", v),
+            Self::ArithmeticError => f.write_str("arithmetic overflow"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for ClassFileError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::IoError(e) => Some(e),
+            Self::InvalidUTF8Error(e) => Some(e),
+            Self::MalformedDescriptor(e) => Some(e),
+            _ => None,
+        }
+    }
+}
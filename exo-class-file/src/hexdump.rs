@@ -0,0 +1,79 @@
+//! Hex dumps of raw bytes.
+
+use alloc::string::String;
+use core::fmt::Write;
+
+use crate::item::attribute_info::Attributes;
+
+/// Bytes shown on each line of a dump.
+const LINE_WIDTH: usize = 16;
+
+/// Render `data` as a table of offset, hex and ASCII columns, in the
+/// style of `hexdump -C`:
+///
+/// ```text
+/// 00000000  ca fe ba be 00 00 00 34  00 1d 0a 00 06 00 0f 09  |.......4........|
+/// ```
+///
+/// Bytes outside printable ASCII are shown as `.`. Every line, including
+/// the last, ends in a newline, and empty data renders as an empty string.
+pub fn hex_dump(data: &[u8]) -> String {
+    let mut out = String::new();
+    for (line, chunk) in data.chunks(LINE_WIDTH).enumerate() {
+        let _ = write!(out, "{:08x} ", line * LINE_WIDTH);
+        for i in 0..LINE_WIDTH {
+            if i % 8 == 0 {
+                out.push(' ');
+            }
+            match chunk.get(i) {
+                Some(v) => {
+                    let _ = write!(out, "{:02x} ", v);
+                }
+                None => out.push_str("   "),
+            }
+        }
+        out.push_str(" |");
+        out.extend(chunk.iter().map(|v| if v.is_ascii_graphic() || *v == b' ' { *v as char } else { '.' }));
+        out.push_str("|\n");
+    }
+    out
+}
+
+impl Attributes {
+    /// A [hex_dump] of the contents of an [Attributes::Unknown],
+    /// or `None` for any attribute the parser understood.
+    pub fn hex_dump(&self) -> Option<String> {
+        match self {
+            Attributes::Unknown { data, .. } => Some(hex_dump(data)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec, vec::Vec};
+
+    use crate::item::attribute_info::Attributes;
+
+    use super::hex_dump;
+
+    #[test]
+    fn hex_dump_test() {
+        assert_eq!(hex_dump(&[]), "");
+        let data: Vec<u8> = (0..20).map(|v| v + 0x3c).collect();
+        assert_eq!(
+            hex_dump(&data),
+            "00000000  3c 3d 3e 3f 40 41 42 43  44 45 46 47 48 49 4a 4b  |<=>?@ABCDEFGHIJK|\n\
+             00000010  4c 4d 4e 4f                                       |LMNO|\n"
+        );
+        assert_eq!(
+            hex_dump(&[0xca, 0xfe, b' ', b'a', 0x7f]),
+            "00000000  ca fe 20 61 7f                                    |.. a.|\n"
+        );
+
+        let unknown = Attributes::Unknown { name: "Vendor".to_string(), data: vec![0x01, b'x'] };
+        assert_eq!(unknown.hex_dump().unwrap(), hex_dump(&[0x01, b'x']));
+        assert!(Attributes::Synthetic.hex_dump().is_none());
+    }
+}
@@ -0,0 +1,92 @@
+//! Minimal I/O abstraction used by the parser.
+//!
+//! With the `std` feature this re-exports the [std::io] types. Without it,
+//! a small [Read] trait and [Cursor] are provided in their place.
+
+#[cfg(feature = "std")]
+pub use std::io::{Cursor, Error, Read};
+
+#[cfg(not(feature = "std"))]
+pub use self::no_std::{Cursor, Error, Read};
+
+#[cfg(not(feature = "std"))]
+mod no_std {
+    /// An I/O error.
+    #[derive(Debug)]
+    pub enum Error {
+        /// The end of the input was reached before enough bytes could be read.
+        UnexpectedEof,
+    }
+
+    impl core::fmt::Display for Error {
+        fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+            match self {
+                Self::UnexpectedEof => f.write_str("unexpected end of input"),
+            }
+        }
+    }
+
+    /// A source of bytes.
+    pub trait Read {
+        /// Read exactly enough bytes to fill `buf`.
+        fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error>;
+    }
+
+    impl Read for &[u8] {
+        fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+            if buf.len() > self.len() {
+                return Err(Error::UnexpectedEof);
+            }
+            let (a, b) = self.split_at(buf.len());
+            buf.copy_from_slice(a);
+            *self = b;
+            Ok(())
+        }
+    }
+
+    impl<R: Read + ?Sized> Read for &mut R {
+        fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+            (**self).read_exact(buf)
+        }
+    }
+
+    /// Wraps an in-memory buffer to make it readable.
+    #[derive(Debug, Clone, Default)]
+    pub struct Cursor<T> {
+        inner: T,
+        position: u64,
+    }
+
+    impl<T> Cursor<T> {
+        pub fn new(inner: T) -> Self {
+            Self { inner, position: 0 }
+        }
+
+        pub fn position(&self) -> u64 {
+            self.position
+        }
+
+        pub fn set_position(&mut self, position: u64) {
+            self.position = position;
+        }
+
+        pub fn get_ref(&self) -> &T {
+            &self.inner
+        }
+
+        pub fn into_inner(self) -> T {
+            self.inner
+        }
+    }
+
+    impl<T: AsRef<[u8]>> Read for Cursor<T> {
+        fn read_exact(&mut self, buf: &mut [u8]) -> Result<(), Error> {
+            let data = self.inner.as_ref();
+            let start = (self.position as usize).min(data.len());
+            let mut remaining = &data[start..];
+            remaining.read_exact(buf)?;
+            self.position += buf.len() as u64;
+            Ok(())
+        }
+    }
+}
@@ -1,12 +1,12 @@
-use std::{
-    io::{Cursor, Read},
-    ops::{Range, RangeInclusive}, collections::HashMap,
-};
+use alloc::{string::{String, ToString}, vec, vec::Vec};
+use core::ops::{Range, RangeInclusive};
 
 use crate::{
+    io::{Cursor, Read},
+    Map,
     error::{self, ClassFileError},
     item::{constant_pool::ConstantPool, file::ClassAccessFlags, ClassFileItem},
-    stream::ClassFileStream,
+    stream::{ClassFileStream, ParseDiagnostic, SkippedItem},
 };
 
 use self::{
@@ -15,23 +15,31 @@ use self::{
         Exceptions, InnerClasses, LineNumberTable, LocalVariableTable, LocalVariableTypeTable,
         MethodParameters, RuntimeInvisibleAnnotations, RuntimeInvisibleParameterAnnotations,
         RuntimeInvisibleTypeAnnotations, RuntimeVisibleAnnotations,
-        RuntimeVisibleParameterAnnotations, RuntimeVisibleTypeAnnotations, Signature,
+        Record, RuntimeVisibleParameterAnnotations, RuntimeVisibleTypeAnnotations, Signature,
         SourceDebugExtension, SourceFile, StackMapTable, Synthetic,
     },
-    elementvaluetypes::ElementValue,
     stackmap::StackMapFrame,
     typepathkinds::TypePathKind,
 };
 
-use super::opcodes::InstructionList;
+pub use self::elementvaluetypes::{ElementConstant, ElementValue, ElementValueType};
+pub use self::verification::{initial_stack_frame, VerificationTypeInfo};
+
+use super::{file::ClassFile, ids::signature::FieldSignature, opcodes::{Cfg, CodeVerificationError, InstructionList}};
 
 /// Verification type items.
 mod verification {
-    use std::io::Read;
+    use crate::io::Read;
+
+    use alloc::vec::Vec;
 
     use crate::{
         error::{self, ClassFileError},
-        item::{constant_pool::ConstantPool, ClassFileItem},
+        item::{
+            constant_pool::ConstantPool,
+            ids::{field::{BaseType, FieldType}, method::MethodDescriptor},
+            ClassFileItem,
+        },
         stream::ClassFileStream,
     };
 
@@ -46,7 +54,7 @@ mod verification {
     pub const ITEM_Uninitialized: u8 = 8;
 
     /// Verification types.
-    #[derive(Debug)]
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub enum VerificationTypeInfo {
         /// The Top_variable_info item indicates that the local variable has the verification type top.
         Top,
@@ -132,12 +140,51 @@ mod verification {
             }
         }
     }
+
+    /// Build the implicit initial frame of a method, which the frames of its
+    /// `StackMapTable` are applied to. The operand stack is empty, so only the
+    /// locals are returned: `this` for instance methods (`UninitializedThis`
+    /// in an `<init>` method), followed by the parameters.
+    ///
+    /// As in a full_frame, a `long` or `double` is a single entry covering
+    /// two locals. `class_index` maps a reference type to the index of its
+    /// `Class` constant; `this_class` is used for `this`.
+    pub fn initial_stack_frame(
+        descriptor: &MethodDescriptor,
+        is_static: bool,
+        is_init: bool,
+        this_class: u16,
+        mut class_index: impl FnMut(&FieldType) -> error::Result<u16>,
+    ) -> error::Result<Vec<VerificationTypeInfo>> {
+        let mut locals = Vec::with_capacity(descriptor.parameters.len() + 1);
+        if !is_static {
+            locals.push(if is_init {
+                VerificationTypeInfo::UninitializedThis
+            } else {
+                VerificationTypeInfo::Object { cpool_index: this_class }
+            });
+        }
+        for parameter in descriptor.parameters.iter() {
+            locals.push(match parameter {
+                FieldType::BaseType(BaseType::Long) => VerificationTypeInfo::Long,
+                FieldType::BaseType(BaseType::Double) => VerificationTypeInfo::Double,
+                FieldType::BaseType(BaseType::Float) => VerificationTypeInfo::Float,
+                // boolean, byte, char and short are all verified as int
+                FieldType::BaseType(_) => VerificationTypeInfo::Integer,
+                v => VerificationTypeInfo::Object { cpool_index: class_index(v)? },
+            });
+        }
+        Ok(locals)
+    }
 }
 
 /// Stack map frame items.
 mod stackmap {
+    use alloc::vec::Vec;
+
+    use core::ops::RangeInclusive;
 
-    use std::{io::Read, ops::Range};
+    use crate::io::Read;
 
     use crate::{
         error::{self, ClassFileError},
@@ -147,15 +194,14 @@ mod stackmap {
 
     use super::verification::VerificationTypeInfo;
 
-    pub const SAME: Range<u8> = (0..63);
-    pub const SAME_LOCALS_1_STACK_ITEM: Range<u8> = (64..127);
+    pub const SAME: RangeInclusive<u8> = 0..=63;
+    pub const SAME_LOCALS_1_STACK_ITEM: RangeInclusive<u8> = 64..=127;
     pub const SAME_LOCALS_1_STACK_ITEM_EXTENDED: u8 = 247;
-    pub const CHOP: Range<u8> = (248..250);
+    pub const CHOP: RangeInclusive<u8> = 248..=250;
     pub const SAME_FRAME_EXTENDED: u8 = 251;
-    pub const APPEND: Range<u8> = (252..254);
+    pub const APPEND: RangeInclusive<u8> = 252..=254;
     pub const FULL_FRAME: u8 = 255;
 
-    // TODO verify validity
     /// A stack map frame.
     #[derive(Debug)]
     pub enum StackMapFrame {
@@ -201,7 +247,11 @@ mod stackmap {
         ///
         /// The value of k is given by the formula 251 - frame_type. The offset_delta
         /// value for the frame is given explicitly.
-        ChopFrame { offset_delta: u16 },
+        ChopFrame {
+            offset_delta: u16,
+            /// The number of locals removed, k.
+            k: u8,
+        },
         /// The frame type same_frame_extended is represented by the tag 251.
         ///
         /// This frame type indicates that the frame has exactly the same
@@ -266,6 +316,7 @@ mod stackmap {
                 }),
                 v if CHOP.contains(&v) => Ok(Self::ChopFrame {
                     offset_delta: s.read_u2()?,
+                    k: 251 - v,
                 }),
                 SAME_FRAME_EXTENDED => Ok(Self::SameFrameExtended {
                     offset_delta: s.read_u2()?,
@@ -318,6 +369,7 @@ pub mod attrtype {
     pub const LocalVariableTable: &'static str = "LocalVariableTable";
     pub const LocalVariableTypeTable: &'static str = "LocalVariableTypeTable";
     pub const Deprecated: &'static str = "Deprecated";
+    pub const Record: &'static str = "Record";
 }
 
 /// Attributes in a class file.
@@ -349,41 +401,7 @@ pub enum Attributes {
 
     If the method is either native or abstract, its method_info structure must not have a Code attribute in its attributes table. Otherwise, its method_info structure must have exactly one Code attribute in its attributes table.
     **/
-    Code {
-        /// The value of the max_stack item gives the maximum
-        /// depth of the operand stack of this method
-        /// at any point during execution of the method.
-        max_stack: u16,
-        /// The value of the max_locals item gives the number of
-        /// local variables in the local variable array allocated
-        /// upon invocation of this method (§2.6.1), including the
-        /// local variables used to pass parameters to the method
-        /// on its invocation.
-        ///
-        /// The greatest local variable index for a value of type
-        /// long or double is max_locals - 2. The greatest local
-        /// variable index for a value of any other type
-        /// is max_locals - 1.
-        max_locals: u16,
-        /**
-        The code array gives the actual bytes of Java Virtual Machine code
-        that implement the method.
-
-        When the code array is read into memory on a byte-addressable machine,
-        if the first byte of the array is aligned on a 4-byte boundary, the
-        tableswitch and lookupswitch 32-bit offsets will be 4-byte aligned.
-        (Refer to the descriptions of those instructions for more
-        information on the consequences of code array alignment.)
-        **/
-        code: InstructionList,
-        /// Each entry in the exception_table array describes one
-        /// exception handler in the code array. The order of the
-        /// handlers in the exception_table array is significant.
-        exception_table: Vec<ExceptionTableEntry>,
-        /// Each value of the attributes table must be an attribute_info structure (§4.7).
-        /// A Code attribute can have any number of optional attributes associated with it.
-        attributes: AttributesCollection,
-    },
+    Code(CodeAttribute),
     /// The StackMapTable attribute is a variable-length attribute in the
     /// attributes table of a Code attribute (§4.7.3).
     ///
@@ -692,6 +710,21 @@ pub enum Attributes {
     MethodParameters {
         parameters: Vec<MethodParametersElement>,
     },
+    /// The Record attribute is a variable-length attribute
+    /// in the attributes table of a ClassFile structure (§4.1).
+    ///
+    /// The Record attribute indicates that the current class
+    /// is a record class, and stores information about the
+    /// record components of the record class.
+    ///
+    /// There may be at most one Record attribute in the
+    /// attributes table of a ClassFile structure.
+    Record {
+        /// Each entry in the components table specifies
+        /// a record component of the record class,
+        /// in declaration order.
+        components: Vec<RecordComponentInfo>,
+    },
     /// The SourceFile attribute is an optional fixed-length
     /// attribute in the attributes table of a ClassFile
     /// structure (§4.1).
@@ -814,189 +847,463 @@ pub enum Attributes {
     /// a Deprecated attribute does not alter the
     /// semantics of a class or interface.
     Deprecated,
+    /// An attribute this parser does not recognize, such as a
+    /// vendor-specific attribute emitted by another compiler.
+    ///
+    /// Only produced when parsing with
+    /// [ParseOptions::tolerant](crate::stream::ParseOptions::tolerant).
+    Unknown {
+        /// The name of the attribute.
+        name: String,
+        /// The raw contents of the attribute.
+        data: Vec<u8>,
+    },
 }
 
 /// Collection of all attributes.
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct AttributesCollection {
-    pub collection: HashMap<String, Vec<Attributes>>
+    /// Each attribute with its name and the `attribute_name_index` it was
+    /// read with, or 0 if it wasn't read from a class file, in declaration order.
+    attributes: Vec<(String, u16, Attributes)>,
+    /// The positions in `attributes` of the attributes of each name.
+    index: Map<String, Vec<usize>>,
 }
 impl AttributesCollection {
+    /// An empty attribute table.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Add an attribute named `k` to the end of the table.
+    /// It has no `attribute_name_index`, as it wasn't read from a class file.
+    pub fn push(&mut self, k: &str, v: Attributes) {
+        self.insert(k.to_string(), 0, v);
+    }
+
     /// Insert an attribute in to the collection.
-    fn insert(&mut self, k: String, v: Attributes) {
-        self.collection.entry(k).or_default().push(v);
+    fn insert(&mut self, k: String, name_index: u16, v: Attributes) {
+        self.index.entry(k.clone()).or_default().push(self.attributes.len());
+        self.attributes.push((k, name_index, v));
+    }
+
+    /// Whether the table holds no attributes.
+    pub fn is_empty(&self) -> bool {
+        self.attributes.is_empty()
+    }
+
+    /// The distinct names of the attributes in the table, in no particular order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.index.keys().map(|v| v.as_str())
+    }
+
+    /// Iterate over the attributes with their names in declaration order,
+    /// followed by those added with [push](Self::push).
+    pub fn iter_ordered(&self) -> impl Iterator<Item = (&str, &Attributes)> {
+        self.attributes.iter().map(|(k, _, v)| (k.as_str(), v))
     }
 
-    pub fn get(&self, k: &str) -> &[Attributes] {
-        self.collection.get(k).map(|v| v.as_slice()).unwrap_or(&[])
+    /// Get the attributes named `k` in declaration order.
+    pub fn get(&self, k: &str) -> impl Iterator<Item = &Attributes> {
+        self.index.get(k).into_iter().flatten().map(|i| &self.attributes[*i].2)
     }
 
+    /// Get the first attribute named `k`, if any.
+    pub fn first(&self, k: &str) -> Option<&Attributes> {
+        self.get(k).next()
+    }
+
+    /// Get the original `attribute_name_index` of the `i`th attribute named `k`,
+    /// or `None` if it was not read from a class file.
+    pub fn name_index(&self, k: &str, i: usize) -> Option<u16> {
+        let name_index = self.attributes[*self.index.get(k)?.get(i)?].1;
+        (name_index != 0).then_some(name_index)
+    }
+
+    /// Get the `signature_index` of the first `Signature` attribute, if any.
+    pub(crate) fn signature_index(&self) -> Option<u16> {
+        self.get(Signature).find_map(|v| match v {
+            Attributes::Signature { signature_index } => Some(*signature_index),
+            _ => None,
+        })
+    }
+
+    /// Get the indices of the descriptors in the annotations of this table:
+    /// annotation types, enum constant types and class literals.
+    pub(crate) fn annotation_descriptor_indices(&self) -> Vec<u16> {
+        let mut indices = vec![];
+        for (_, attribute) in self.iter_ordered() {
+            match attribute {
+                Attributes::RuntimeVisibleAnnotations { annotations }
+                | Attributes::RuntimeInvisibleAnnotations { annotations } => {
+                    annotations.iter().for_each(|v| v.descriptor_indices(&mut indices));
+                }
+                Attributes::RuntimeVisibleParameterAnnotations { parameter_annotations }
+                | Attributes::RuntimeInvisibleParameterAnnotations { parameter_annotations } => {
+                    for parameter in parameter_annotations {
+                        parameter.annotations.iter().for_each(|v| v.descriptor_indices(&mut indices));
+                    }
+                }
+                Attributes::RuntimeVisibleTypeAnnotations { annotations }
+                | Attributes::RuntimeInvisibleTypeAnnotations { annotations } => {
+                    for annotation in annotations {
+                        indices.push(annotation.type_index);
+                        annotation.element_value_pairs.iter().for_each(|v| v.value.descriptor_indices(&mut indices));
+                    }
+                }
+                Attributes::AnnotationDefault { default_value } => default_value.descriptor_indices(&mut indices),
+                _ => (),
+            }
+        }
+        indices
+    }
+
+    /// Remove the attributes named `k`, returning them in declaration order.
     pub fn take(&mut self, k: &str) -> Vec<Attributes> {
-        self.collection.remove(k).unwrap_or_default()
+        if self.index.remove(k).is_none() {
+            return vec![];
+        }
+        let (taken, kept) = core::mem::take(&mut self.attributes).into_iter().partition(|v| v.0 == k);
+        self.attributes = kept;
+        self.index.clear();
+        for (i, v) in self.attributes.iter().enumerate() {
+            self.index.entry(v.0.clone()).or_default().push(i);
+        }
+        taken.into_iter().map(|v: (String, u16, Attributes)| v.2).collect()
     }
 }
 
-impl ClassFileItem for AttributesCollection {
-    fn read_from_stream<R: Read>(s: &mut ClassFileStream<R>, cp: Option<&ConstantPool>) -> error::Result<Self>
-    where
-        Self: Sized {
-        let attributes_count = s.read_u2()?;
-        let mut attributes = Self {
-            collection: HashMap::new()
-        };
+/// Attributes which may appear at most once in an attribute table.
+const AT_MOST_ONCE: [&str; 19] = [
+    ConstantValue,
+    Code,
+    StackMapTable,
+    Exceptions,
+    InnerClasses,
+    EnclosingMethod,
+    Signature,
+    SourceFile,
+    SourceDebugExtension,
+    BootstrapMethods,
+    RuntimeVisibleAnnotations,
+    RuntimeInvisibleAnnotations,
+    RuntimeVisibleParameterAnnotations,
+    RuntimeInvisibleParameterAnnotations,
+    RuntimeVisibleTypeAnnotations,
+    RuntimeInvisibleTypeAnnotations,
+    AnnotationDefault,
+    MethodParameters,
+    Record,
+];
+
+/// The structure an attribute table belongs to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AttributeContext {
+    ClassFile,
+    Field,
+    Method,
+    Code,
+    RecordComponent,
+}
+
+impl AttributeContext {
+    /// Whether a type annotation with the target type `target_type`
+    /// may appear in this context, per table 4.7.20-A and 4.7.20-B.
+    pub fn allows_target_type(self, target_type: u8) -> bool {
+        match self {
+            Self::ClassFile => [0x00, 0x10, 0x11].contains(&target_type),
+            Self::Method => [0x01, 0x12, 0x14, 0x15, 0x16, 0x17].contains(&target_type),
+            Self::Field | Self::RecordComponent => target_type == 0x13,
+            Self::Code => (0x40..=0x4B).contains(&target_type),
+        }
+    }
+
+    /// Whether the attribute named `name` may appear in this context.
+    /// Attributes not defined by the specification may appear anywhere.
+    pub fn allows(self, name: &str) -> bool {
+        if [SourceFile, InnerClasses, EnclosingMethod, SourceDebugExtension, BootstrapMethods, Record].contains(&name) {
+            self == Self::ClassFile
+        } else if name == ConstantValue {
+            self == Self::Field
+        } else if [
+            Code,
+            Exceptions,
+            RuntimeVisibleParameterAnnotations,
+            RuntimeInvisibleParameterAnnotations,
+            AnnotationDefault,
+            MethodParameters,
+        ]
+        .contains(&name)
+        {
+            self == Self::Method
+        } else if [StackMapTable, LineNumberTable, LocalVariableTable, LocalVariableTypeTable].contains(&name) {
+            self == Self::Code
+        } else if [Synthetic, Deprecated].contains(&name) {
+            ![Self::Code, Self::RecordComponent].contains(&self)
+        } else if [Signature, RuntimeVisibleAnnotations, RuntimeInvisibleAnnotations].contains(&name) {
+            self != Self::Code
+        } else {
+            true
+        }
+    }
+}
+
+impl AttributesCollection {
+    /// Read an attribute table belonging to `context`, rejecting
+    /// attributes which may not appear there.
+    pub fn read_in_context<R: Read>(
+        s: &mut ClassFileStream<R>,
+        cp: Option<&ConstantPool>,
+        context: AttributeContext,
+    ) -> error::Result<Self> {
+        Self::read(s, cp, Some(context))
+    }
+
+    fn read<R: Read>(
+        stream: &mut ClassFileStream<R>,
+        cp: Option<&ConstantPool>,
+        context: Option<AttributeContext>,
+    ) -> error::Result<Self> {
+        let attributes_count = stream.read_u2()?;
+        let mut attributes = Self::new();
+        let options = stream.options();
         for _ in 0..attributes_count {
-            let cp = cp.expect("constant pool should exist at the time of attribute deserialization");
-            let attribute_name_index = s.read_u2()?;
-            let attribute_length = s.read_u4()?;
-            let mut info = Cursor::new(s.read_dynamic(attribute_length as usize)?);
-    
-            let mut s = ClassFileStream::new(&mut info);
-    
-            let attribute_name = cp.get_utf8_constant(attribute_name_index as usize)?;
-    
-            let a = match attribute_name {
-                ConstantValue => Ok(Attributes::ConstantValue {
-                    constantvalue_index: s.read_u2()?,
-                }),
-                Code => {
-                    let max_stack = s.read_u2()?;
-                    let max_locals = s.read_u2()?;
-                    let code_length = s.read_u4()?;
-                    let code = s.read_sequence::<u8>(Some(cp), code_length as usize)?;
-                    let exception_table_length = s.read_u2()?;
-                    let exception_table = s.read_sequence::<ExceptionTableEntry>(
-                        Some(cp),
-                        exception_table_length as usize,
-                    )?;
-                    let attributes = AttributesCollection::read_from_stream(&mut s, Some(cp))?;
+            let cp = cp.ok_or(ClassFileError::MissingConstantPool)?;
+            let attribute_name_index = stream.read_u2()?;
+            let attribute_length = stream.read_u4()?;
+            let mut info = Cursor::new(stream.read_dynamic(attribute_length as usize)?);
     
-                    let code = InstructionList::read_from_stream(
-                        &mut ClassFileStream::new(&mut Cursor::new(code)),
-                        Some(cp),
-                    )?;
-                    Ok(Attributes::Code {
-                        max_stack,
-                        max_locals,
-                        code,
-                        exception_table,
-                        attributes,
-                    })
-                }
-                StackMapTable => {
-                    let number_of_entries = s.read_u2()?;
-                    let entries = s.read_sequence(Some(cp), number_of_entries as usize)?;
-                    Ok(Attributes::StackMapTable { entries })
-                }
-                Exceptions => {
-                    let number_of_exceptions = s.read_u2()?;
-                    let exception_index_table =
-                        s.read_sequence(Some(cp), number_of_exceptions as usize)?;
-                    Ok(Attributes::Exceptions {
-                        exception_index_table,
-                    })
+            let mut s = ClassFileStream::with_options(&mut info, options);
+            let a = Self::read_attribute(&mut s, cp, attribute_name_index, attribute_length, context);
+            stream.absorb(&mut s);
+            let (attribute_name, a) = match a {
+                Ok(v) => v,
+                Err(e) => {
+                    // the attribute's length is known, so the stream is already past it
+                    let item = SkippedItem::Attribute { name_index: attribute_name_index, context };
+                    stream.recover(item, e)?;
+                    continue;
                 }
-                InnerClasses => {
-                    let number_of_classes = s.read_u2()?;
-                    Ok(Attributes::InnerClasses {
-                        classes: s.read_sequence(Some(cp), number_of_classes as usize)?,
-                    })
-                }
-                EnclosingMethod => Ok(Attributes::EnclosingMethod {
-                    class_index: s.read_u2()?,
-                    method_index: s.read_u2()?,
-                }),
-                Synthetic => Ok(Attributes::Synthetic),
-                Signature => Ok(Attributes::Signature {
-                    signature_index: s.read_u2()?,
-                }),
-                SourceFile => Ok(Attributes::SourceFile {
-                    sourcefile_index: s.read_u2()?,
-                }),
-                SourceDebugExtension => {
-                    let bytes = s.read_dynamic(attribute_length as usize)?;
-                    Ok(Attributes::SourceDebugExtension {
-                        debug_extension: bytes,
-                    })
-                }
-                LineNumberTable => {
-                    let line_number_table_length = s.read_u2()?;
-                    Ok(Attributes::LineNumberTable {
-                        line_number_table: s
-                            .read_sequence(Some(cp), line_number_table_length as usize)?,
-                    })
-                }
-                LocalVariableTable => {
-                    let local_variable_table_length = s.read_u2()?;
-                    Ok(Attributes::LocalVariableTable {
-                        local_variable_table: s
-                            .read_sequence(Some(cp), local_variable_table_length as usize)?,
-                    })
-                }
-                LocalVariableTypeTable => {
-                    let local_variable_type_table_length = s.read_u2()?;
-                    Ok(Attributes::LocalVariableTypeTable {
-                        local_variable_type_table: s
-                            .read_sequence(Some(cp), local_variable_type_table_length as usize)?,
-                    })
-                }
-                Deprecated => Ok(Attributes::Deprecated),
-                RuntimeVisibleAnnotations => {
-                    let num_annotations = s.read_u2()?;
-                    Ok(Attributes::RuntimeVisibleAnnotations {
-                        annotations: s.read_sequence(Some(cp), num_annotations as usize)?,
-                    })
-                }
-                RuntimeInvisibleAnnotations => {
-                    let num_annotations = s.read_u2()?;
-                    Ok(Attributes::RuntimeInvisibleAnnotations {
-                        annotations: s.read_sequence(Some(cp), num_annotations as usize)?,
-                    })
-                }
-                RuntimeVisibleParameterAnnotations => {
-                    let num_parameters = s.read_u1()?;
-                    Ok(Attributes::RuntimeVisibleParameterAnnotations {
-                        parameter_annotations: s.read_sequence(Some(cp), num_parameters as usize)?,
-                    })
-                }
-                RuntimeInvisibleParameterAnnotations => {
-                    let num_parameters = s.read_u1()?;
-                    Ok(Attributes::RuntimeInvisibleParameterAnnotations {
-                        parameter_annotations: s.read_sequence(Some(cp), num_parameters as usize)?,
-                    })
-                }
-                RuntimeVisibleTypeAnnotations => {
-                    let num_annotations = s.read_u2()?;
-                    Ok(Attributes::RuntimeVisibleTypeAnnotations {
-                        annotations: s.read_sequence(Some(cp), num_annotations as usize)?,
-                    })
-                }
-                RuntimeInvisibleTypeAnnotations => {
-                    let num_annotations = s.read_u2()?;
-                    Ok(Attributes::RuntimeInvisibleTypeAnnotations {
-                        annotations: s.read_sequence(Some(cp), num_annotations as usize)?,
-                    })
-                }
-                AnnotationDefault => Ok(Attributes::AnnotationDefault {
-                    default_value: ElementValue::read_from_stream(&mut s, Some(cp))?,
-                }),
-                BootstrapMethods => {
-                    let num_bootstrap_methods = s.read_u2()?;
-                    Ok(Attributes::BootstrapMethods {
-                        bootstrap_methods: s.read_sequence(Some(cp), num_bootstrap_methods as usize)?,
-                    })
-                }
-                MethodParameters => {
-                    let parameters_count = s.read_u1()?;
-                    Ok(Attributes::MethodParameters {
-                        parameters: s.read_sequence(Some(cp), parameters_count as usize)?,
-                    })
-                }
-                v => Err(ClassFileError::UnknownAttribute(v.to_string())),
-            }?;
-            attributes.insert(attribute_name.to_string(), a);
+            };
+            if attributes.first(attribute_name).is_some() && AT_MOST_ONCE.contains(&attribute_name) {
+                stream.diagnose(ParseDiagnostic::DuplicateAttribute {
+                    name: attribute_name.to_string(),
+                    context,
+                })?;
+            }
+            attributes.insert(attribute_name.to_string(), attribute_name_index, a);
         };
         Ok(attributes)
     }
+
+    /// Read the body of a single attribute from `s`, which
+    /// holds exactly the attribute's `attribute_length` bytes.
+    fn read_attribute<'a, R: Read>(
+        s: &mut ClassFileStream<R>,
+        cp: &'a ConstantPool,
+        attribute_name_index: u16,
+        attribute_length: u32,
+        context: Option<AttributeContext>,
+    ) -> error::Result<(&'a str, Attributes)> {
+        let options = s.options();
+        let attribute_name = cp.get_utf8_constant(attribute_name_index as usize)?;
+        // checked before reading the attribute, so a nested `Code` is never recursed into
+        if let Some(context) = context {
+            if !context.allows(attribute_name) {
+                return Err(match context {
+                    AttributeContext::Code => ClassFileError::IllegalNestedAttribute(attribute_name.to_string()),
+                    context => ClassFileError::AttributeInWrongContext(attribute_name.to_string(), context),
+                });
+            }
+        }
+
+        let a = match attribute_name {
+            ConstantValue => Ok(Attributes::ConstantValue {
+                constantvalue_index: s.read_u2()?,
+            }),
+            Code => {
+                let max_stack = s.read_u2()?;
+                let max_locals = s.read_u2()?;
+                let code_length = s.read_u4()?;
+                if code_length > options.code_length_limit() {
+                    return Err(ClassFileError::CodeTooLong(code_length));
+                }
+                let code = s.read_sequence::<u8>(Some(cp), code_length as usize)?;
+                let exception_table_length = s.read_u2()?;
+                let exception_table = s.read_sequence::<ExceptionTableEntry>(
+                    Some(cp),
+                    exception_table_length as usize,
+                )?;
+                let attributes = AttributesCollection::read_in_context(s, Some(cp), AttributeContext::Code)?;
+                for attribute in attributes.get(LineNumberTable) {
+                    if let Attributes::LineNumberTable { line_number_table } = attribute {
+                        for entry in line_number_table {
+                            if entry.start_pc as u32 >= code_length {
+                                s.diagnose(ParseDiagnostic::LineNumberOutOfRange {
+                                    start_pc: entry.start_pc,
+                                    code_length,
+                                })?;
+                            }
+                        }
+                    }
+                }
+
+                let raw_code = code;
+                let code = InstructionList::read_from_stream(
+                    &mut ClassFileStream::with_options(&mut Cursor::new(&raw_code), options),
+                    Some(cp),
+                )?;
+                Ok(Attributes::Code(CodeAttribute {
+                    max_stack,
+                    max_locals,
+                    code,
+                    raw_code,
+                    exception_table,
+                    attributes,
+                }))
+            }
+            StackMapTable => {
+                let number_of_entries = s.read_u2()?;
+                let entries = s.read_sequence(Some(cp), number_of_entries as usize)?;
+                Ok(Attributes::StackMapTable { entries })
+            }
+            Exceptions => {
+                let number_of_exceptions = s.read_u2()?;
+                let exception_index_table =
+                    s.read_sequence(Some(cp), number_of_exceptions as usize)?;
+                Ok(Attributes::Exceptions {
+                    exception_index_table,
+                })
+            }
+            InnerClasses => {
+                let number_of_classes = s.read_u2()?;
+                Ok(Attributes::InnerClasses {
+                    classes: s.read_sequence(Some(cp), number_of_classes as usize)?,
+                })
+            }
+            EnclosingMethod => Ok(Attributes::EnclosingMethod {
+                class_index: s.read_u2()?,
+                method_index: s.read_u2()?,
+            }),
+            Synthetic => Ok(Attributes::Synthetic),
+            Signature => Ok(Attributes::Signature {
+                signature_index: s.read_u2()?,
+            }),
+            SourceFile => Ok(Attributes::SourceFile {
+                sourcefile_index: s.read_u2()?,
+            }),
+            SourceDebugExtension => {
+                let bytes = s.read_dynamic(attribute_length as usize)?;
+                Ok(Attributes::SourceDebugExtension {
+                    debug_extension: bytes,
+                })
+            }
+            LineNumberTable => {
+                let line_number_table_length = s.read_u2()?;
+                Ok(Attributes::LineNumberTable {
+                    line_number_table: s
+                        .read_sequence(Some(cp), line_number_table_length as usize)?,
+                })
+            }
+            LocalVariableTable => {
+                let local_variable_table_length = s.read_u2()?;
+                Ok(Attributes::LocalVariableTable {
+                    local_variable_table: s
+                        .read_sequence(Some(cp), local_variable_table_length as usize)?,
+                })
+            }
+            LocalVariableTypeTable => {
+                let local_variable_type_table_length = s.read_u2()?;
+                Ok(Attributes::LocalVariableTypeTable {
+                    local_variable_type_table: s
+                        .read_sequence(Some(cp), local_variable_type_table_length as usize)?,
+                })
+            }
+            Deprecated => Ok(Attributes::Deprecated),
+            RuntimeVisibleAnnotations => {
+                let num_annotations = s.read_u2()?;
+                Ok(Attributes::RuntimeVisibleAnnotations {
+                    annotations: s.read_sequence(Some(cp), num_annotations as usize)?,
+                })
+            }
+            RuntimeInvisibleAnnotations => {
+                let num_annotations = s.read_u2()?;
+                Ok(Attributes::RuntimeInvisibleAnnotations {
+                    annotations: s.read_sequence(Some(cp), num_annotations as usize)?,
+                })
+            }
+            RuntimeVisibleParameterAnnotations => {
+                let num_parameters = s.read_u1()?;
+                Ok(Attributes::RuntimeVisibleParameterAnnotations {
+                    parameter_annotations: s.read_sequence(Some(cp), num_parameters as usize)?,
+                })
+            }
+            RuntimeInvisibleParameterAnnotations => {
+                let num_parameters = s.read_u1()?;
+                Ok(Attributes::RuntimeInvisibleParameterAnnotations {
+                    parameter_annotations: s.read_sequence(Some(cp), num_parameters as usize)?,
+                })
+            }
+            RuntimeVisibleTypeAnnotations => {
+                let num_annotations = s.read_u2()?;
+                let annotations = s.read_sequence(Some(cp), num_annotations as usize)?;
+                check_target_types(&annotations, context)?;
+                Ok(Attributes::RuntimeVisibleTypeAnnotations { annotations })
+            }
+            RuntimeInvisibleTypeAnnotations => {
+                let num_annotations = s.read_u2()?;
+                let annotations = s.read_sequence(Some(cp), num_annotations as usize)?;
+                check_target_types(&annotations, context)?;
+                Ok(Attributes::RuntimeInvisibleTypeAnnotations { annotations })
+            }
+            AnnotationDefault => Ok(Attributes::AnnotationDefault {
+                default_value: ElementValue::read_from_stream(s, Some(cp))?,
+            }),
+            BootstrapMethods => {
+                let num_bootstrap_methods = s.read_u2()?;
+                Ok(Attributes::BootstrapMethods {
+                    bootstrap_methods: s.read_sequence(Some(cp), num_bootstrap_methods as usize)?,
+                })
+            }
+            MethodParameters => {
+                let parameters_count = s.read_u1()?;
+                Ok(Attributes::MethodParameters {
+                    parameters: s.read_sequence(Some(cp), parameters_count as usize)?,
+                })
+            }
+            Record => {
+                let components_count = s.read_u2()?;
+                Ok(Attributes::Record {
+                    components: s.read_sequence(Some(cp), components_count as usize)?,
+                })
+            }
+            v if options.tolerant => Ok(Attributes::Unknown {
+                name: v.to_string(),
+                data: s.read_dynamic(attribute_length as usize)?,
+            }),
+            v => Err(ClassFileError::UnknownAttribute(v.to_string())),
+        }?;
+        Ok((attribute_name, a))
+    }
+}
+
+/// Check that each type annotation may appear in `context`, if it is known.
+fn check_target_types(annotations: &[TypeAnnotation], context: Option<AttributeContext>) -> error::Result<()> {
+    if let Some(context) = context {
+        if let Some(v) = annotations.iter().find(|v| !context.allows_target_type(v.target_type)) {
+            return Err(ClassFileError::TypeAnnotationWrongContext(v.target_type, context));
+        }
+    }
+    Ok(())
+}
+
+/// Reads an attribute table without checking which attributes may appear in it.
+impl ClassFileItem for AttributesCollection {
+    fn read_from_stream<R: Read>(s: &mut ClassFileStream<R>, cp: Option<&ConstantPool>) -> error::Result<Self>
+    where
+        Self: Sized {
+        Self::read(s, cp, None)
+    }
 }
 
 // impl ClassFileItem for Attributes {
@@ -1005,7 +1312,7 @@ impl ClassFileItem for AttributesCollection {
 //         cp: Option<&ConstantPool>,
 //     ) -> error::Result<Self>
 //     where
-//         Self: std::marker::Sized,
+//         Self: core::marker::Sized,
 //     {
 //         let cp = cp.expect("constant pool should exist at the time of attribute deserialization");
 //         let attribute_name_index = s.read_u2()?;
@@ -1160,6 +1467,64 @@ impl ClassFileItem for AttributesCollection {
 //     }
 // }
 
+/// A record component, an entry in the `components` table of the `Record` attribute.
+#[derive(Debug)]
+pub struct RecordComponentInfo {
+    /// The value of the name_index item must be a valid
+    /// index into the constant_pool table. The constant_pool
+    /// entry at that index must be a CONSTANT_Utf8_info structure
+    /// representing a valid unqualified name denoting
+    /// the record component (§4.2.2).
+    pub name_index: u16,
+    /// The value of the descriptor_index item must be a valid
+    /// index into the constant_pool table. The constant_pool
+    /// entry at that index must be a CONSTANT_Utf8_info structure
+    /// representing a field descriptor which encodes the
+    /// type of the record component (§4.3.2).
+    pub descriptor_index: u16,
+    pub attributes: AttributesCollection,
+}
+
+impl RecordComponentInfo {
+    /// Parse this component's `Signature` attribute, returning `None`
+    /// if the component has no generic signature.
+    pub fn generic_signature(&self, cp: &ConstantPool) -> Option<error::Result<FieldSignature>> {
+        let index = self.attributes.signature_index()?;
+        Some(cp.get_utf8_constant(index as usize).and_then(|v| {
+            FieldSignature::new(v).ok_or(ClassFileError::MalformedSignature(index))
+        }))
+    }
+
+    /// The annotations of this component's `RuntimeVisibleAnnotations`
+    /// attribute, paired with their resolved type descriptors.
+    pub fn visible_annotations<'a>(&'a self, cp: &'a ConstantPool) -> error::Result<Vec<(&'a str, &'a Annotation)>> {
+        self.attributes
+            .get(RuntimeVisibleAnnotations)
+            .flat_map(|v| match v {
+                Attributes::RuntimeVisibleAnnotations { annotations } => annotations.as_slice(),
+                _ => &[],
+            })
+            .map(|v| Ok((cp.get_utf8_constant(v.type_index as usize)?, v)))
+            .collect()
+    }
+}
+
+impl ClassFileItem for RecordComponentInfo {
+    fn read_from_stream<R: Read>(
+        s: &mut ClassFileStream<R>,
+        cp: Option<&ConstantPool>,
+    ) -> error::Result<Self>
+    where
+        Self: core::marker::Sized,
+    {
+        Ok(Self {
+            name_index: s.read_u2()?,
+            descriptor_index: s.read_u2()?,
+            attributes: AttributesCollection::read_in_context(s, cp, AttributeContext::RecordComponent)?,
+        })
+    }
+}
+
 /// Method parameters element.
 #[derive(Debug)]
 pub struct MethodParametersElement {
@@ -1185,7 +1550,7 @@ impl ClassFileItem for MethodParametersElement {
         cp: Option<&ConstantPool>,
     ) -> error::Result<Self>
     where
-        Self: std::marker::Sized,
+        Self: core::marker::Sized,
     {
         let name_index = s.read_u2()?;
         let access_flags = s.read_u2()?;
@@ -1251,7 +1616,7 @@ impl ClassFileItem for BootstrapMethodsElement {
         cp: Option<&ConstantPool>,
     ) -> error::Result<Self>
     where
-        Self: std::marker::Sized,
+        Self: core::marker::Sized,
     {
         let bootstrap_method_ref = s.read_u2()?;
         let num_bootstrap_arguments = s.read_u2()?;
@@ -1269,6 +1634,9 @@ impl ClassFileItem for BootstrapMethodsElement {
 /// single run-time visible annotation on a type
 /// used in a declaration or expression.
 pub struct TypeAnnotation {
+    /// The value of the target_type item denotes the
+    /// kind of target on which the annotation appears.
+    pub target_type: u8,
     /// The value of the target_info item denotes
     /// precisely which type in a declaration
     /// or expression is annotated.
@@ -1293,13 +1661,15 @@ impl ClassFileItem for TypeAnnotation {
         cp: Option<&ConstantPool>,
     ) -> error::Result<Self>
     where
-        Self: std::marker::Sized,
+        Self: core::marker::Sized,
     {
-        let target_info = TargetInfoType::read_from_stream(s, cp)?;
+        let target_type = s.read_u1()?;
+        let target_info = TargetInfoType::read_body(s, cp, target_type)?;
         let target_path = TypePath::read_from_stream(s, cp)?;
         let type_index = s.read_u2()?;
         let num_element_value_pairs = s.read_u2()?;
         Ok(Self {
+            target_type,
             target_info,
             target_path,
             type_index,
@@ -1308,7 +1678,7 @@ impl ClassFileItem for TypeAnnotation {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 /// Type path.
 /// If the value of path_length is 0, then the
 /// annotation appears directly on the type itself.
@@ -1332,16 +1702,16 @@ impl ClassFileItem for TypePath {
         cp: Option<&ConstantPool>,
     ) -> error::Result<Self>
     where
-        Self: std::marker::Sized,
+        Self: core::marker::Sized,
     {
-        let path_length = s.read_u2()?;
+        let path_length = s.read_u1()?;
         Ok(Self {
             path: s.read_sequence(cp, path_length as usize)?,
         })
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 /// Type path entry.
 pub struct TypePathEntry {
     pub type_path_kind: TypePathKind,
@@ -1361,7 +1731,7 @@ impl ClassFileItem for TypePathEntry {
         cp: Option<&ConstantPool>,
     ) -> error::Result<Self>
     where
-        Self: std::marker::Sized,
+        Self: core::marker::Sized,
     {
         Ok(Self {
             type_path_kind: TypePathKind::from_u8(s.read_u1()?)?,
@@ -1379,7 +1749,7 @@ mod typepathkinds {
     pub const ANNOTATION_BOUND_WILDCARD_PARAMETERIZED_TYPE: u8 = 2;
     pub const ANNOTATION_TYPEARG_PARAMETERIZED_TYPE: u8 = 3;
 
-    #[derive(Debug)]
+    #[derive(Debug, PartialEq, Eq)]
     /// Type path kind.
     pub enum TypePathKind {
         /// Annotation is deeper in an array type
@@ -1410,7 +1780,7 @@ mod typepathkinds {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 /// Target info type.
 pub enum TargetInfoType {
     /// The type_parameter_target item indicates that an
@@ -1557,9 +1927,21 @@ impl ClassFileItem for TargetInfoType {
         cp: Option<&ConstantPool>,
     ) -> error::Result<Self>
     where
-        Self: std::marker::Sized,
+        Self: core::marker::Sized,
     {
-        match s.read_u1()? {
+        let target_type = s.read_u1()?;
+        Self::read_body(s, cp, target_type)
+    }
+}
+
+impl TargetInfoType {
+    /// Read the target_info item for a target_type which has already been read.
+    fn read_body<R: Read>(
+        s: &mut ClassFileStream<R>,
+        cp: Option<&ConstantPool>,
+        target_type: u8,
+    ) -> error::Result<Self> {
+        match target_type {
             0x00 | 0x01 => Ok(Self::TypeParameterTarget {
                 type_parameter_index: s.read_u1()?,
             }),
@@ -1598,7 +1980,7 @@ impl ClassFileItem for TargetInfoType {
     }
 }
 
-#[derive(Debug)]
+#[derive(Debug, PartialEq, Eq)]
 /// LocalVarTarget table entry.
 pub struct LocalVarTargetTableEntry {
     /// The given local variable has a value at indices into
@@ -1620,7 +2002,7 @@ impl ClassFileItem for LocalVarTargetTableEntry {
         cp: Option<&ConstantPool>,
     ) -> error::Result<Self>
     where
-        Self: std::marker::Sized,
+        Self: core::marker::Sized,
     {
         Ok(Self {
             pc_range: (s.read_u2()?..s.read_u2()?),
@@ -1645,7 +2027,7 @@ impl ClassFileItem for ParameterAnnotation {
         cp: Option<&ConstantPool>,
     ) -> error::Result<Self>
     where
-        Self: std::marker::Sized,
+        Self: core::marker::Sized,
     {
         let num_annotations = s.read_u2()?;
         Ok(Self {
@@ -1661,20 +2043,99 @@ pub struct Annotation {
     pub element_value_pairs: Vec<ElementValuePairElement>,
 }
 
+impl Annotation {
+    /// Walk this annotation and every annotation nested within it, including
+    /// those inside array values, depth-first in declaration order.
+    pub fn walk(&self) -> impl Iterator<Item = &Annotation> {
+        AnnotationWalk {
+            stack: vec![AnnotationWalkNode::Annotation(self)],
+        }
+    }
+}
+
+enum AnnotationWalkNode<'a> {
+    Annotation(&'a Annotation),
+    Value(&'a ElementValue),
+}
+
+/// Iterator returned by [`Annotation::walk`].
+struct AnnotationWalk<'a> {
+    stack: Vec<AnnotationWalkNode<'a>>,
+}
+
+impl<'a> Iterator for AnnotationWalk<'a> {
+    type Item = &'a Annotation;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while let Some(node) = self.stack.pop() {
+            match node {
+                AnnotationWalkNode::Annotation(annotation) => {
+                    self.stack.extend(
+                        annotation
+                            .element_value_pairs
+                            .iter()
+                            .rev()
+                            .map(|pair| AnnotationWalkNode::Value(&pair.value)),
+                    );
+                    return Some(annotation);
+                }
+                AnnotationWalkNode::Value(ElementValue::AnnotationValue { annotation_value }) => {
+                    self.stack.push(AnnotationWalkNode::Annotation(annotation_value));
+                }
+                AnnotationWalkNode::Value(ElementValue::ArrayValue { values }) => {
+                    self.stack.extend(values.iter().rev().map(AnnotationWalkNode::Value));
+                }
+                AnnotationWalkNode::Value(_) => (),
+            }
+        }
+        None
+    }
+}
+
+impl Annotation {
+    /// Add the indices of this annotation's type and of the descriptors in its values to `out`.
+    fn descriptor_indices(&self, out: &mut Vec<u16>) {
+        out.push(self.type_index);
+        self.element_value_pairs.iter().for_each(|v| v.value.descriptor_indices(out));
+    }
+
+    /// Read an annotation nested `depth` element values deep.
+    fn read_nested<R: Read>(s: &mut ClassFileStream<R>, cp: Option<&ConstantPool>, depth: u32) -> error::Result<Self> {
+        let type_index = s.read_u2()?;
+        let length = s.read_u2()?;
+        let mut element_value_pairs = Vec::new();
+        for _ in 0..length {
+            element_value_pairs.push(ElementValuePairElement {
+                element_name_index: s.read_u2()?,
+                value: ElementValue::read_nested(s, cp, depth)?,
+            });
+        }
+        Ok(Self { type_index, element_value_pairs })
+    }
+}
+
 impl ClassFileItem for Annotation {
     fn read_from_stream<R: Read>(
         s: &mut ClassFileStream<R>,
         cp: Option<&ConstantPool>,
     ) -> error::Result<Self>
     where
-        Self: std::marker::Sized,
+        Self: core::marker::Sized,
     {
-        let type_index = s.read_u2()?;
-        let num_element_value_pairs = s.read_u2()?;
-        Ok(Self {
-            type_index,
-            element_value_pairs: s.read_sequence(cp, num_element_value_pairs as usize)?,
-        })
+        Self::read_nested(s, cp, 0)
+    }
+}
+
+impl ElementValue {
+    /// Add the indices of the enum types, class literals and annotation types in this value to `out`.
+    fn descriptor_indices(&self, out: &mut Vec<u16>) {
+        match self {
+            Self::Const { .. } => (),
+            Self::EnumConstValue { type_name_index, .. } => out.push(*type_name_index),
+            Self::ClassInfoIndex { class_info_index } => out.push(*class_info_index),
+            Self::AnnotationValue { annotation_value } => annotation_value.descriptor_indices(out),
+            Self::ArrayValue { values } => values.iter().for_each(|v| v.descriptor_indices(out)),
+        }
     }
 }
 
@@ -1702,7 +2163,7 @@ impl ClassFileItem for ElementValuePairElement {
         cp: Option<&ConstantPool>,
     ) -> error::Result<Self>
     where
-        Self: std::marker::Sized,
+        Self: core::marker::Sized,
     {
         Ok(Self {
             element_name_index: s.read_u2()?,
@@ -1713,7 +2174,8 @@ impl ClassFileItem for ElementValuePairElement {
 
 /// Element value types.
 mod elementvaluetypes {
-    use std::io::Read;
+    use alloc::vec::Vec;
+    use crate::io::Read;
 
     use crate::{
         error::{self, ClassFileError},
@@ -1738,6 +2200,7 @@ mod elementvaluetypes {
     pub const ARRAY_TYPE: char = '[';
 
     /// Possible element value types.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub enum ElementValueType {
         Byte,
         Char,
@@ -1779,7 +2242,11 @@ mod elementvaluetypes {
     /// Represents the value of an element-value pair.
     #[derive(Debug)]
     pub enum ElementValue {
-        ConstValueIndex {
+        /// A primitive constant or a String literal.
+        Const {
+            /// The tag of this element_value, determining how
+            /// the constant is to be interpreted.
+            tag: ElementValueType,
             /// The const_value_index item denotes either
             /// a primitive constant value or a String
             /// literal as the value of this
@@ -1789,7 +2256,7 @@ mod elementvaluetypes {
             /// be a valid index into the constant_pool table.
             /// The constant_pool entry at that index must be
             /// of a type appropriate to the tag item.
-            const_value_index: u16,
+            index: u16,
         },
         /// The enum_const_value item denotes an
         /// enum constant as the value of
@@ -1850,6 +2317,51 @@ mod elementvaluetypes {
             values: Vec<ElementValue>,
         },
     }
+    /// A resolved primitive constant or String literal of an element_value.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub enum ElementConstant<'a> {
+        Byte(i8),
+        Char(char),
+        Double(f64),
+        Float(f32),
+        Int(i32),
+        Long(i64),
+        Short(i16),
+        Boolean(bool),
+        String(&'a str),
+    }
+
+    impl ElementValue {
+        /// Resolve a `Const` element value against the constant pool.
+        ///
+        /// `CONSTANT_Integer` entries are narrowed according to the tag.
+        /// Returns `None` if this is not a `Const` element value.
+        pub fn resolve_constant<'a>(
+            &self,
+            cp: &'a ConstantPool,
+        ) -> error::Result<Option<ElementConstant<'a>>> {
+            let (tag, index) = match self {
+                Self::Const { tag, index } => (*tag, *index as usize),
+                _ => return Ok(None),
+            };
+            Ok(Some(match tag {
+                ElementValueType::Byte => ElementConstant::Byte(cp.get_int(index)? as i8),
+                ElementValueType::Char => ElementConstant::Char(
+                    char::from_u32(cp.get_int(index)? as u16 as u32)
+                        .ok_or(ClassFileError::UnexpectedConstantType(index))?,
+                ),
+                ElementValueType::Double => ElementConstant::Double(cp.get_double(index)?),
+                ElementValueType::Float => ElementConstant::Float(cp.get_float(index)?),
+                ElementValueType::Int => ElementConstant::Int(cp.get_int(index)?),
+                ElementValueType::Long => ElementConstant::Long(cp.get_long(index)?),
+                ElementValueType::Short => ElementConstant::Short(cp.get_int(index)? as i16),
+                ElementValueType::Boolean => ElementConstant::Boolean(cp.get_int(index)? != 0),
+                ElementValueType::String => ElementConstant::String(cp.get_utf8_constant(index)?),
+                _ => return Ok(None),
+            }))
+        }
+    }
+
     impl ClassFileItem for ElementValue {
         fn read_from_stream<R: Read>(
             s: &mut ClassFileStream<R>,
@@ -1858,33 +2370,26 @@ mod elementvaluetypes {
         where
             Self: Sized,
         {
-            match ElementValueType::from_char(s.read_u1()? as char)? {
-                ElementValueType::Byte => Ok(Self::ConstValueIndex {
-                    const_value_index: s.read_u2()?,
-                }),
-                ElementValueType::Char => Ok(Self::ConstValueIndex {
-                    const_value_index: s.read_u2()?,
-                }),
-                ElementValueType::Double => Ok(Self::ConstValueIndex {
-                    const_value_index: s.read_u2()?,
-                }),
-                ElementValueType::Float => Ok(Self::ConstValueIndex {
-                    const_value_index: s.read_u2()?,
-                }),
-                ElementValueType::Int => Ok(Self::ConstValueIndex {
-                    const_value_index: s.read_u2()?,
-                }),
-                ElementValueType::Long => Ok(Self::ConstValueIndex {
-                    const_value_index: s.read_u2()?,
-                }),
-                ElementValueType::Short => Ok(Self::ConstValueIndex {
-                    const_value_index: s.read_u2()?,
-                }),
-                ElementValueType::Boolean => Ok(Self::ConstValueIndex {
-                    const_value_index: s.read_u2()?,
-                }),
-                ElementValueType::String => Ok(Self::ConstValueIndex {
-                    const_value_index: s.read_u2()?,
+            Self::read_nested(s, cp, 0)
+        }
+    }
+
+    impl ElementValue {
+        /// Read an element value inside `depth` enclosing annotations or arrays.
+        pub(super) fn read_nested<R: Read>(s: &mut ClassFileStream<R>, cp: Option<&ConstantPool>, depth: u32) -> error::Result<Self> {
+            let tag = ElementValueType::from_char(s.read_u1()? as char)?;
+            match tag {
+                ElementValueType::Byte
+                | ElementValueType::Char
+                | ElementValueType::Double
+                | ElementValueType::Float
+                | ElementValueType::Int
+                | ElementValueType::Long
+                | ElementValueType::Short
+                | ElementValueType::Boolean
+                | ElementValueType::String => Ok(Self::Const {
+                    tag,
+                    index: s.read_u2()?,
                 }),
                 ElementValueType::Enum => Ok(Self::EnumConstValue {
                     type_name_index: s.read_u2()?,
@@ -1893,14 +2398,19 @@ mod elementvaluetypes {
                 ElementValueType::Class => Ok(Self::ClassInfoIndex {
                     class_info_index: s.read_u2()?,
                 }),
+                ElementValueType::Annotation | ElementValueType::Array if depth >= s.options().annotation_depth_limit() => {
+                    Err(ClassFileError::RecursionLimitExceeded)
+                }
                 ElementValueType::Annotation => Ok(Self::AnnotationValue {
-                    annotation_value: Annotation::read_from_stream(s, cp)?,
+                    annotation_value: Annotation::read_nested(s, cp, depth + 1)?,
                 }),
                 ElementValueType::Array => {
-                    let num_values = s.read_u2()?;
-                    Ok(Self::ArrayValue {
-                        values: s.read_sequence(cp, num_values as usize)?,
-                    })
+                    let length = s.read_u2()?;
+                    let mut values = Vec::new();
+                    for _ in 0..length {
+                        values.push(Self::read_nested(s, cp, depth + 1)?);
+                    }
+                    Ok(Self::ArrayValue { values })
                 }
             }
         }
@@ -1953,7 +2463,7 @@ impl ClassFileItem for LocalVariableTypeTableEntry {
         cp: Option<&ConstantPool>,
     ) -> error::Result<Self>
     where
-        Self: std::marker::Sized,
+        Self: core::marker::Sized,
     {
         Ok(Self {
             pc_range: (s.read_u2()?..s.read_u2()?),
@@ -2009,7 +2519,7 @@ impl ClassFileItem for LocalVariableTableEntry {
         cp: Option<&ConstantPool>,
     ) -> error::Result<Self>
     where
-        Self: std::marker::Sized,
+        Self: core::marker::Sized,
     {
         Ok(Self {
             pc_range: (s.read_u2()?..s.read_u2()?),
@@ -2042,7 +2552,7 @@ impl ClassFileItem for LineNumberTableEntry {
         cp: Option<&ConstantPool>,
     ) -> error::Result<Self>
     where
-        Self: std::marker::Sized,
+        Self: core::marker::Sized,
     {
         Ok(Self {
             start_pc: s.read_u2()?,
@@ -2106,7 +2616,7 @@ impl ClassFileItem for ClassArrayEntry {
         cp: Option<&ConstantPool>,
     ) -> error::Result<Self>
     where
-        Self: std::marker::Sized,
+        Self: core::marker::Sized,
     {
         Ok(Self {
             inner_class_info_index: s.read_u2()?,
@@ -2118,6 +2628,95 @@ impl ClassFileItem for ClassArrayEntry {
     }
 }
 
+/// The payload of a `Code` attribute: the instructions of a method
+/// and the information needed to execute them.
+#[derive(Debug)]
+pub struct CodeAttribute {
+    /// The value of the max_stack item gives the maximum
+    /// depth of the operand stack of this method
+    /// at any point during execution of the method.
+    pub max_stack: u16,
+    /// The value of the max_locals item gives the number of
+    /// local variables in the local variable array allocated
+    /// upon invocation of this method (§2.6.1), including the
+    /// local variables used to pass parameters to the method
+    /// on its invocation.
+    ///
+    /// The greatest local variable index for a value of type
+    /// long or double is max_locals - 2. The greatest local
+    /// variable index for a value of any other type
+    /// is max_locals - 1.
+    pub max_locals: u16,
+    /**
+    The code array gives the actual bytes of Java Virtual Machine code
+    that implement the method.
+
+    When the code array is read into memory on a byte-addressable machine,
+    if the first byte of the array is aligned on a 4-byte boundary, the
+    tableswitch and lookupswitch 32-bit offsets will be 4-byte aligned.
+    (Refer to the descriptions of those instructions for more
+    information on the consequences of code array alignment.)
+    **/
+    pub code: InstructionList,
+    /// The undecoded bytes of the code array.
+    pub raw_code: Vec<u8>,
+    /// Each entry in the exception_table array describes one
+    /// exception handler in the code array. The order of the
+    /// handlers in the exception_table array is significant.
+    pub exception_table: Vec<ExceptionTableEntry>,
+    /// Each value of the attributes table must be an attribute_info structure (§4.7).
+    /// A Code attribute can have any number of optional attributes associated with it.
+    pub attributes: AttributesCollection,
+}
+
+impl CodeAttribute {
+    /// Build a control-flow graph of this code, including the edges
+    /// to its exception handlers.
+    pub fn build_cfg(&self) -> core::result::Result<Cfg, CodeVerificationError> {
+        self.code.build_cfg(&self.exception_table)
+    }
+
+    /// Statically verify this code against the class file it belongs to.
+    pub fn static_verify(&self, file: &ClassFile) -> core::result::Result<(), CodeVerificationError> {
+        self.code.static_verify(file, self.max_locals as usize)
+    }
+
+    /// Check that the locals of every frame in this code's `StackMapTable`
+    /// fit in `max_locals`, starting from `initial_locals`, the method's
+    /// [initial_stack_frame]. A `chop_frame` can't remove more locals than
+    /// the previous frame has.
+    ///
+    /// Fails with `BadStackMapFrame` holding the index of the first bad frame.
+    pub fn verify_stack_map_locals(&self, initial_locals: &[VerificationTypeInfo]) -> core::result::Result<(), CodeVerificationError> {
+        let slots = |locals: &[VerificationTypeInfo]| {
+            locals
+                .iter()
+                .map(|v| if matches!(v, VerificationTypeInfo::Long | VerificationTypeInfo::Double) { 2 } else { 1 })
+                .sum::<usize>()
+        };
+        let mut locals = initial_locals.to_vec();
+        let frames = self.attributes.get(attrtype::StackMapTable).flat_map(|v| match v {
+            Attributes::StackMapTable { entries } => entries.as_slice(),
+            _ => &[],
+        });
+        for (index, frame) in frames.enumerate() {
+            match frame {
+                StackMapFrame::ChopFrame { k, .. } => {
+                    let len = locals.len().checked_sub(*k as usize).ok_or(CodeVerificationError::BadStackMapFrame(index))?;
+                    locals.truncate(len);
+                }
+                StackMapFrame::AppendFrame { locals: appended, .. } => locals.extend(appended.iter().copied()),
+                StackMapFrame::FullFrame { locals: full, .. } => locals = full.clone(),
+                _ => continue,
+            }
+            if slots(&locals) > self.max_locals as usize {
+                return Err(CodeVerificationError::BadStackMapFrame(index));
+            }
+        }
+        Ok(())
+    }
+}
+
 /// An entry in the exception table.
 #[derive(Debug)]
 pub struct ExceptionTableEntry {
@@ -2170,3 +2769,340 @@ impl ClassFileItem for ExceptionTableEntry {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec, vec::Vec};
+
+    use crate::{
+        error::ClassFileError,
+        io::Cursor,
+        item::{
+            constant_pool::{ConstantPool, ConstantPoolEntry},
+            file::ClassFile,
+            ClassFileItem,
+        },
+        stream::{ClassFileStream, ParseOptions},
+    };
+    use exo_parser::Lexer;
+
+    use super::{
+        attrtype, initial_stack_frame, Annotation, AttributeContext, Attributes, AttributesCollection, CodeAttribute, ElementConstant, ElementValue,
+        ElementValuePairElement, ElementValueType, VerificationTypeInfo,
+        stackmap::StackMapFrame,
+    };
+    use crate::item::ids::{field::FieldType, method::MethodDescriptor, signature::ReferenceTypeSignature};
+    use crate::item::opcodes::{CodeVerificationError, InstructionList};
+
+    #[test]
+    fn type_annotation_context_test() {
+        let cp = ConstantPool {
+            entries: vec![
+                ConstantPoolEntry::utf8("RuntimeVisibleTypeAnnotations".to_string()),
+                ConstantPoolEntry::utf8("LNonNull;".to_string()),
+            ],
+        };
+        let read = |data: &[u8], context| {
+            AttributesCollection::read_in_context(&mut ClassFileStream::new(&mut Cursor::new(data)), Some(&cp), context)
+        };
+
+        // an empty_target (field type) annotation
+        let field = [0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x08, 0x00, 0x01, 0x13, 0x00, 0x00, 0x02, 0x00, 0x00];
+        assert!(read(&field, AttributeContext::Field).is_ok());
+        assert!(matches!(
+            read(&field, AttributeContext::ClassFile),
+            Err(ClassFileError::TypeAnnotationWrongContext(0x13, AttributeContext::ClassFile))
+        ));
+
+        // a supertype_target annotation on the superclass
+        let supertype = [0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x0a, 0x00, 0x01, 0x10, 0xff, 0xff, 0x00, 0x00, 0x02, 0x00, 0x00];
+        assert!(read(&supertype, AttributeContext::ClassFile).is_ok());
+        assert!(matches!(
+            read(&supertype, AttributeContext::Field),
+            Err(ClassFileError::TypeAnnotationWrongContext(0x10, AttributeContext::Field))
+        ));
+    }
+
+    #[test]
+    fn record_component_test() {
+        let utf8 = |v: &str| ConstantPoolEntry::utf8(v.to_string());
+        let cp = ConstantPool {
+            entries: vec![
+                utf8("Record"),
+                utf8("values"),
+                utf8("Ljava/util/List<Ljava/lang/String;>;"),
+                utf8("Signature"),
+                utf8("RuntimeVisibleAnnotations"),
+                utf8("Ljava/lang/Deprecated;"),
+                utf8("Ljava/util/List;"),
+            ],
+        };
+        // one component, with a Signature and a single marker annotation
+        let data = [
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x1c, 0x00, 0x01,
+            0x00, 0x02, 0x00, 0x07, 0x00, 0x02,
+            0x00, 0x04, 0x00, 0x00, 0x00, 0x02, 0x00, 0x03,
+            0x00, 0x05, 0x00, 0x00, 0x00, 0x06, 0x00, 0x01, 0x00, 0x06, 0x00, 0x00,
+        ];
+        let attributes = AttributesCollection::read_in_context(
+            &mut ClassFileStream::new(&mut Cursor::new(data)),
+            Some(&cp),
+            AttributeContext::ClassFile,
+        )
+        .unwrap();
+        let [Attributes::Record { components }] = attributes.get(attrtype::Record).collect::<Vec<_>>()[..] else {
+            panic!("expected a single Record attribute");
+        };
+        let component = &components[0];
+        assert_eq!(cp.get_utf8_constant(component.name_index as usize).unwrap(), "values");
+        let signature = component.generic_signature(&cp).unwrap().unwrap();
+        let ReferenceTypeSignature::Class(class) = signature.0 else {
+            panic!("expected a class type signature");
+        };
+        assert_eq!(class.class.name, "List");
+        assert_eq!(class.class.type_arguments.len(), 1);
+        let annotations = component.visible_annotations(&cp).unwrap();
+        assert_eq!(annotations.len(), 1);
+        assert_eq!(annotations[0].0, "Ljava/lang/Deprecated;");
+
+        assert!(AttributeContext::RecordComponent.allows(attrtype::Signature));
+        assert!(!AttributeContext::RecordComponent.allows(attrtype::Synthetic));
+        assert!(!AttributeContext::Method.allows(attrtype::Record));
+    }
+
+    #[test]
+    fn element_value_const_test() {
+        let utf8 = |s: &str| ConstantPoolEntry::utf8(s.to_string());
+        let cp = ConstantPool {
+            entries: vec![
+                utf8("LAnn;"),
+                utf8("c"),
+                ConstantPoolEntry::Integer { bytes: 'A' as i32 },
+                utf8("b"),
+                ConstantPoolEntry::Integer { bytes: 1 },
+            ],
+        };
+        // @Ann(c='A', b=true)
+        let data = [0, 1, 0, 2, 0, 2, b'C', 0, 3, 0, 4, b'Z', 0, 5];
+        let annotation =
+            Annotation::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(data)), Some(&cp)).unwrap();
+
+        let values: Vec<_> = annotation
+            .element_value_pairs
+            .iter()
+            .map(|pair| pair.value.resolve_constant(&cp).unwrap().unwrap())
+            .collect();
+        assert_eq!(values, [ElementConstant::Char('A'), ElementConstant::Boolean(true)]);
+    }
+
+    #[test]
+    fn raw_code_test() {
+        let file = include_bytes!("../../../../local/Test.class");
+        let class_file =
+            ClassFile::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(file)), None).unwrap();
+
+        for method in class_file.methods.iter() {
+            for attribute in method.attributes.get(attrtype::Code) {
+                let Attributes::Code(CodeAttribute { code, raw_code, .. }) = attribute else {
+                    panic!("expected code attribute");
+                };
+                let last = code.opcodes.len() - 1;
+                assert_eq!(code.byte_to_code.len(), raw_code.len());
+                assert_eq!(code.byte_to_code[&(raw_code.len() - 1)], last);
+            }
+        }
+    }
+
+    #[test]
+    fn annotation_walk_test() {
+        let pair = |value| ElementValuePairElement { element_name_index: 0, value };
+        let nested = |type_index, element_value_pairs| ElementValue::AnnotationValue {
+            annotation_value: Annotation { type_index, element_value_pairs },
+        };
+        let annotation = Annotation {
+            type_index: 1,
+            element_value_pairs: vec![
+                pair(ElementValue::Const { tag: ElementValueType::Int, index: 9 }),
+                pair(nested(2, vec![pair(nested(3, vec![]))])),
+                pair(ElementValue::ArrayValue {
+                    values: vec![
+                        nested(4, vec![]),
+                        ElementValue::ArrayValue { values: vec![nested(5, vec![])] },
+                    ],
+                }),
+            ],
+        };
+        let order: Vec<u16> = annotation.walk().map(|a| a.type_index).collect();
+        assert_eq!(order, [1, 2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn annotation_depth_test() {
+        // an annotation whose one element is `arrays` nested arrays, the innermost empty
+        let nested_arrays = |arrays: usize| {
+            let mut data = vec![0x00, 0x01, 0x00, 0x01, 0x00, 0x01];
+            for _ in 1..arrays {
+                data.extend([b'[', 0x00, 0x01]);
+            }
+            data.extend([b'[', 0x00, 0x00]);
+            data
+        };
+        let read = |data: &[u8], options| {
+            Annotation::read_from_stream(&mut ClassFileStream::with_options(&mut Cursor::new(data), options), None)
+        };
+
+        assert!(read(&nested_arrays(255), ParseOptions::default()).is_ok());
+        assert!(matches!(read(&nested_arrays(256), ParseOptions::default()), Err(ClassFileError::RecursionLimitExceeded)));
+        assert!(matches!(read(&nested_arrays(100_000), ParseOptions::default()), Err(ClassFileError::RecursionLimitExceeded)));
+
+        let options = ParseOptions { max_annotation_depth: Some(2), ..ParseOptions::default() };
+        assert!(read(&nested_arrays(2), options).is_ok());
+        assert!(matches!(read(&nested_arrays(3), options), Err(ClassFileError::RecursionLimitExceeded)));
+    }
+
+    #[test]
+    fn missing_constant_pool_test() {
+        // one attribute, name index 1, length 0
+        let data = [0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00];
+        let result = AttributesCollection::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(data)), None);
+        assert!(matches!(result, Err(ClassFileError::MissingConstantPool)));
+        let empty = AttributesCollection::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new([0x00, 0x00])), None);
+        assert!(empty.unwrap().is_empty());
+    }
+
+    #[test]
+    fn name_index_test() {
+        let synthetic = ConstantPoolEntry::utf8("Synthetic".to_string());
+        let cp = ConstantPool { entries: vec![synthetic.clone(), synthetic] };
+        // two zero-length Synthetic attributes, named by entries 2 and 1
+        let data = [0x00, 0x02, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00];
+        let mut attributes =
+            AttributesCollection::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(data)), Some(&cp)).unwrap();
+        assert_eq!(attributes.get(attrtype::Synthetic).count(), 2);
+        assert_eq!(attributes.name_index(attrtype::Synthetic, 0), Some(2));
+        assert_eq!(attributes.name_index(attrtype::Synthetic, 1), Some(1));
+        assert_eq!(attributes.name_index(attrtype::Synthetic, 2), None);
+        attributes.take(attrtype::Synthetic);
+        assert_eq!(attributes.name_index(attrtype::Synthetic, 0), None);
+    }
+
+    #[test]
+    fn declaration_order_test() {
+        let utf8 = |v: &str| ConstantPoolEntry::utf8(v.to_string());
+        let cp = ConstantPool { entries: vec![utf8("Synthetic"), utf8("Deprecated"), utf8("SourceFile")] };
+        // Synthetic, Deprecated, Synthetic, SourceFile #1
+        let data = [
+            0x00, 0x04, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00, 0x00, 0x00,
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01,
+        ];
+        let mut attributes =
+            AttributesCollection::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(data)), Some(&cp)).unwrap();
+        let names = |attributes: &AttributesCollection| attributes.iter_ordered().map(|v| v.0.to_string()).collect::<Vec<_>>();
+        assert_eq!(names(&attributes), ["Synthetic", "Deprecated", "Synthetic", "SourceFile"]);
+
+        assert_eq!(attributes.take(attrtype::Deprecated).len(), 1);
+        attributes.push(attrtype::Deprecated, Attributes::Deprecated);
+        assert_eq!(names(&attributes), ["Synthetic", "Synthetic", "SourceFile", "Deprecated"]);
+        // taking an attribute keeps the name indices of the others
+        assert_eq!(attributes.name_index(attrtype::SourceFile, 0), Some(3));
+        assert_eq!(attributes.name_index(attrtype::Deprecated, 0), None);
+        assert!(attributes.take(attrtype::Signature).is_empty());
+    }
+
+    #[test]
+    fn code_too_long_test() {
+        let cp = ConstantPool { entries: vec![ConstantPoolEntry::utf8("Code".to_string())] };
+        let code_length = 70000u32;
+        let mut code = vec![0, 0, 0, 0];
+        code.extend(code_length.to_be_bytes());
+        code.resize(code.len() + code_length as usize, 0x00);
+        code.extend([0, 0, 0, 0]);
+        let mut data = vec![0x00, 0x01, 0x00, 0x01];
+        data.extend((code.len() as u32).to_be_bytes());
+        data.extend(code);
+
+        let strict = AttributesCollection::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(&data)), Some(&cp));
+        assert!(matches!(strict, Err(ClassFileError::CodeTooLong(70000))));
+
+        let options = ParseOptions { max_code_length: Some(code_length), ..ParseOptions::default() };
+        let attributes =
+            AttributesCollection::read_from_stream(&mut ClassFileStream::with_options(&mut Cursor::new(&data), options), Some(&cp))
+                .unwrap();
+        let Attributes::Code(CodeAttribute { code, .. }) = attributes.first(attrtype::Code).unwrap() else {
+            panic!("expected code attribute");
+        };
+        assert_eq!(code.opcodes.len(), 70000);
+    }
+
+    #[test]
+    fn initial_stack_frame_test() {
+        let descriptor = |s: &str| Lexer::stream(Lexer::new(), s.to_string()).token::<MethodDescriptor>().unwrap().token;
+        let class_index = |v: &FieldType| Ok(if matches!(v, FieldType::ArrayType(_)) { 20 } else { 30 });
+
+        let frame = initial_stack_frame(&descriptor("(JZLjava/lang/String;[ID)V"), false, false, 10, class_index).unwrap();
+        assert_eq!(
+            frame,
+            [
+                VerificationTypeInfo::Object { cpool_index: 10 },
+                VerificationTypeInfo::Long,
+                VerificationTypeInfo::Integer,
+                VerificationTypeInfo::Object { cpool_index: 30 },
+                VerificationTypeInfo::Object { cpool_index: 20 },
+                VerificationTypeInfo::Double,
+            ]
+        );
+
+        let frame = initial_stack_frame(&descriptor("(F)V"), false, true, 10, class_index).unwrap();
+        assert_eq!(frame, [VerificationTypeInfo::UninitializedThis, VerificationTypeInfo::Float]);
+        assert!(initial_stack_frame(&descriptor("()V"), true, false, 10, class_index).unwrap().is_empty());
+    }
+
+    #[test]
+    fn stack_map_frame_range_test() {
+        let read = |data: &[u8]| StackMapFrame::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(data)), None);
+
+        assert!(matches!(read(&[63]), Ok(StackMapFrame::SameFrame)));
+        // an int on the stack
+        assert!(matches!(read(&[64, 0x01]), Ok(StackMapFrame::SameLocals1StackItemFrame { .. })));
+        assert!(matches!(read(&[127, 0x01]), Ok(StackMapFrame::SameLocals1StackItemFrame { .. })));
+        assert!(matches!(read(&[128]), Err(ClassFileError::UnknownStackMapFrameTag(128))));
+        assert!(matches!(read(&[250, 0x00, 0x05]), Ok(StackMapFrame::ChopFrame { offset_delta: 5, k: 1 })));
+        assert!(matches!(
+            read(&[254, 0x00, 0x05, 0x01, 0x02, 0x03]),
+            Ok(StackMapFrame::AppendFrame { offset_delta: 5, locals }) if locals.len() == 3
+        ));
+    }
+    #[test]
+    fn stack_map_locals_test() {
+        let code = |max_locals: u16, entries: Vec<StackMapFrame>| CodeAttribute {
+            max_stack: 0,
+            max_locals,
+            code: InstructionList::from_opcodes(vec![]),
+            raw_code: vec![],
+            exception_table: vec![],
+            attributes: {
+                let mut attributes = AttributesCollection::new();
+                attributes.push(attrtype::StackMapTable, Attributes::StackMapTable { entries });
+                attributes
+            },
+        };
+        let verify = |code: CodeAttribute, initial: &[VerificationTypeInfo]| code.verify_stack_map_locals(initial);
+        let initial = [VerificationTypeInfo::Integer, VerificationTypeInfo::Long];
+        let append = |locals: Vec<VerificationTypeInfo>| StackMapFrame::AppendFrame { offset_delta: 0, locals };
+        let chop = |k| StackMapFrame::ChopFrame { offset_delta: 0, k };
+
+        // the long takes two locals, so three are in use before the append
+        let frames = vec![append(vec![VerificationTypeInfo::Float]), chop(2), StackMapFrame::SameFrame, chop(1)];
+        assert!(verify(code(4, frames), &initial).is_ok());
+        let frames = vec![append(vec![VerificationTypeInfo::Float, VerificationTypeInfo::Double])];
+        assert!(matches!(verify(code(5, frames), &initial), Err(CodeVerificationError::BadStackMapFrame(0))));
+
+        let frames = vec![chop(1), chop(2)];
+        assert!(matches!(verify(code(3, frames), &initial), Err(CodeVerificationError::BadStackMapFrame(1))));
+
+        // a full frame replaces the locals, so they can be chopped again
+        let full = StackMapFrame::FullFrame { offset_delta: 0, locals: vec![VerificationTypeInfo::Top; 3], stack: vec![] };
+        assert!(verify(code(3, vec![chop(2), full, chop(3)]), &initial).is_ok());
+        assert!(verify(code(0, vec![]), &[]).is_ok());
+    }
+}
@@ -1,4 +1,5 @@
-use std::io::Read;
+use alloc::{string::String, vec, vec::Vec};
+use crate::io::Read;
 
 use crate::{
     error::{self, ClassFileError},
@@ -9,7 +10,7 @@ pub use self::refkind::RefKind;
 
 use crate::item::ClassFileItem;
 
-use super::ConstantPool;
+use super::{decode_modified_utf8, encode_modified_utf8, validate_modified_utf8, ConstantPool};
 
 /// The tag values for each type of constant pool entry.
 mod tags {
@@ -42,7 +43,8 @@ mod refkind {
     pub const REF_invokeSpecial: u8 = 7;
     pub const REF_newInvokeSpecial: u8 = 8;
     pub const REF_invokeInterface: u8 = 9;
-    #[derive(Debug, Clone, Copy)]
+    /// The kind of a `CONSTANT_MethodHandle_info`, characterizing its bytecode behavior.
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
     pub enum RefKind {
         REF_getField = REF_getField as isize,
         REF_getStatic = REF_getStatic as isize,
@@ -56,6 +58,7 @@ mod refkind {
     }
 
     impl RefKind {
+        /// Decode a reference kind from its byte value, which must be in the range 1 to 9.
         pub fn decode(v: u8) -> error::Result<Self> {
             match v {
                 REF_getField => Ok(Self::REF_getField),
@@ -70,6 +73,11 @@ mod refkind {
                 _ => Err(ClassFileError::UnknownReferenceKind(v)),
             }
         }
+
+        /// The byte value of this reference kind.
+        pub fn value(&self) -> u8 {
+            *self as u8
+        }
     }
 }
 
@@ -180,7 +188,13 @@ pub enum ConstantPoolEntry {
         descriptor_index: u16,
     },
     /// The CONSTANT_Utf8_info structure is used to represent constant string values.
-    Utf8 { data: String },
+    Utf8 {
+        data: String,
+        /// The modified UTF-8 bytes as read, kept only when `data` can't hold
+        /// them exactly because they encode an unpaired surrogate. `data` has
+        /// U+FFFD in its place.
+        raw: Option<Vec<u8>>,
+    },
     /// The CONSTANT_MethodHandle_info structure is used to represent a method handle.
     MethodHandle {
         /// The value of the reference_kind item must be in the range 1 to 9.
@@ -225,6 +239,103 @@ pub enum ConstantPoolEntry {
         /// representing a method name and method descriptor.
         name_and_type_index: u16,
     },
+    /// The slot after a `Long` or `Double`, which each take up two entries
+    /// in the constant_pool table. It isn't a valid index, and isn't
+    /// read from or written to a class file.
+    Unusable,
+}
+
+/// The kind of a constant pool entry, without its contents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConstantKind {
+    Class,
+    Fieldref,
+    Methodref,
+    InterfaceMethodref,
+    String,
+    Integer,
+    Float,
+    Long,
+    Double,
+    NameAndType,
+    Utf8,
+    MethodHandle,
+    MethodType,
+    InvokeDynamic,
+    Unusable,
+}
+
+impl ConstantKind {
+    /// The tag byte identifying this kind in a class file.
+    pub fn tag(&self) -> u8 {
+        match self {
+            Self::Class => tags::CONSTANT_Class,
+            Self::Fieldref => tags::CONSTANT_Fieldref,
+            Self::Methodref => tags::CONSTANT_Methodref,
+            Self::InterfaceMethodref => tags::CONSTANT_InterfaceMethodref,
+            Self::String => tags::CONSTANT_String,
+            Self::Integer => tags::CONSTANT_Integer,
+            Self::Float => tags::CONSTANT_Float,
+            Self::Long => tags::CONSTANT_Long,
+            Self::Double => tags::CONSTANT_Double,
+            Self::NameAndType => tags::CONSTANT_NameAndType,
+            Self::Utf8 => tags::CONSTANT_Utf8,
+            Self::MethodHandle => tags::CONSTANT_MethodHandle,
+            Self::MethodType => tags::CONSTANT_MethodType,
+            Self::InvokeDynamic => tags::CONSTANT_InvokeDynamic,
+            // never written, so it has no tag of its own
+            Self::Unusable => 0,
+        }
+    }
+}
+
+impl ConstantPoolEntry {
+    /// A `Utf8` constant holding `data`.
+    pub fn utf8(data: impl Into<String>) -> Self {
+        Self::Utf8 { data: data.into(), raw: None }
+    }
+
+    /// The kind of this entry.
+    pub fn kind(&self) -> ConstantKind {
+        match self {
+            Self::Class { .. } => ConstantKind::Class,
+            Self::Fieldref { .. } => ConstantKind::Fieldref,
+            Self::Methodref { .. } => ConstantKind::Methodref,
+            Self::InterfaceMethodref { .. } => ConstantKind::InterfaceMethodref,
+            Self::String { .. } => ConstantKind::String,
+            Self::Integer { .. } => ConstantKind::Integer,
+            Self::Float { .. } => ConstantKind::Float,
+            Self::Long { .. } => ConstantKind::Long,
+            Self::Double { .. } => ConstantKind::Double,
+            Self::NameAndType { .. } => ConstantKind::NameAndType,
+            Self::Utf8 { .. } => ConstantKind::Utf8,
+            Self::MethodHandle { .. } => ConstantKind::MethodHandle,
+            Self::MethodType { .. } => ConstantKind::MethodType,
+            Self::InvokeDynamic { .. } => ConstantKind::InvokeDynamic,
+            Self::Unusable => ConstantKind::Unusable,
+        }
+    }
+
+    /// The tag byte of this entry in a class file.
+    pub fn tag(&self) -> u8 {
+        self.kind().tag()
+    }
+
+    /// The constant pool indices this entry refers to.
+    pub(crate) fn references(&self) -> Vec<u16> {
+        match self {
+            Self::Class { name_index } => vec![*name_index],
+            Self::String { string_index } => vec![*string_index],
+            Self::Fieldref { class_index, name_and_type_index }
+            | Self::Methodref { class_index, name_and_type_index }
+            | Self::InterfaceMethodref { class_index, name_and_type_index } => vec![*class_index, *name_and_type_index],
+            Self::NameAndType { name_index, descriptor_index } => vec![*name_index, *descriptor_index],
+            Self::MethodHandle { reference_index, .. } => vec![*reference_index],
+            Self::MethodType { descriptor_index } => vec![*descriptor_index],
+            Self::InvokeDynamic { name_and_type_index, .. } => vec![*name_and_type_index],
+            _ => vec![],
+        }
+    }
 }
 
 impl ClassFileItem for ConstantPoolEntry {
@@ -270,9 +381,20 @@ impl ClassFileItem for ConstantPoolEntry {
             tags::CONSTANT_Utf8 => {
                 let length = s.read_u2()?;
                 let bytes = s.read_dynamic(length as usize)?;
-                Ok(Self::Utf8 {
-                    data: String::from_utf8(bytes).map_err(ClassFileError::InvalidUTF8Error)?,
-                })
+                // checked before decoding, as a raw NUL or a four-byte sequence
+                // can't be told apart from their modified forms once decoded
+                validate_modified_utf8(&bytes)?;
+                // valid modified UTF-8 only differs from standard UTF-8 in how it writes
+                // NUL and supplementary characters, so it's decoded when those are present
+                match String::from_utf8(bytes) {
+                    Ok(data) => Ok(Self::Utf8 { data, raw: None }),
+                    Err(e) => {
+                        let bytes = e.into_bytes();
+                        let data = decode_modified_utf8(&bytes)?;
+                        let raw = (encode_modified_utf8(&data) != bytes).then_some(bytes);
+                        Ok(Self::Utf8 { data, raw })
+                    }
+                }
             }
             tags::CONSTANT_MethodHandle => Ok(Self::MethodHandle {
                 reference_kind: RefKind::decode(s.read_u1()?)?,
@@ -1,14 +1,17 @@
-use std::io::Read;
+use alloc::{borrow::Cow, format, string::{String, ToString}, vec, vec::Vec};
+use crate::io::Read;
 
 use exo_parser::Lexer;
 
 use crate::{error::{self, ClassFileError}, stream::ClassFileStream};
 
-pub use self::entry::{ConstantPoolEntry, RefKind};
+pub use self::entry::{ConstantKind, ConstantPoolEntry, RefKind};
+pub use self::mutf8::{decode_modified_utf8, encode_modified_utf8, validate_modified_utf8};
 
-use super::{ClassFileItem, ids::{class::ClassName, field::FieldDescriptor, method::{MethodDescriptor, ReturnDescriptor, MethodName}, UnqualifiedName}, file::ClassFile, attribute_info::{Attributes, attrtype}};
+use super::{ClassFileItem, ids::{class::ClassName, field::FieldDescriptor, method::{MethodDescriptor, ReturnDescriptor, MethodName}, UnqualifiedName, BinaryName, NameError}, file::ClassFile, attribute_info::{Attributes, attrtype}};
 
 mod entry;
+mod mutf8;
 
 
 /// The constant pool. Contains all constant pool entries.
@@ -30,9 +33,20 @@ impl ClassFileItem for ConstantPool {
             return Err(ClassFileError::BadConstantPoolLength);
         }
         let len = (len - 1) as usize;
-        Ok(Self {
-            entries: s.read_sequence::<ConstantPoolEntry>(cp, len)?,
-        })
+        let mut entries = Vec::with_capacity(len);
+        while entries.len() < len {
+            let entry = ConstantPoolEntry::read_from_stream(s, cp)?;
+            let wide = matches!(entry, ConstantPoolEntry::Long { .. } | ConstantPoolEntry::Double { .. });
+            entries.push(entry);
+            if wide {
+                // a long or double as the last entry would run past the end of the pool
+                if entries.len() == len {
+                    return Err(ClassFileError::BadConstantPoolLength);
+                }
+                entries.push(ConstantPoolEntry::Unusable);
+            }
+        }
+        Ok(Self { entries })
     }
 }
 #[derive(Debug)]
@@ -104,6 +118,10 @@ pub enum ConstantPoolVerificationError {
     /// Returned if the class name in a class info structure is malformed.
     ClassInfoStructureMalformedClassName,
 
+    /// Returned if the name in the `UTF8` entry at the given index is illegal,
+    /// whether it is used by a class, a member reference or a `NameAndType`.
+    IllegalName(u16, NameError),
+
     /// Returned if the class name is malformed in a `Fieldref`, `Methodref` or `InterfaceMethodref`.
     RefInfoMalformedClassName,
 
@@ -119,9 +137,6 @@ pub enum ConstantPoolVerificationError {
     /// Returned if the return type of an `<init>` method is not `void`.
     MethodRefInitReturnNotVoid,
 
-    /// Returned when an invalid name is found in a `NameAndType` structure.
-    NameAndTypeNameInvalid,
-
     /// Returned when the descriptor of a `NameAndType` structure is malformed.
     NameAndTypeMalformedDescriptor,
 
@@ -141,7 +156,79 @@ pub enum ConstantPoolVerificationError {
     InvokeDynamicInvalidMethodName,
 
     /// Returned if there are more than 1 bootstrap methods attributes on a class.
-    BootstrapMethodsTooMany
+    BootstrapMethodsTooMany,
+
+    /// Returned if the `inner_class_info_index` of an `InnerClasses` entry is not a `Class`.
+    InnerClassesInnerClassInfoNotClass,
+
+    /// Returned if the `outer_class_info_index` of an `InnerClasses` entry is neither 0 nor a `Class`.
+    InnerClassesOuterClassInfoNotClass,
+
+    /// Returned if the `inner_name_index` of an `InnerClasses` entry is neither 0 nor a `UTF8` entry.
+    InnerClassesInnerNameNotUTF8,
+
+    /// Returned if an `InnerClasses` entry has an `outer_class_info_index` but
+    /// no `inner_name_index` in a class file of version 51.0 or above.
+    InnerClassesOuterClassWithoutInnerName,
+
+    /// Returned if the `class_index` of an `EnclosingMethod` attribute is not a `Class`.
+    EnclosingMethodClassIndexNotClass,
+
+    /// Returned if the `method_index` of an `EnclosingMethod` attribute is neither 0 nor a `NameAndType`.
+    EnclosingMethodMethodIndexNotNameAndType
+}
+
+/// The resolved parts of a `Fieldref` constant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FieldRefParts<'a> {
+    /// Internal name of the class owning the field.
+    pub class_name: &'a str,
+    /// Name of the field.
+    pub name: &'a str,
+    /// Field descriptor.
+    pub descriptor: &'a str,
+}
+
+/// The resolved parts of a `Methodref` or `InterfaceMethodref` constant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MethodRefParts<'a> {
+    /// Internal name of the class owning the method.
+    pub class_name: &'a str,
+    /// Name of the method.
+    pub name: &'a str,
+    /// Method descriptor.
+    pub descriptor: &'a str,
+    /// Whether the constant is an `InterfaceMethodref`.
+    pub interface: bool,
+}
+
+/// The resolved parts of a `MethodHandle` constant.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MethodHandleParts<'a> {
+    /// The kind of the method handle.
+    pub kind: RefKind,
+    /// Internal name of the class owning the field or method.
+    pub class_name: &'a str,
+    /// Name of the field or method.
+    pub name: &'a str,
+    /// Field or method descriptor.
+    pub descriptor: &'a str,
+}
+
+/// A resolved static argument to a bootstrap method.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BootstrapArgument<'a> {
+    Int(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    /// A `String` constant.
+    String(&'a str),
+    /// The internal name of a `Class` constant.
+    Class(&'a str),
+    MethodHandle(MethodHandleParts<'a>),
+    /// The descriptor of a `MethodType` constant.
+    MethodType(&'a str),
 }
 
 impl ConstantPool {
@@ -150,36 +237,303 @@ impl ConstantPool {
         if index == 0 {
             return Err(ClassFileError::InvalidConstant(index));
         }
-        self.entries.get(index - 1).ok_or(ClassFileError::ConstantNotPresent(index))
+        match self.entries.get(index - 1) {
+            Some(ConstantPoolEntry::Unusable) => Err(ClassFileError::InvalidConstant(index)),
+            entry => entry.ok_or(ClassFileError::ConstantNotPresent(index)),
+        }
+    }
+
+    /// Get the kind of the constant at `index`, without resolving anything it references.
+    ///
+    /// Fails with `InvalidConstant` for index 0 and for the unusable
+    /// slot following a `Long` or `Double`.
+    pub fn kind_at(&self, index: usize) -> error::Result<ConstantKind> {
+        self.get_constant(index).map(ConstantPoolEntry::kind)
     }
 
     /// Get a UTF-8 constant from the pool.
     pub fn get_utf8_constant(&self, index: usize) -> error::Result<&str> {
         let c = self.get_constant(index)?;
-        if let ConstantPoolEntry::Utf8 { data } = c {
+        if let ConstantPoolEntry::Utf8 { data, .. } = c {
             return Ok(data);
         }
         Err(ClassFileError::ExpectedString)
     }
+
+    /// Get the modified UTF-8 bytes of a UTF-8 constant, as they appear in a
+    /// class file. Unlike [ConstantPool::get_utf8_constant], these keep any
+    /// unpaired surrogates.
+    pub fn get_utf8_bytes(&self, index: usize) -> error::Result<Cow<'_, [u8]>> {
+        match self.get_constant(index)? {
+            ConstantPoolEntry::Utf8 { raw: Some(raw), .. } => Ok(Cow::Borrowed(raw)),
+            ConstantPoolEntry::Utf8 { data, raw: None } => Ok(Cow::Owned(encode_modified_utf8(data))),
+            _ => Err(ClassFileError::ExpectedString),
+        }
+    }
+
+    /// Check that no chain of index references between entries loops back on
+    /// itself, so that resolving any entry is guaranteed to terminate.
+    ///
+    /// References to indices outside the pool are ignored here; they are
+    /// reported by [`ConstantPool::verify_cp_index_types`].
+    pub fn verify_no_cycles(&self) -> error::Result<()> {
+        #[derive(Clone, Copy, PartialEq, Eq)]
+        enum State {
+            Unvisited,
+            InProgress,
+            Done,
+        }
+        let len = self.entries.len();
+        let mut state = vec![State::Unvisited; len + 1];
+        let mut stack: Vec<(usize, Vec<u16>)> = Vec::new();
+        for root in 1..=len {
+            if state[root] != State::Unvisited {
+                continue;
+            }
+            state[root] = State::InProgress;
+            stack.push((root, self.entries[root - 1].references()));
+            while let Some((index, refs)) = stack.last_mut() {
+                let index = *index;
+                match refs.pop() {
+                    Some(next) => {
+                        let next = next as usize;
+                        if next == 0 || next > len {
+                            continue;
+                        }
+                        match state[next] {
+                            State::InProgress => return Err(ClassFileError::ConstantPoolCycle(next)),
+                            State::Unvisited => {
+                                state[next] = State::InProgress;
+                                stack.push((next, self.entries[next - 1].references()));
+                            }
+                            State::Done => (),
+                        }
+                    }
+                    None => {
+                        state[index] = State::Done;
+                        stack.pop();
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Get the name of a `Class` constant from the pool.
+    pub fn get_class_name(&self, index: usize) -> error::Result<&str> {
+        match self.get_constant(index)? {
+            ConstantPoolEntry::Class { name_index } => self.get_utf8_constant(*name_index as usize),
+            _ => Err(ClassFileError::UnexpectedConstantType(index)),
+        }
+    }
+
+    /// Get the name and descriptor of a `NameAndType` constant from the pool.
+    pub fn get_name_and_type(&self, index: usize) -> error::Result<(&str, &str)> {
+        match self.get_constant(index)? {
+            ConstantPoolEntry::NameAndType { name_index, descriptor_index } => Ok((
+                self.get_utf8_constant(*name_index as usize)?,
+                self.get_utf8_constant(*descriptor_index as usize)?,
+            )),
+            _ => Err(ClassFileError::UnexpectedConstantType(index)),
+        }
+    }
+
+    /// Resolve a `Fieldref` constant to its owning class, name and descriptor.
+    pub fn resolve_fieldref(&self, index: usize) -> error::Result<FieldRefParts<'_>> {
+        match self.get_constant(index)? {
+            ConstantPoolEntry::Fieldref { class_index, name_and_type_index } => {
+                let (name, descriptor) = self.get_name_and_type(*name_and_type_index as usize)?;
+                Ok(FieldRefParts {
+                    class_name: self.get_class_name(*class_index as usize)?,
+                    name,
+                    descriptor,
+                })
+            }
+            _ => Err(ClassFileError::UnexpectedConstantType(index)),
+        }
+    }
+
+    /// Resolve a `Methodref` or `InterfaceMethodref` constant to its owning class, name and descriptor.
+    pub fn resolve_methodref(&self, index: usize) -> error::Result<MethodRefParts<'_>> {
+        let (class_index, name_and_type_index, interface) = match self.get_constant(index)? {
+            ConstantPoolEntry::Methodref { class_index, name_and_type_index } => (class_index, name_and_type_index, false),
+            ConstantPoolEntry::InterfaceMethodref { class_index, name_and_type_index } => (class_index, name_and_type_index, true),
+            _ => return Err(ClassFileError::UnexpectedConstantType(index)),
+        };
+        let (name, descriptor) = self.get_name_and_type(*name_and_type_index as usize)?;
+        Ok(MethodRefParts {
+            class_name: self.get_class_name(*class_index as usize)?,
+            name,
+            descriptor,
+            interface,
+        })
+    }
+
+    /// Resolve a `MethodHandle` constant to its kind and the field or method it refers to.
+    pub fn resolve_method_handle(&self, index: usize) -> error::Result<MethodHandleParts<'_>> {
+        let (kind, reference_index) = match self.get_constant(index)? {
+            ConstantPoolEntry::MethodHandle { reference_kind, reference_index } => (*reference_kind, *reference_index as usize),
+            _ => return Err(ClassFileError::UnexpectedConstantType(index)),
+        };
+        match kind {
+            RefKind::REF_getField | RefKind::REF_getStatic | RefKind::REF_putField | RefKind::REF_putStatic => {
+                let parts = self.resolve_fieldref(reference_index)?;
+                Ok(MethodHandleParts { kind, class_name: parts.class_name, name: parts.name, descriptor: parts.descriptor })
+            }
+            _ => {
+                let parts = self.resolve_methodref(reference_index)?;
+                Ok(MethodHandleParts { kind, class_name: parts.class_name, name: parts.name, descriptor: parts.descriptor })
+            }
+        }
+    }
+
+    /// Resolve a loadable constant used as a static argument to a bootstrap method.
+    pub fn resolve_bootstrap_argument(&self, index: usize) -> error::Result<BootstrapArgument<'_>> {
+        match self.get_constant(index)? {
+            ConstantPoolEntry::Integer { bytes } => Ok(BootstrapArgument::Int(*bytes)),
+            ConstantPoolEntry::Float { .. } => self.get_float(index).map(BootstrapArgument::Float),
+            ConstantPoolEntry::Long { bytes } => Ok(BootstrapArgument::Long(*bytes)),
+            ConstantPoolEntry::Double { .. } => self.get_double(index).map(BootstrapArgument::Double),
+            ConstantPoolEntry::String { string_index } => self.get_utf8_constant(*string_index as usize).map(BootstrapArgument::String),
+            ConstantPoolEntry::Class { .. } => self.get_class_name(index).map(BootstrapArgument::Class),
+            ConstantPoolEntry::MethodHandle { .. } => self.resolve_method_handle(index).map(BootstrapArgument::MethodHandle),
+            ConstantPoolEntry::MethodType { descriptor_index } => self.get_utf8_constant(*descriptor_index as usize).map(BootstrapArgument::MethodType),
+            _ => Err(ClassFileError::UnexpectedConstantType(index)),
+        }
+    }
+
+    /// Get an integer constant from the pool.
+    pub fn get_int(&self, index: usize) -> error::Result<i32> {
+        match self.get_constant(index)? {
+            ConstantPoolEntry::Integer { bytes } => Ok(*bytes),
+            _ => Err(ClassFileError::UnexpectedConstantType(index)),
+        }
+    }
+
+    /// Get a float constant from the pool.
+    pub fn get_float(&self, index: usize) -> error::Result<f32> {
+        match self.get_constant(index)? {
+            ConstantPoolEntry::Float { float } => Ok(f32::from_bits(*float)),
+            _ => Err(ClassFileError::UnexpectedConstantType(index)),
+        }
+    }
+
+    /// Get a long constant from the pool.
+    pub fn get_long(&self, index: usize) -> error::Result<i64> {
+        match self.get_constant(index)? {
+            ConstantPoolEntry::Long { bytes } => Ok(*bytes),
+            _ => Err(ClassFileError::UnexpectedConstantType(index)),
+        }
+    }
+
+    /// Get a double constant from the pool.
+    pub fn get_double(&self, index: usize) -> error::Result<f64> {
+        match self.get_constant(index)? {
+            ConstantPoolEntry::Double { bytes } => Ok(f64::from_bits(*bytes)),
+            _ => Err(ClassFileError::UnexpectedConstantType(index)),
+        }
+    }
+
+    /// Render every entry in the style of `javap -v`, such as
+    /// `#2 = Class #1 // java/lang/Object`.
+    ///
+    /// The trailing comment holds the resolved value of a reference,
+    /// and is left out if the reference can't be resolved. As with `javap`,
+    /// the unusable slot after a `Long` or `Double` isn't listed.
+    pub fn describe_entries(&self) -> Vec<String> {
+        self.entries
+            .iter()
+            .enumerate()
+            .filter(|(_, entry)| !matches!(entry, ConstantPoolEntry::Unusable))
+            .map(|(i, entry)| format!("#{} = {}", i + 1, self.describe_entry(entry)))
+            .collect()
+    }
+
+    fn describe_entry(&self, entry: &ConstantPoolEntry) -> String {
+        let with_comment = |v: String, comment: error::Result<String>| match comment {
+            Ok(comment) => format!("{} // {}", v, comment),
+            Err(_) => v,
+        };
+        let member = |class_index: u16, name_and_type_index: u16| -> error::Result<String> {
+            let (name, descriptor) = self.get_name_and_type(name_and_type_index as usize)?;
+            Ok(format!("{}.{}:{}", self.get_class_name(class_index as usize)?, name, descriptor))
+        };
+        match entry {
+            ConstantPoolEntry::Class { name_index } => with_comment(
+                format!("Class #{}", name_index),
+                self.get_utf8_constant(*name_index as usize).map(ToString::to_string),
+            ),
+            ConstantPoolEntry::Fieldref { class_index, name_and_type_index } => with_comment(
+                format!("Fieldref #{}.#{}", class_index, name_and_type_index),
+                member(*class_index, *name_and_type_index),
+            ),
+            ConstantPoolEntry::Methodref { class_index, name_and_type_index } => with_comment(
+                format!("Methodref #{}.#{}", class_index, name_and_type_index),
+                member(*class_index, *name_and_type_index),
+            ),
+            ConstantPoolEntry::InterfaceMethodref { class_index, name_and_type_index } => with_comment(
+                format!("InterfaceMethodref #{}.#{}", class_index, name_and_type_index),
+                member(*class_index, *name_and_type_index),
+            ),
+            ConstantPoolEntry::String { string_index } => with_comment(
+                format!("String #{}", string_index),
+                self.get_utf8_constant(*string_index as usize).map(ToString::to_string),
+            ),
+            ConstantPoolEntry::Integer { bytes } => format!("Integer {}", bytes),
+            ConstantPoolEntry::Float { float } => format!("Float {:?}f", f32::from_bits(*float)),
+            ConstantPoolEntry::Long { bytes } => format!("Long {}l", bytes),
+            ConstantPoolEntry::Double { bytes } => format!("Double {:?}d", f64::from_bits(*bytes)),
+            ConstantPoolEntry::NameAndType { name_index, descriptor_index } => with_comment(
+                format!("NameAndType #{}:#{}", name_index, descriptor_index),
+                self.get_utf8_constant(*name_index as usize).and_then(|name| {
+                    Ok(format!("{}:{}", name, self.get_utf8_constant(*descriptor_index as usize)?))
+                }),
+            ),
+            ConstantPoolEntry::Utf8 { data, .. } => format!("Utf8 {}", data),
+            ConstantPoolEntry::MethodHandle { reference_kind, reference_index } => with_comment(
+                format!("MethodHandle {}:#{}", reference_kind.value(), reference_index),
+                match reference_kind {
+                    RefKind::REF_getField | RefKind::REF_getStatic | RefKind::REF_putField | RefKind::REF_putStatic => {
+                        self.resolve_fieldref(*reference_index as usize).map(|v| (v.class_name, v.name, v.descriptor))
+                    }
+                    _ => self.resolve_methodref(*reference_index as usize).map(|v| (v.class_name, v.name, v.descriptor)),
+                }
+                .map(|(class_name, name, descriptor)| format!("{:?} {}.{}:{}", reference_kind, class_name, name, descriptor)),
+            ),
+            ConstantPoolEntry::MethodType { descriptor_index } => with_comment(
+                format!("MethodType #{}", descriptor_index),
+                self.get_utf8_constant(*descriptor_index as usize).map(ToString::to_string),
+            ),
+            ConstantPoolEntry::InvokeDynamic { bootstrap_method_attr_index, name_and_type_index } => with_comment(
+                format!("InvokeDynamic #{}:#{}", bootstrap_method_attr_index, name_and_type_index),
+                self.get_name_and_type(*name_and_type_index as usize).map(|(name, descriptor)| format!("{}:{}", name, descriptor)),
+            ),
+            ConstantPoolEntry::Unusable => "Unusable".to_string(),
+        }
+    }
     
     /// Verifies that the constant pool is well-formed.
-    pub fn verify_structure(&self, class_file: &ClassFile) -> std::result::Result<(), ConstantPoolVerificationError> {
+    pub fn verify_structure(&self, class_file: &ClassFile) -> core::result::Result<(), ConstantPoolVerificationError> {
         self.verify_cp_index_types().map_err(ConstantPoolVerificationError::IndexVerificationError)?;
 
         for entry in self.entries.iter() {
             match entry {
                 ConstantPoolEntry::Class { name_index } => {
                     let name = self.get_utf8_constant(*name_index as usize).map_err(ConstantPoolVerificationError::ClassFileError)?;
-                    let lexer = Lexer::new();
-                    let mut stream = Lexer::stream(lexer, name.to_string());
-                    if stream.token::<ClassName>().is_err() && stream.token::<FieldDescriptor>().is_err() {
-                        return Err(ConstantPoolVerificationError::ClassInfoStructureMalformedClassName);
+                    if name.starts_with('[') {
+                        let lexer = Lexer::new();
+                        let mut stream = Lexer::stream(lexer, name.to_string());
+                        if stream.token::<FieldDescriptor>().is_err() {
+                            return Err(ConstantPoolVerificationError::ClassInfoStructureMalformedClassName);
+                        }
+                    } else {
+                        BinaryName::validate(name).map_err(|e| ConstantPoolVerificationError::IllegalName(*name_index, e))?;
                     }
                 },
                 ConstantPoolEntry::Methodref { class_index, name_and_type_index } | ConstantPoolEntry::Fieldref { class_index, name_and_type_index } | ConstantPoolEntry::InterfaceMethodref { class_index, name_and_type_index } => {
                     let name = match self.get_constant(*class_index as usize).map_err(ConstantPoolVerificationError::ClassFileError)? {
                         ConstantPoolEntry::Class { name_index } => self.get_utf8_constant(*name_index as usize),
-                        _ => panic!("we checked types")
+                        _ => Err(ClassFileError::UnexpectedConstantType(*class_index as usize)),
                     }.map_err(ConstantPoolVerificationError::ClassFileError)?;
                     let lexer = Lexer::new();
                     let mut stream = Lexer::stream(lexer.clone(), name.to_string());
@@ -189,7 +543,7 @@ impl ConstantPool {
 
                     let (name_index, descriptor_index) = match self.get_constant(*name_and_type_index as usize).map_err(ConstantPoolVerificationError::ClassFileError)? {
                         ConstantPoolEntry::NameAndType { name_index, descriptor_index } => (name_index, descriptor_index),
-                        _ => panic!("Should be impossible, we verified types")                        
+                        _ => return Err(ConstantPoolVerificationError::ClassFileError(ClassFileError::UnexpectedConstantType(*name_and_type_index as usize))),
                     };
 
                     if matches!(entry, ConstantPoolEntry::Fieldref { .. }) {
@@ -201,31 +555,26 @@ impl ConstantPool {
                     } else {
                         let descriptor = self.get_utf8_constant(*descriptor_index as usize).map_err(ConstantPoolVerificationError::ClassFileError)?;
                         let mut stream = Lexer::stream(lexer, descriptor.to_string()); 
-                        let d = stream.token::<MethodDescriptor>(); 
-                        if d.is_err() {
-                            return Err(ConstantPoolVerificationError::MethodRefMalformedMethodDescriptor);
-                        }
-                        let d = d.unwrap();
-                        if matches!(entry, ConstantPoolEntry::Methodref { .. }) {
-                            let name = self.get_utf8_constant(*name_index as usize).map_err(ConstantPoolVerificationError::ClassFileError)?;
-                            if name.starts_with('<') {
-                                if name != "<init>" {
-                                    return Err(ConstantPoolVerificationError::MethodRefInvalidName);
-                                }
-                                if !matches!(d.return_desc, ReturnDescriptor::Void(_)) {
-                                    return Err(ConstantPoolVerificationError::MethodRefInitReturnNotVoid);
-                                }
+                        let d = stream.token::<MethodDescriptor>().map_err(|_| ConstantPoolVerificationError::MethodRefMalformedMethodDescriptor)?;
+                        let name = self.get_utf8_constant(*name_index as usize).map_err(ConstantPoolVerificationError::ClassFileError)?;
+                        UnqualifiedName::validate_method(name).map_err(|e| ConstantPoolVerificationError::IllegalName(*name_index, e))?;
+                        if matches!(entry, ConstantPoolEntry::Methodref { .. }) && name.starts_with('<') {
+                            if name != "<init>" {
+                                return Err(ConstantPoolVerificationError::MethodRefInvalidName);
+                            }
+                            if !matches!(d.return_desc, ReturnDescriptor::Void(_)) {
+                                return Err(ConstantPoolVerificationError::MethodRefInitReturnNotVoid);
                             }
                         }
                     }
                 },
                 ConstantPoolEntry::NameAndType { name_index, descriptor_index } => {
                     let name = self.get_utf8_constant(*name_index as usize).map_err(ConstantPoolVerificationError::ClassFileError)?;
-                    let lexer = Lexer::new();
-                    let mut stream = Lexer::stream(lexer.clone(), name.to_string());
-                    if stream.token::<UnqualifiedName>().is_err() {
-                        return Err(ConstantPoolVerificationError::NameAndTypeNameInvalid);
+                    // `<init>` and `<clinit>` are the only names allowed which are not unqualified names
+                    if name != "<init>" && name != "<clinit>" {
+                        UnqualifiedName::validate(name).map_err(|e| ConstantPoolVerificationError::IllegalName(*name_index, e))?;
                     }
+                    let lexer = Lexer::new();
                     
                     let descriptor = self.get_utf8_constant(*descriptor_index as usize).map_err(ConstantPoolVerificationError::ClassFileError)?;
                     let mut stream = Lexer::stream(lexer.clone(), descriptor.to_string());
@@ -242,22 +591,22 @@ impl ConstantPool {
                     }
                 },
                 ConstantPoolEntry::InvokeDynamic { bootstrap_method_attr_index, name_and_type_index } => {
-                    let bs_methods = class_file.attributes.get(attrtype::BootstrapMethods);
-                    if bs_methods.is_empty() {
+                    let mut bs_methods = class_file.attributes.get(attrtype::BootstrapMethods);
+                    let Some(bs_method) = bs_methods.next() else {
                         return Err(ConstantPoolVerificationError::InvokeDynamicNoBootstrapMethodsAttr);
-                    }
-                    if bs_methods.len() > 1 {
+                    };
+                    if bs_methods.next().is_some() {
                         return Err(ConstantPoolVerificationError::BootstrapMethodsTooMany);
                     }
-                    if let Attributes::BootstrapMethods { bootstrap_methods } = &bs_methods[0] {
-                        if *bootstrap_method_attr_index as usize > bootstrap_methods.len() {
+                    if let Attributes::BootstrapMethods { bootstrap_methods } = bs_method {
+                        if *bootstrap_method_attr_index as usize >= bootstrap_methods.len() {
                             return Err(ConstantPoolVerificationError::InvokeDynamicInvalidBootstrapMethodsIndex);
                         }
                     }
                     let lexer = Lexer::new();
                     let (name_index, descriptor_index) = match self.get_constant(*name_and_type_index as usize).map_err(ConstantPoolVerificationError::ClassFileError)? {
                         ConstantPoolEntry::NameAndType { name_index, descriptor_index } => (name_index, descriptor_index),
-                        _ => panic!("Should be impossible, we verified types")                        
+                        _ => return Err(ConstantPoolVerificationError::ClassFileError(ClassFileError::UnexpectedConstantType(*name_and_type_index as usize))),
                     };
 
                     let descriptor = self.get_utf8_constant(*descriptor_index as usize).map_err(ConstantPoolVerificationError::ClassFileError)?;
@@ -277,11 +626,51 @@ impl ConstantPool {
                 _ => ()
             }
         }
+        self.verify_nesting_attributes(class_file)?;
+        Ok(())
+    }
+
+    /// Verifies the constant pool references of the `InnerClasses` and `EnclosingMethod` attributes.
+    fn verify_nesting_attributes(&self, class_file: &ClassFile) -> core::result::Result<(), ConstantPoolVerificationError> {
+        let is_kind = |index: u16, f: fn(&ConstantPoolEntry) -> bool| -> core::result::Result<bool, ConstantPoolVerificationError> {
+            Ok(f(self.get_constant(index as usize).map_err(ConstantPoolVerificationError::ClassFileError)?))
+        };
+        let is_class = |e: &ConstantPoolEntry| matches!(e, ConstantPoolEntry::Class { .. });
+
+        for attr in class_file.attributes.get(attrtype::InnerClasses) {
+            if let Attributes::InnerClasses { classes } = attr {
+                for class in classes {
+                    if !is_kind(class.inner_class_info_index, is_class)? {
+                        return Err(ConstantPoolVerificationError::InnerClassesInnerClassInfoNotClass);
+                    }
+                    if class.outer_class_info_index != 0 && !is_kind(class.outer_class_info_index, is_class)? {
+                        return Err(ConstantPoolVerificationError::InnerClassesOuterClassInfoNotClass);
+                    }
+                    if class.inner_name_index != 0 && !is_kind(class.inner_name_index, |e| matches!(e, ConstantPoolEntry::Utf8 { .. }))? {
+                        return Err(ConstantPoolVerificationError::InnerClassesInnerNameNotUTF8);
+                    }
+                    if class_file.version.0 >= 51 && class.inner_name_index == 0 && class.outer_class_info_index != 0 {
+                        return Err(ConstantPoolVerificationError::InnerClassesOuterClassWithoutInnerName);
+                    }
+                }
+            }
+        }
+
+        for attr in class_file.attributes.get(attrtype::EnclosingMethod) {
+            if let Attributes::EnclosingMethod { class_index, method_index } = attr {
+                if !is_kind(*class_index, is_class)? {
+                    return Err(ConstantPoolVerificationError::EnclosingMethodClassIndexNotClass);
+                }
+                if *method_index != 0 && !is_kind(*method_index, |e| matches!(e, ConstantPoolEntry::NameAndType { .. }))? {
+                    return Err(ConstantPoolVerificationError::EnclosingMethodMethodIndexNotNameAndType);
+                }
+            }
+        }
         Ok(())
     }
 
     /// Verify all constant pool index types within this constant pool.
-    pub fn verify_cp_index_types(&self) -> std::result::Result<(), IndexVerificationError> {
+    pub fn verify_cp_index_types(&self) -> core::result::Result<(), IndexVerificationError> {
         for (index, entry) in self.entries.iter().enumerate() {
             match entry {
                 ConstantPoolEntry::Class { name_index } => verify_index!(
@@ -335,8 +724,8 @@ impl ConstantPool {
                             
                             if matches!(reference_kind, RefKind::REF_newInvokeSpecial) {
                                 if let ConstantPoolEntry::Methodref { name_and_type_index, .. } = entry {
-                                    if let ConstantPoolEntry::NameAndType { name_index, .. } = &self.entries[*name_and_type_index as usize] {
-                                        if let ConstantPoolEntry::Utf8 { data } = &self.entries[*name_index as usize] {
+                                    if let ConstantPoolEntry::NameAndType { name_index, .. } = self.get_constant(*name_and_type_index as usize).map_err(IndexVerificationError::c)? {
+                                        if let ConstantPoolEntry::Utf8 { data, .. } = self.get_constant(*name_index as usize).map_err(IndexVerificationError::c)? {
                                             if data != "<init>" {
                                                 verify_index!(index, false, IndexVerificationErrorType::MethodHandleReferenceIndexBadType)?;
                                             }
@@ -361,8 +750,8 @@ impl ConstantPool {
                                 _ => { verify_index!(index, false, IndexVerificationErrorType::MethodHandleReferenceIndexBadType)?; unreachable!() }
                             };
 
-                            if let ConstantPoolEntry::NameAndType { name_index, .. } = &self.entries[info_index as usize] {
-                                if let ConstantPoolEntry::Utf8 { data } = &self.entries[*name_index as usize] {
+                            if let ConstantPoolEntry::NameAndType { name_index, .. } = self.get_constant(info_index as usize).map_err(IndexVerificationError::c)? {
+                                if let ConstantPoolEntry::Utf8 { data, .. } = self.get_constant(*name_index as usize).map_err(IndexVerificationError::c)? {
                                     verify_index!(index, data != "<init>", IndexVerificationErrorType::MethodHandleReferenceIndexBadType)?;
                                     verify_index!(index, data != "<clinit>", IndexVerificationErrorType::MethodHandleReferenceIndexBadType)?;
                                 }
@@ -382,3 +771,179 @@ impl ConstantPool {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec, vec::Vec};
+
+    use crate::{error::ClassFileError, io::Cursor, item::ClassFileItem, stream::ClassFileStream};
+
+    use super::{ConstantKind, ConstantPool, ConstantPoolEntry, RefKind};
+
+    #[test]
+    fn describe_entries_test() {
+        let cp = ConstantPool {
+            entries: vec![
+                ConstantPoolEntry::utf8("java/lang/Object".to_string()),
+                ConstantPoolEntry::Class { name_index: 1 },
+                ConstantPoolEntry::utf8("<init>".to_string()),
+                ConstantPoolEntry::utf8("()V".to_string()),
+                ConstantPoolEntry::NameAndType { name_index: 3, descriptor_index: 4 },
+                ConstantPoolEntry::Methodref { class_index: 2, name_and_type_index: 5 },
+                ConstantPoolEntry::Long { bytes: 3 },
+                ConstantPoolEntry::Unusable,
+                ConstantPoolEntry::String { string_index: 40 },
+            ],
+        };
+        assert_eq!(
+            cp.describe_entries(),
+            [
+                "#1 = Utf8 java/lang/Object",
+                "#2 = Class #1 // java/lang/Object",
+                "#3 = Utf8 <init>",
+                "#4 = Utf8 ()V",
+                "#5 = NameAndType #3:#4 // <init>:()V",
+                "#6 = Methodref #2.#5 // java/lang/Object.<init>:()V",
+                "#7 = Long 3l",
+                "#9 = String #40",
+            ]
+        );
+    }
+
+    #[test]
+    fn constant_bounds_test() {
+        let cp = ConstantPool {
+            entries: vec![
+                ConstantPoolEntry::utf8("a".to_string()),
+                ConstantPoolEntry::Long { bytes: 1 },
+                ConstantPoolEntry::Unusable,
+                ConstantPoolEntry::utf8("b".to_string()),
+                ConstantPoolEntry::utf8("c".to_string()),
+            ],
+        };
+        assert_eq!(cp.get_utf8_constant(1).unwrap(), "a");
+        assert_eq!(cp.get_utf8_constant(4).unwrap(), "b");
+        assert_eq!(cp.get_utf8_constant(5).unwrap(), "c");
+        assert!(matches!(cp.get_constant(6), Err(ClassFileError::ConstantNotPresent(6))));
+        assert!(matches!(cp.get_utf8_constant(6), Err(ClassFileError::ConstantNotPresent(6))));
+        assert!(matches!(cp.get_constant(0), Err(ClassFileError::InvalidConstant(0))));
+        assert!(matches!(cp.get_constant(3), Err(ClassFileError::InvalidConstant(3))));
+        assert!(matches!(cp.get_constant(usize::MAX), Err(ClassFileError::ConstantNotPresent(_))));
+    }
+
+    #[test]
+    fn read_wide_constants_test() {
+        let read = |data: &[u8]| ConstantPool::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(data)), None);
+        // a count of 4: a long taking #1 and #2, then an integer at #3
+        let cp = read(&[0, 4, 5, 0, 0, 0, 0, 0, 0, 0, 7, 3, 0, 0, 0, 9]).unwrap();
+        assert_eq!(cp.entries.len(), 3);
+        assert!(matches!(cp.get_constant(1), Ok(ConstantPoolEntry::Long { bytes: 7 })));
+        assert!(matches!(cp.get_constant(2), Err(ClassFileError::InvalidConstant(2))));
+        assert!(matches!(cp.get_constant(3), Ok(ConstantPoolEntry::Integer { bytes: 9 })));
+        assert_eq!(cp.describe_entries().len(), 2);
+
+        // a count of 2 leaves no room for the long's second slot
+        assert!(matches!(read(&[0, 2, 5, 0, 0, 0, 0, 0, 0, 0, 7]), Err(ClassFileError::BadConstantPoolLength)));
+    }
+
+    #[test]
+    fn kind_at_test() {
+        let cp = ConstantPool {
+            entries: vec![
+                ConstantPoolEntry::utf8("A".to_string()),
+                ConstantPoolEntry::Class { name_index: 1 },
+                ConstantPoolEntry::Long { bytes: 3 },
+                ConstantPoolEntry::Unusable,
+                ConstantPoolEntry::InvokeDynamic { bootstrap_method_attr_index: 0, name_and_type_index: 99 },
+            ],
+        };
+        assert_eq!(cp.kind_at(1).unwrap(), ConstantKind::Utf8);
+        assert_eq!(cp.kind_at(2).unwrap(), ConstantKind::Class);
+        assert_eq!(cp.kind_at(3).unwrap(), ConstantKind::Long);
+        // the second slot of the long
+        assert!(matches!(cp.kind_at(4), Err(ClassFileError::InvalidConstant(4))));
+        // the dangling reference isn't followed
+        assert_eq!(cp.kind_at(5).unwrap(), ConstantKind::InvokeDynamic);
+        assert!(matches!(cp.kind_at(0), Err(ClassFileError::InvalidConstant(0))));
+        assert!(matches!(cp.kind_at(6), Err(ClassFileError::ConstantNotPresent(6))));
+
+        let tags: Vec<u8> = cp.entries.iter().map(ConstantPoolEntry::tag).collect();
+        assert_eq!(tags, [1, 7, 5, 0, 18]);
+    }
+
+    #[test]
+    fn numeric_constants_test() {
+        let cp = ConstantPool {
+            entries: vec![
+                ConstantPoolEntry::Integer { bytes: -7 },
+                ConstantPoolEntry::Float { float: 0x7fc00000 },
+                ConstantPoolEntry::Float { float: 0x80000000 },
+                ConstantPoolEntry::Long { bytes: i64::MIN },
+                ConstantPoolEntry::Unusable,
+                ConstantPoolEntry::Double { bytes: 0x7ff8000000000000 },
+                ConstantPoolEntry::Unusable,
+                ConstantPoolEntry::Double { bytes: 0x8000000000000000 },
+                ConstantPoolEntry::Unusable,
+            ],
+        };
+        assert_eq!(cp.get_int(1).unwrap(), -7);
+        assert!(cp.get_float(2).unwrap().is_nan());
+        let neg_zero = cp.get_float(3).unwrap();
+        assert!(neg_zero == 0.0 && neg_zero.is_sign_negative());
+        assert_eq!(cp.get_long(4).unwrap(), i64::MIN);
+        assert!(cp.get_double(6).unwrap().is_nan());
+        let neg_zero = cp.get_double(8).unwrap();
+        assert!(neg_zero == 0.0 && neg_zero.is_sign_negative());
+        assert!(matches!(cp.get_double(7), Err(ClassFileError::InvalidConstant(7))));
+        assert!(matches!(cp.get_double(1), Err(ClassFileError::UnexpectedConstantType(1))));
+    }
+
+    #[test]
+    fn resolve_ref_test() {
+        let utf8 = |s: &str| ConstantPoolEntry::utf8(s.to_string());
+        let cp = ConstantPool {
+            entries: vec![
+                utf8("java/lang/System"),
+                ConstantPoolEntry::Class { name_index: 1 },
+                utf8("out"),
+                utf8("Ljava/io/PrintStream;"),
+                ConstantPoolEntry::NameAndType { name_index: 3, descriptor_index: 4 },
+                ConstantPoolEntry::Fieldref { class_index: 2, name_and_type_index: 5 },
+            ],
+        };
+        let parts = cp.resolve_fieldref(6).unwrap();
+        assert_eq!((parts.class_name, parts.name, parts.descriptor), ("java/lang/System", "out", "Ljava/io/PrintStream;"));
+        assert!(matches!(cp.resolve_methodref(6), Err(ClassFileError::UnexpectedConstantType(6))));
+    }
+
+    #[test]
+    fn verify_no_cycles_test() {
+        let utf8 = |s: &str| ConstantPoolEntry::utf8(s.to_string());
+        let mut cp = ConstantPool {
+            entries: vec![
+                utf8("java/lang/Object"),
+                ConstantPoolEntry::Class { name_index: 1 },
+                ConstantPoolEntry::NameAndType { name_index: 1, descriptor_index: 1 },
+                ConstantPoolEntry::Methodref { class_index: 2, name_and_type_index: 3 },
+                ConstantPoolEntry::String { string_index: 40 },
+            ],
+        };
+        assert!(cp.verify_no_cycles().is_ok());
+
+        cp.entries[0] = ConstantPoolEntry::Class { name_index: 4 };
+        assert!(matches!(cp.verify_no_cycles(), Err(ClassFileError::ConstantPoolCycle(_))));
+
+        cp.entries[0] = ConstantPoolEntry::String { string_index: 1 };
+        assert!(matches!(cp.verify_no_cycles(), Err(ClassFileError::ConstantPoolCycle(1))));
+    }
+
+    #[test]
+    fn ref_kind_test() {
+        for v in 1..=9 {
+            assert_eq!(RefKind::decode(v).unwrap().value(), v);
+        }
+        assert_eq!(RefKind::decode(8).unwrap(), RefKind::REF_newInvokeSpecial);
+        assert!(matches!(RefKind::decode(0), Err(ClassFileError::UnknownReferenceKind(0))));
+        assert!(matches!(RefKind::decode(10), Err(ClassFileError::UnknownReferenceKind(10))));
+    }
+}
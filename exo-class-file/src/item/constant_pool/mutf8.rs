@@ -0,0 +1,157 @@
+//! Modified UTF-8, the string encoding used by class files.
+
+use alloc::{string::String, vec::Vec};
+
+use crate::error::{self, ClassFileError};
+
+/// Check that `bytes` are valid modified UTF-8, without decoding them.
+///
+/// Every character must be encoded in one, two or three bytes, with
+/// NUL written as `0xC0 0x80` and supplementary characters as a
+/// surrogate pair of three-byte sequences. Unpaired surrogates are
+/// accepted, as Java strings may contain them.
+///
+/// On failure the error holds the offset of the offending byte.
+pub fn validate_modified_utf8(bytes: &[u8]) -> error::Result<()> {
+    let continuation = |i: usize| match bytes.get(i) {
+        Some(v) if v & 0xc0 == 0x80 => Ok(*v),
+        _ => Err(ClassFileError::InvalidModifiedUTF8(i)),
+    };
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            0x01..=0x7f => i += 1,
+            // `0xc0 0x80` is the only allowed overlong form, for NUL
+            0xc0 => {
+                if continuation(i + 1)? != 0x80 {
+                    return Err(ClassFileError::InvalidModifiedUTF8(i));
+                }
+                i += 2;
+            }
+            0xc2..=0xdf => {
+                continuation(i + 1)?;
+                i += 2;
+            }
+            lead @ 0xe0..=0xef => {
+                let second = continuation(i + 1)?;
+                continuation(i + 2)?;
+                if lead == 0xe0 && second < 0xa0 {
+                    return Err(ClassFileError::InvalidModifiedUTF8(i));
+                }
+                i += 3;
+            }
+            _ => return Err(ClassFileError::InvalidModifiedUTF8(i)),
+        }
+    }
+    Ok(())
+}
+
+/// Encode a string as modified UTF-8.
+pub fn encode_modified_utf8(s: &str) -> Vec<u8> {
+    let mut out = Vec::with_capacity(s.len());
+    for unit in s.encode_utf16() {
+        match unit {
+            0x0001..=0x007f => out.push(unit as u8),
+            0x0000 | 0x0080..=0x07ff => {
+                out.push(0xc0 | (unit >> 6) as u8);
+                out.push(0x80 | (unit & 0x3f) as u8);
+            }
+            _ => {
+                out.push(0xe0 | (unit >> 12) as u8);
+                out.push(0x80 | ((unit >> 6) & 0x3f) as u8);
+                out.push(0x80 | (unit & 0x3f) as u8);
+            }
+        }
+    }
+    out
+}
+
+/// Decode modified UTF-8 in to a string.
+///
+/// `0xC0 0x80` decodes to NUL and surrogate pairs are joined in to the
+/// supplementary character they encode. Unpaired surrogates can't be held
+/// in a `String` and are replaced with U+FFFD, so a `Utf8` constant holding
+/// one also keeps its raw bytes.
+pub fn decode_modified_utf8(bytes: &[u8]) -> error::Result<String> {
+    validate_modified_utf8(bytes)?;
+    let mut units = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let (unit, len) = match bytes[i] {
+            v @ 0x01..=0x7f => (v as u16, 1),
+            v @ 0xc0..=0xdf => (((v as u16 & 0x1f) << 6) | (bytes[i + 1] as u16 & 0x3f), 2),
+            v => (
+                ((v as u16 & 0x0f) << 12) | ((bytes[i + 1] as u16 & 0x3f) << 6) | (bytes[i + 2] as u16 & 0x3f),
+                3,
+            ),
+        };
+        units.push(unit);
+        i += len;
+    }
+    Ok(char::decode_utf16(units).map(|v| v.unwrap_or(char::REPLACEMENT_CHARACTER)).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{
+        error::ClassFileError,
+        io::Cursor,
+        item::{constant_pool::ConstantPoolEntry, ClassFileItem},
+        stream::ClassFileStream,
+    };
+
+    use super::{decode_modified_utf8, encode_modified_utf8, validate_modified_utf8};
+
+    #[test]
+    fn validate_modified_utf8_test() {
+        assert!(validate_modified_utf8(b"java/lang/Object").is_ok());
+        assert!(validate_modified_utf8("h\u{e9}llo \u{20ac}".as_bytes()).is_ok());
+        // NUL
+        assert!(validate_modified_utf8(&[b'a', 0xc0, 0x80]).is_ok());
+        // U+1D11E as a surrogate pair
+        assert!(validate_modified_utf8(&[0xed, 0xa0, 0xb4, 0xed, 0xb4, 0x9e]).is_ok());
+
+        let invalid = |bytes: &[u8], at: usize| {
+            matches!(validate_modified_utf8(bytes), Err(ClassFileError::InvalidModifiedUTF8(v)) if v == at)
+        };
+        // raw NUL
+        assert!(invalid(&[b'a', 0x00], 1));
+        // four-byte standard UTF-8
+        assert!(invalid("\u{1d11e}".as_bytes(), 0));
+        // overlong forms
+        assert!(invalid(&[0xc1, 0x81], 0));
+        assert!(invalid(&[0xe0, 0x81, 0x81], 0));
+        // truncated and stray continuation bytes
+        assert!(invalid(&[0xe2, 0x82], 2));
+        assert!(invalid(&[b'a', 0x80], 1));
+
+        // checked on the raw bytes as the constant is read
+        let read = |bytes: &[u8]| {
+            let data = [&[1, 0, bytes.len() as u8], bytes].concat();
+            ConstantPoolEntry::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(data)), None)
+        };
+        assert!(matches!(read(&[b'a', 0xc0, 0x80]), Ok(ConstantPoolEntry::Utf8 { data, raw: None }) if data == "a\0"));
+        // an unpaired surrogate is kept in the raw bytes
+        let lone = [b'a', 0xed, 0xa0, 0xb4];
+        assert!(matches!(read(&lone), Ok(ConstantPoolEntry::Utf8 { data, raw: Some(raw) }) if data == "a\u{fffd}" && raw == lone));
+        assert!(matches!(read(&[b'a', 0x00]), Err(ClassFileError::InvalidModifiedUTF8(1))));
+        assert!(matches!(read("\u{1d11e}".as_bytes()), Err(ClassFileError::InvalidModifiedUTF8(0))));
+    }
+    #[test]
+    fn decode_modified_utf8_test() {
+        assert_eq!(decode_modified_utf8(b"java/lang/Object").unwrap(), "java/lang/Object");
+        assert_eq!(decode_modified_utf8(&[b'a', 0xc0, 0x80, b'b']).unwrap(), "a\0b");
+        assert_eq!(decode_modified_utf8(&[0xed, 0xa0, 0xb4, 0xed, 0xb4, 0x9e]).unwrap(), "\u{1d11e}");
+        assert_eq!(decode_modified_utf8(&[0xed, 0xa0, 0xb4, b'a']).unwrap(), "\u{fffd}a");
+        assert!(matches!(decode_modified_utf8(&[b'a', 0x00]), Err(ClassFileError::InvalidModifiedUTF8(1))));
+    }
+
+    #[test]
+    fn encode_modified_utf8_test() {
+        assert_eq!(encode_modified_utf8("java/lang/Object"), b"java/lang/Object");
+        assert_eq!(encode_modified_utf8("a\0b"), [b'a', 0xc0, 0x80, b'b']);
+        assert_eq!(encode_modified_utf8("\u{1d11e}"), [0xed, 0xa0, 0xb4, 0xed, 0xb4, 0x9e]);
+        let s = "h\u{e9}llo \u{20ac} \0 \u{1d11e}";
+        assert_eq!(decode_modified_utf8(&encode_modified_utf8(s)).unwrap(), s);
+    }
+}
@@ -1,11 +1,11 @@
-use std::io::Read;
+use crate::io::Read;
 
 use crate::{
     error::{self, ClassFileError},
     stream::ClassFileStream,
 };
 
-use super::{attribute_info::{Attributes, AttributesCollection}, ClassFileItem, ConstantPool};
+use super::{attribute_info::{attrtype, AttributeContext, Attributes, AttributesCollection}, ids::signature::FieldSignature, ClassFileItem, ConstantPool};
 
 /// Field info.
 #[derive(Debug)]
@@ -33,6 +33,29 @@ pub struct FieldInfo {
     pub attributes: AttributesCollection,
 }
 
+impl FieldInfo {
+    /// Whether this field is synthetic, marked by either
+    /// the `ACC_SYNTHETIC` flag or a `Synthetic` attribute.
+    pub fn is_synthetic(&self) -> bool {
+        self.access_flags.is_synthetic() || self.attributes.first(attrtype::Synthetic).is_some()
+    }
+
+    /// Whether this field has a `Deprecated` attribute. There is
+    /// no access flag for deprecation.
+    pub fn is_deprecated(&self) -> bool {
+        self.attributes.first(attrtype::Deprecated).is_some()
+    }
+
+    /// Parse this field's `Signature` attribute, returning `None`
+    /// if the field has no generic signature.
+    pub fn generic_signature(&self, cp: &ConstantPool) -> Option<error::Result<FieldSignature>> {
+        let index = self.attributes.signature_index()?;
+        Some(cp.get_utf8_constant(index as usize).and_then(|v| {
+            FieldSignature::new(v).ok_or(ClassFileError::MalformedSignature(index))
+        }))
+    }
+}
+
 impl ClassFileItem for FieldInfo {
     fn read_from_stream<R: Read>(
         s: &mut ClassFileStream<R>,
@@ -52,7 +75,7 @@ impl ClassFileItem for FieldInfo {
             access_flags,
             name_index,
             descriptor_index,
-            attributes: AttributesCollection::read_from_stream(s, cp)?,
+            attributes: AttributesCollection::read_in_context(s, cp, AttributeContext::Field)?,
         })
     }
 }
@@ -79,3 +102,58 @@ bitflags::bitflags! {
         const ACC_ENUM = 0x4000;
     }
 }
+
+impl FieldAccessFlags {
+    /// Whether the field is declared public.
+    pub fn is_public(&self) -> bool {
+        self.contains(Self::ACC_PUBLIC)
+    }
+
+    /// Whether the field is declared private.
+    pub fn is_private(&self) -> bool {
+        self.contains(Self::ACC_PRIVATE)
+    }
+
+    /// Whether the field is declared protected.
+    pub fn is_protected(&self) -> bool {
+        self.contains(Self::ACC_PROTECTED)
+    }
+
+    /// Whether the field is declared static.
+    pub fn is_static(&self) -> bool {
+        self.contains(Self::ACC_STATIC)
+    }
+
+    /// Whether the field is declared final.
+    pub fn is_final(&self) -> bool {
+        self.contains(Self::ACC_FINAL)
+    }
+
+    /// Whether the field is declared volatile.
+    pub fn is_volatile(&self) -> bool {
+        self.contains(Self::ACC_VOLATILE)
+    }
+
+    /// Whether the field is declared transient.
+    pub fn is_transient(&self) -> bool {
+        self.contains(Self::ACC_TRANSIENT)
+    }
+
+    /// Whether the field is synthetic.
+    pub fn is_synthetic(&self) -> bool {
+        self.contains(Self::ACC_SYNTHETIC)
+    }
+
+    /// Whether the field is an element of an enum.
+    pub fn is_enum(&self) -> bool {
+        self.contains(Self::ACC_ENUM)
+    }
+
+    /// Check the flags against the combination rules of §4.5 which apply to
+    /// every field: at most one of public, private and protected may be set,
+    /// and a field can't be both final and volatile.
+    pub fn is_valid_combination(&self) -> bool {
+        let visibility = *self & (Self::ACC_PUBLIC | Self::ACC_PRIVATE | Self::ACC_PROTECTED);
+        visibility.bits().count_ones() <= 1 && !(self.is_final() && self.is_volatile())
+    }
+}
@@ -1,12 +1,16 @@
-use std::io::Read;
+use alloc::{boxed::Box, collections::BTreeSet, string::{String, ToString}, vec, vec::Vec};
+use crate::io::{Cursor, Read};
+use exo_parser::{Lexer, Parseable};
 
 use crate::{
     error::{self, ClassFileError},
-    stream::ClassFileStream,
+    stream::{ClassFileStream, ParseDiagnostic, ParseOptions, RecoveredError, RecordingReader, SkippedItem},
 };
 
-use super::{fields::FieldInfo, methods::MethodInfo, attribute_info::{Attributes, AttributesCollection}};
+use super::{fields::{FieldAccessFlags, FieldInfo}, methods::{MethodAccessFlags, MethodInfo}, attribute_info::{attrtype, initial_stack_frame, AttributeContext, Attributes, AttributesCollection, BootstrapMethodsElement, CodeAttribute}, ids::{field::FieldType, method::MethodDescriptor, signature::ClassSignature}};
 pub use super::{constant_pool::ConstantPool, ClassFileItem};
+use super::constant_pool::{BootstrapArgument, ConstantPoolEntry, MethodHandleParts};
+use super::opcodes::CodeVerificationError;
 
 /// The magic number of a class file.
 pub const CLASS_MAGIC: u32 = 0xCAFEBABE;
@@ -29,12 +33,102 @@ bitflags::bitflags! {
         const ACC_ANNOTATION = 0x2000;
         /// Declared as an enum type.
         const ACC_ENUM = 0x4000;
+        /// Is a module, not a class or interface.
+        const ACC_MODULE = 0x8000;
+    }
+}
+
+impl ClassAccessFlags {
+    /// Get class access flags from `bits`, which may contain bits this
+    /// library doesn't recognise, such as those of newer preview features.
+    ///
+    /// When `tolerant`, unrecognised bits are kept in the returned flags
+    /// rather than dropped. Otherwise they're reported with
+    /// [ClassFileError::UnknownClassAccessFlags].
+    pub fn from_bits_checked(bits: u16, tolerant: bool) -> error::Result<Self> {
+        let unknown = bits & !Self::all().bits();
+        if unknown == 0 {
+            Ok(Self::from_bits_truncate(bits))
+        } else if tolerant {
+            // SAFETY: bitflags places no requirements on the bits, unknown bits
+            // are only carried along and excluded from `all()`
+            Ok(unsafe { Self::from_bits_unchecked(bits) })
+        } else {
+            Err(ClassFileError::UnknownClassAccessFlags(unknown))
+        }
+    }
+
+    /// Whether the class is declared public.
+    pub fn is_public(&self) -> bool {
+        self.contains(Self::ACC_PUBLIC)
+    }
+
+    /// Whether the class is declared final.
+    pub fn is_final(&self) -> bool {
+        self.contains(Self::ACC_FINAL)
+    }
+
+    /// Whether this is an interface rather than a class.
+    pub fn is_interface(&self) -> bool {
+        self.contains(Self::ACC_INTERFACE)
+    }
+
+    /// Whether the class is declared abstract.
+    pub fn is_abstract(&self) -> bool {
+        self.contains(Self::ACC_ABSTRACT)
+    }
+
+    /// Whether the class is synthetic.
+    pub fn is_synthetic(&self) -> bool {
+        self.contains(Self::ACC_SYNTHETIC)
+    }
+
+    /// Whether this is an annotation interface.
+    pub fn is_annotation(&self) -> bool {
+        self.contains(Self::ACC_ANNOTATION)
+    }
+
+    /// Whether the class is declared as an enum.
+    pub fn is_enum(&self) -> bool {
+        self.contains(Self::ACC_ENUM)
+    }
+
+    /// Whether this is a module rather than a class or interface.
+    pub fn is_module(&self) -> bool {
+        self.contains(Self::ACC_MODULE)
+    }
+
+    /// Whether `ACC_SUPER` is set, which JVMs before Java SE 8 required for
+    /// the modern `invokespecial` semantics. See [ClassFile::has_super_semantics].
+    pub fn is_super(&self) -> bool {
+        self.contains(Self::ACC_SUPER)
     }
 }
 
 
 
+/// A broken rule of §4.1 on combining class access flags, from [ClassFile::verify_class_flags].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClassFlagConflict {
+    /// The first flags are set without the second.
+    Requires(ClassAccessFlags, ClassAccessFlags),
+    /// The first flags are set along with the second, which they exclude.
+    Excludes(ClassAccessFlags, ClassAccessFlags),
+}
+
+impl core::fmt::Display for ClassFlagConflict {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        match self {
+            Self::Requires(a, b) => write!(f, "{:?} requires {:?}", a, b),
+            Self::Excludes(a, b) => write!(f, "{:?} can't be combined with {:?}", a, b),
+        }
+    }
+}
+
 /// A class file.
+///
+/// A parsed class file has no interior mutability, so it is `Send`
+/// and `Sync` and can be shared between threads behind an `Arc`.
 #[derive(Debug)]
 pub struct ClassFile {
     /// The class file's version (major, minor).
@@ -100,7 +194,708 @@ pub struct ClassFile {
     /// that are inherited from superclasses or superinterfaces. 
     pub methods: Vec<MethodInfo>,
     /// Each value of the attributes table must be an attribute_info structure (§4.7). 
-    pub attributes: AttributesCollection
+    pub attributes: AttributesCollection,
+    /// The raw bytes this class file was parsed from, if
+    /// [ParseOptions::capture_source] was set.
+    source_bytes: Option<Vec<u8>>,
+}
+
+/// An `invokedynamic` call site resolved through its bootstrap method.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedIndy<'a> {
+    /// Name of the call site.
+    pub name: &'a str,
+    /// Method descriptor of the call site.
+    pub descriptor: &'a str,
+    /// The bootstrap method handle.
+    pub bootstrap_method: MethodHandleParts<'a>,
+    /// The static arguments passed to the bootstrap method.
+    pub static_arguments: Vec<BootstrapArgument<'a>>,
+}
+
+/// An entry of the `BootstrapMethods` attribute with its constant pool references resolved.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResolvedBootstrapMethod<'a> {
+    /// The bootstrap method handle.
+    pub method: MethodHandleParts<'a>,
+    /// The static arguments passed to the bootstrap method.
+    pub arguments: Vec<BootstrapArgument<'a>>,
+}
+
+/// The method an `invokespecial` instruction invokes, from [ClassFile::select_invokespecial].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectedMethod {
+    /// Internal name of the class declaring the method.
+    pub class_name: String,
+    /// Index of the method in that class's `methods`.
+    pub method_index: usize,
+}
+
+/// A method with its name and descriptor resolved from the constant pool.
+#[derive(Debug)]
+pub struct ResolvedMethod<'a> {
+    /// The method's access flags.
+    pub access_flags: MethodAccessFlags,
+    /// The method's name.
+    pub name: String,
+    /// The method's parsed descriptor.
+    pub descriptor: MethodDescriptor,
+    /// The method as stored in the class file, for its attributes.
+    pub info: &'a MethodInfo,
+}
+
+impl<'a> ResolvedMethod<'a> {
+    /// The `Code` attribute of this method, if it has one.
+    pub fn code(&self) -> Option<&'a CodeAttribute> {
+        self.info.code()
+    }
+
+    /// The attributes of this method.
+    pub fn attributes(&self) -> &'a AttributesCollection {
+        &self.info.attributes
+    }
+}
+
+/// A field with its name and descriptor resolved from the constant pool.
+#[derive(Debug)]
+pub struct ResolvedField<'a> {
+    /// The field's access flags.
+    pub access_flags: FieldAccessFlags,
+    /// The field's name.
+    pub name: String,
+    /// The field's parsed descriptor.
+    pub descriptor: FieldType,
+    /// The field as stored in the class file, for its attributes.
+    pub info: &'a FieldInfo,
+}
+
+impl<'a> ResolvedField<'a> {
+    /// The attributes of this field.
+    pub fn attributes(&self) -> &'a AttributesCollection {
+        &self.info.attributes
+    }
+}
+
+/// A class file parsed with [ClassFile::partial], missing the items which
+/// couldn't be parsed.
+#[derive(Debug)]
+pub struct PartialClassFile {
+    /// The class file, without the skipped fields, methods and attributes.
+    pub class_file: ClassFile,
+    /// Why each item was skipped, in the order they were found.
+    pub errors: Vec<RecoveredError>,
+}
+
+impl ClassFile {
+    /// Parse a class file from a byte slice, such as a memory-mapped file.
+    pub fn read_from_bytes(bytes: &[u8]) -> error::Result<Self> {
+        Self::read_from_bytes_with_options(bytes, ParseOptions::default())
+    }
+
+    /// Parse a class file from a byte slice with the given parse options.
+    pub fn read_from_bytes_with_options(bytes: &[u8], options: ParseOptions) -> error::Result<Self> {
+        Self::read_from_stream(&mut ClassFileStream::with_options(&mut Cursor::new(bytes), options), None)
+    }
+
+    /// Parse a class file from a byte slice with the given parse options,
+    /// appending any non-fatal issues found to `diagnostics`.
+    pub fn read_from_bytes_with_diagnostics(
+        bytes: &[u8],
+        options: ParseOptions,
+        diagnostics: &mut Vec<ParseDiagnostic>,
+    ) -> error::Result<Self> {
+        let mut cursor = Cursor::new(bytes);
+        let mut s = ClassFileStream::with_options(&mut cursor, options);
+        let class_file = Self::read_from_stream(&mut s, None);
+        diagnostics.append(&mut s.3);
+        class_file
+    }
+
+    /// Parse as much of a class file as possible, skipping malformed fields,
+    /// methods and attributes rather than failing, as with [ParseOptions::recover].
+    ///
+    /// Every item in a class file after the constant pool is either fixed
+    /// size or length-prefixed, so parsing resumes at the next item. Errors
+    /// in the header or constant pool are still fatal.
+    pub fn partial(bytes: &[u8], options: ParseOptions) -> error::Result<PartialClassFile> {
+        let mut cursor = Cursor::new(bytes);
+        let mut s = ClassFileStream::with_options(&mut cursor, ParseOptions { recover: true, ..options });
+        let class_file = Self::read_from_stream(&mut s, None)?;
+        Ok(PartialClassFile { class_file, errors: core::mem::take(&mut s.4) })
+    }
+
+    /// Whether this class file depends on preview features
+    /// of its Java SE release, signalled by a minor version of `0xFFFF`.
+    pub fn is_preview(&self) -> bool {
+        self.version.1 == 0xFFFF
+    }
+
+    /// The Java SE feature release of this class file's major version,
+    /// e.g. 8 for 52 and 17 for 61. Versions 45 through 48 map to 1 through 4,
+    /// i.e. Java 1.1 through 1.4.
+    pub fn java_version(&self) -> u32 {
+        (self.version.0 as u32).saturating_sub(44)
+    }
+
+    /// Whether this class is synthetic, marked by either
+    /// the `ACC_SYNTHETIC` flag or a `Synthetic` attribute.
+    pub fn is_synthetic(&self) -> bool {
+        self.access_flags.is_synthetic()
+            || self.attributes.first(attrtype::Synthetic).is_some()
+    }
+
+    /// Whether `invokespecial` in this class uses the semantics of `ACC_SUPER`,
+    /// searching for methods from the direct superclass.
+    ///
+    /// Since Java SE 8 the JVM considers `ACC_SUPER` set in every class file,
+    /// whatever its version. When `legacy_super` is set, the rule of older
+    /// JVMs is emulated instead, where only classes with the flag set have
+    /// these semantics. Compilers have set it since JDK 1.0.2.
+    pub fn has_super_semantics(&self, legacy_super: bool) -> bool {
+        !legacy_super || self.access_flags.is_super()
+    }
+
+    /// Select the method invoked by an `invokespecial` of the `Methodref` or
+    /// `InterfaceMethodref` at `cp_index` in this class, following JVMS 6.5.
+    ///
+    /// When the reference names a superclass of this class, isn't to an
+    /// instance initialization method, and this class
+    /// [has super semantics](Self::has_super_semantics) under `legacy_super`, the search starts
+    /// from the direct superclass, so `super.m()` reaches the nearest
+    /// override rather than the class named. Otherwise it starts from the
+    /// class named. Superclasses are then searched in order, and
+    /// `java/lang/Object` is searched for public methods of interfaces.
+    /// Default methods of superinterfaces aren't searched.
+    ///
+    /// Classes other than this one are loaded through `resolver`. Returns
+    /// `None` if no method is found, including when the superclasses
+    /// form a cycle.
+    pub fn select_invokespecial<F: Fn(&str) -> Option<ClassFile>>(
+        &self,
+        cp_index: u16,
+        legacy_super: bool,
+        resolver: F,
+    ) -> error::Result<Option<SelectedMethod>> {
+        let method = self.constant_pool.resolve_methodref(cp_index as usize)?;
+        let this_name = self.class_name()?;
+        let super_name = self.super_class_name()?;
+        let start = match super_name {
+            Some(super_name)
+                if method.name != "<init>"
+                    && !method.interface
+                    && self.has_super_semantics(legacy_super)
+                    && self.is_superclass(method.class_name, &resolver)? =>
+            {
+                super_name
+            }
+            _ => method.class_name,
+        };
+
+        let mut from_interface = false;
+        let mut next = Some(start.to_string());
+        let mut visited = BTreeSet::new();
+        while let Some(name) = next.take() {
+            if !visited.insert(name.clone()) {
+                break;
+            }
+            let loaded;
+            let class = if name == this_name {
+                self
+            } else {
+                match resolver(&name) {
+                    Some(v) => {
+                        loaded = v;
+                        &loaded
+                    }
+                    None => break,
+                }
+            };
+            from_interface |= class.access_flags.is_interface();
+            let cp = &class.constant_pool;
+            let found = class.methods.iter().position(|m| {
+                !m.access_flags.is_static()
+                    && (!from_interface || name != "java/lang/Object" || m.access_flags.is_public())
+                    && cp.get_utf8_constant(m.name_index as usize).ok() == Some(method.name)
+                    && cp.get_utf8_constant(m.descriptor_index as usize).ok() == Some(method.descriptor)
+            });
+            if let Some(method_index) = found {
+                return Ok(Some(SelectedMethod { class_name: name, method_index }));
+            }
+            next = class.super_class_name()?.map(|v| v.to_string());
+        }
+        Ok(None)
+    }
+
+    /// Whether `name` is a superclass of this class, loading superclasses through `resolver`.
+    fn is_superclass(&self, name: &str, resolver: &dyn Fn(&str) -> Option<ClassFile>) -> error::Result<bool> {
+        let mut next = self.super_class_name()?.map(|v| v.to_string());
+        let mut visited = BTreeSet::new();
+        while let Some(current) = next.take() {
+            if current == name {
+                return Ok(true);
+            }
+            if !visited.insert(current.clone()) {
+                break;
+            }
+            next = match resolver(&current) {
+                Some(class) => class.super_class_name()?.map(|v| v.to_string()),
+                None => None,
+            };
+        }
+        Ok(false)
+    }
+
+    /// Whether this class has a `Deprecated` attribute. There is
+    /// no access flag for deprecation.
+    pub fn is_deprecated(&self) -> bool {
+        self.attributes.first(attrtype::Deprecated).is_some()
+    }
+
+    /// Parse this class's `Signature` attribute, returning `None`
+    /// if the class has no generic signature.
+    pub fn generic_signature(&self) -> Option<error::Result<ClassSignature>> {
+        let index = self.attributes.signature_index()?;
+        Some(self.constant_pool.get_utf8_constant(index as usize).and_then(|v| {
+            ClassSignature::new(v).ok_or(ClassFileError::MalformedSignature(index))
+        }))
+    }
+
+    /// The internal name of this class.
+    pub fn class_name(&self) -> error::Result<&str> {
+        self.constant_pool.get_class_name(self.this_class as usize)
+    }
+
+    /// The internal name of the direct superclass, if there is one.
+    pub fn super_class_name(&self) -> error::Result<Option<&str>> {
+        if self.super_class == 0 {
+            return Ok(None);
+        }
+        self.constant_pool.get_class_name(self.super_class as usize).map(Some)
+    }
+
+    /// The internal names of the direct superinterfaces.
+    pub fn interface_names(&self) -> error::Result<Vec<&str>> {
+        self.interfaces
+            .iter()
+            .map(|v| self.constant_pool.get_class_name(*v as usize))
+            .collect()
+    }
+
+    /// The text of every `String` constant, in constant pool order. These are
+    /// the strings the class can load with `ldc`, decoded from modified UTF-8,
+    /// with any unpaired surrogate replaced by U+FFFD.
+    ///
+    /// A `String` constant that doesn't refer to a `Utf8` constant is skipped.
+    pub fn string_constants(&self) -> Vec<String> {
+        let cp = &self.constant_pool;
+        cp.entries
+            .iter()
+            .filter_map(|entry| match entry {
+                ConstantPoolEntry::String { string_index } => cp.get_utf8_constant(*string_index as usize).ok(),
+                _ => None,
+            })
+            .map(String::from)
+            .collect()
+    }
+
+    /// [ClassFile::string_constants] without duplicates, keeping the first of each.
+    pub fn unique_string_constants(&self) -> Vec<String> {
+        let mut seen = BTreeSet::new();
+        self.string_constants().into_iter().filter(|v| seen.insert(v.clone())).collect()
+    }
+
+    /// The number of local variable slots a method's arguments occupy,
+    /// including `this` for instance methods. This is the smallest valid
+    /// `max_locals` for the method.
+    pub fn argument_slot_count(&self, method: &MethodInfo) -> error::Result<usize> {
+        let descriptor = self.constant_pool.get_utf8_constant(method.descriptor_index as usize)?;
+        let descriptor = parse_descriptor::<MethodDescriptor>(descriptor)?;
+        let this = !method.access_flags.is_static();
+        Ok(descriptor.argument_slot_count() + this as usize)
+    }
+
+    /// The methods of this class with their names and descriptors resolved.
+    pub fn resolved_methods(&self) -> error::Result<Vec<ResolvedMethod<'_>>> {
+        self.methods
+            .iter()
+            .map(|info| {
+                let (name, descriptor) = self.resolve_member(info.name_index, info.descriptor_index)?;
+                Ok(ResolvedMethod { access_flags: info.access_flags, name, descriptor, info })
+            })
+            .collect()
+    }
+
+    /// The fields of this class with their names and descriptors resolved.
+    pub fn resolved_fields(&self) -> error::Result<Vec<ResolvedField<'_>>> {
+        self.fields
+            .iter()
+            .map(|info| {
+                let (name, descriptor) = self.resolve_member(info.name_index, info.descriptor_index)?;
+                Ok(ResolvedField { access_flags: info.access_flags, name, descriptor, info })
+            })
+            .collect()
+    }
+
+    /// Look up the name of a field or method, and parse its descriptor.
+    fn resolve_member<T: Parseable>(&self, name_index: u16, descriptor_index: u16) -> error::Result<(String, T)> {
+        let name = self.constant_pool.get_utf8_constant(name_index as usize)?;
+        let descriptor = parse_descriptor(self.constant_pool.get_utf8_constant(descriptor_index as usize)?)?;
+        Ok((name.to_string(), descriptor))
+    }
+
+    /// Whether `method`, declared in this class, overrides a method declared
+    /// in `super_class_file`, following the rules of JVMS 5.4.5.
+    ///
+    /// Constructors, static and private methods never override. The
+    /// overridden method must have the same name and descriptor, and must
+    /// not be private or static; if it is package-private, both classes must
+    /// be in the same package.
+    pub fn overrides(&self, method: &MethodInfo, super_class_file: &ClassFile) -> bool {
+        let cp = &self.constant_pool;
+        let name = match cp.get_utf8_constant(method.name_index as usize) {
+            Ok(v) => v,
+            Err(_) => return false,
+        };
+        if name == "<init>" || name == "<clinit>" || method.access_flags.is_static() || method.access_flags.is_private() {
+            return false;
+        }
+        let descriptor = match cp.get_utf8_constant(method.descriptor_index as usize).ok().and_then(|v| parse_descriptor::<MethodDescriptor>(v).ok()) {
+            Some(v) => v,
+            None => return false,
+        };
+        let same_package = match (self.class_name(), super_class_file.class_name()) {
+            (Ok(a), Ok(b)) => package_of(a) == package_of(b),
+            _ => false,
+        };
+
+        let super_cp = &super_class_file.constant_pool;
+        super_class_file.methods.iter().any(|sup| {
+            let flags = &sup.access_flags;
+            if flags.is_private() || flags.is_static() {
+                return false;
+            }
+            if !(flags.is_public() || flags.is_protected() || same_package) {
+                return false;
+            }
+            if super_cp.get_utf8_constant(sup.name_index as usize).ok() != Some(name) {
+                return false;
+            }
+            super_cp
+                .get_utf8_constant(sup.descriptor_index as usize)
+                .ok()
+                .and_then(|v| parse_descriptor::<MethodDescriptor>(v).ok())
+                .is_some_and(|v| descriptor.same_signature(&v))
+        })
+    }
+
+    /// Resolve the `InvokeDynamic` constant at `cp_index` to its name,
+    /// descriptor, bootstrap method and static arguments, looking up the
+    /// bootstrap method in this class's `BootstrapMethods` attribute.
+    pub fn resolve_invoke_dynamic(&self, cp_index: u16) -> error::Result<ResolvedIndy<'_>> {
+        let cp = &self.constant_pool;
+        let (bootstrap_index, name_and_type_index) = match cp.get_constant(cp_index as usize)? {
+            ConstantPoolEntry::InvokeDynamic { bootstrap_method_attr_index, name_and_type_index } => {
+                (*bootstrap_method_attr_index, *name_and_type_index)
+            }
+            _ => return Err(ClassFileError::UnexpectedConstantType(cp_index as usize)),
+        };
+        let (name, descriptor) = cp.get_name_and_type(name_and_type_index as usize)?;
+        let bootstrap = self
+            .attributes
+            .get(attrtype::BootstrapMethods)
+            .find_map(|v| match v {
+                Attributes::BootstrapMethods { bootstrap_methods } => bootstrap_methods.get(bootstrap_index as usize),
+                _ => None,
+            })
+            .ok_or(ClassFileError::BadBootstrapMethodIndex(bootstrap_index))?;
+        let bootstrap = self.resolve_bootstrap_method(bootstrap)?;
+        Ok(ResolvedIndy {
+            name,
+            descriptor,
+            bootstrap_method: bootstrap.method,
+            static_arguments: bootstrap.arguments,
+        })
+    }
+
+    /// Resolve every entry of this class's `BootstrapMethods` attribute,
+    /// in order. Classes without the attribute have no bootstrap methods.
+    pub fn bootstrap_methods(&self) -> error::Result<Vec<ResolvedBootstrapMethod<'_>>> {
+        self.attributes
+            .get(attrtype::BootstrapMethods)
+            .flat_map(|v| match v {
+                Attributes::BootstrapMethods { bootstrap_methods } => bootstrap_methods.as_slice(),
+                _ => &[],
+            })
+            .map(|v| self.resolve_bootstrap_method(v))
+            .collect()
+    }
+
+    fn resolve_bootstrap_method(&self, element: &BootstrapMethodsElement) -> error::Result<ResolvedBootstrapMethod<'_>> {
+        let cp = &self.constant_pool;
+        Ok(ResolvedBootstrapMethod {
+            method: cp.resolve_method_handle(element.bootstrap_method_ref as usize)?,
+            arguments: element
+                .bootstrap_arguments
+                .iter()
+                .map(|v| cp.resolve_bootstrap_argument(*v as usize))
+                .collect::<error::Result<_>>()?,
+        })
+    }
+
+    /// Check this class's access flags against the combinations §4.1 allows:
+    /// a module has no other flags, an annotation is an interface, an
+    /// interface is abstract and not final, super, an enum or a module, and a
+    /// class isn't both final and abstract.
+    pub fn verify_class_flags(&self) -> error::Result<()> {
+        use ClassAccessFlags as F;
+        let flags = self.access_flags;
+        let conflict = if flags.is_module() && flags != F::ACC_MODULE {
+            Some(ClassFlagConflict::Excludes(F::ACC_MODULE, flags - F::ACC_MODULE))
+        } else if flags.is_annotation() && !flags.is_interface() {
+            Some(ClassFlagConflict::Requires(F::ACC_ANNOTATION, F::ACC_INTERFACE))
+        } else if flags.is_interface() && !flags.is_abstract() {
+            Some(ClassFlagConflict::Requires(F::ACC_INTERFACE, F::ACC_ABSTRACT))
+        } else if flags.is_interface() && flags.intersects(F::ACC_FINAL | F::ACC_SUPER | F::ACC_ENUM) {
+            Some(ClassFlagConflict::Excludes(F::ACC_INTERFACE, flags & (F::ACC_FINAL | F::ACC_SUPER | F::ACC_ENUM)))
+        } else if flags.contains(F::ACC_FINAL | F::ACC_ABSTRACT) {
+            Some(ClassFlagConflict::Excludes(F::ACC_FINAL, F::ACC_ABSTRACT))
+        } else {
+            None
+        };
+        conflict.map_or(Ok(()), |v| Err(ClassFileError::BadClassFlagCombination(v)))
+    }
+
+    /// Check the access flags of this class and its fields and methods
+    /// against the combinations the specification allows, collecting every
+    /// invalid set of flags found.
+    pub fn verify_access_flags(&self) -> core::result::Result<(), Vec<ClassFileError>> {
+        let mut errors = vec![];
+        if let Err(e) = self.verify_class_flags() {
+            errors.push(e);
+        }
+        for field in self.fields.iter() {
+            if !field.access_flags.is_valid_combination() {
+                errors.push(ClassFileError::BadFieldAccessFlags);
+            }
+        }
+        for method in self.methods.iter() {
+            if !method.access_flags.is_valid_combination() {
+                errors.push(ClassFileError::BadMethodAccessFlags);
+            }
+        }
+        errors.is_empty().then_some(()).ok_or(errors)
+    }
+
+    /// Statically verify the code of every method, including that its return
+    /// instructions match its descriptor, that its `max_locals` holds its
+    /// arguments and locals and that its stack map frames keep to
+    /// `max_locals`, collecting the first error found in each method.
+    pub fn verify_code(&self) -> core::result::Result<(), Vec<ClassFileError>> {
+        let mut errors = vec![];
+        for (index, method) in self.methods.iter().enumerate() {
+            if let Some(code) = method.code() {
+                let result = code.static_verify(self).and_then(|()| {
+                    let descriptor = self
+                        .constant_pool
+                        .get_utf8_constant(method.descriptor_index as usize)
+                        .map_err(CodeVerificationError::ClassFileError)?;
+                    let descriptor =
+                        parse_descriptor::<MethodDescriptor>(descriptor).map_err(CodeVerificationError::ClassFileError)?;
+                    code.code.verify_return_types(&descriptor)?;
+                    code.code
+                        .verify_max_locals(&descriptor, method.access_flags.is_static(), code.max_locals as usize)?;
+                    let name = self
+                        .constant_pool
+                        .get_utf8_constant(method.name_index as usize)
+                        .map_err(CodeVerificationError::ClassFileError)?;
+                    // only the number of locals is checked, so the classes of references don't matter
+                    let initial = initial_stack_frame(&descriptor, method.access_flags.is_static(), name == "<init>", self.this_class, |_| Ok(0))
+                        .map_err(CodeVerificationError::ClassFileError)?;
+                    code.verify_stack_map_locals(&initial)
+                });
+                if let Err(e) = result {
+                    errors.push(ClassFileError::CodeVerification(index, Box::new(e)));
+                }
+            }
+        }
+        errors.is_empty().then_some(()).ok_or(errors)
+    }
+
+    /// Run every available verification pass, collecting all the errors
+    /// found rather than stopping at the first.
+    ///
+    /// The passes are the version check, [ConstantPool::verify_no_cycles],
+    /// [ConstantPool::verify_structure] (which includes
+    /// [ConstantPool::verify_cp_index_types]),
+    /// [ClassFile::verify_access_flags] and [ClassFile::verify_code].
+    /// Code is only verified when the constant pool is valid.
+    ///
+    /// Every major version from 45 (JDK 1.0 and 1.1) on is accepted. The
+    /// passes gated on the version are:
+    /// - the version check itself, which rejects anything before 45.0;
+    /// - the `InnerClasses` rule that an entry without an `inner_name_index`
+    ///   has no `outer_class_info_index`, only checked from 51.0.
+    ///
+    /// No pass requires a `StackMapTable`, so code from before 50.0, which
+    /// never has one, verifies the same as newer code.
+    pub fn verify_all(&self) -> core::result::Result<(), Vec<ClassFileError>> {
+        let mut errors = vec![];
+        if self.version.0 < 45 {
+            errors.push(ClassFileError::UnsupportedVersion(self.version.0, self.version.1));
+        }
+        let cp = &self.constant_pool;
+        let cp_start = errors.len();
+        if let Err(e) = cp.verify_no_cycles() {
+            errors.push(e);
+        }
+        if errors.len() == cp_start {
+            if let Err(e) = cp.verify_structure(self) {
+                errors.push(ClassFileError::ConstantPoolVerification(Box::new(e)));
+            }
+        }
+        let cp_valid = errors.len() == cp_start;
+        if let Err(e) = self.verify_access_flags() {
+            errors.extend(e);
+        }
+        if cp_valid {
+            if let Err(e) = self.verify_code() {
+                errors.extend(e);
+            }
+        }
+        errors.is_empty().then_some(()).ok_or(errors)
+    }
+
+    /// Rename the class `old` to `new`, both internal names.
+    ///
+    /// Every class name, descriptor and signature in the constant pool
+    /// which refers to `old` is rewritten, including array types such as
+    /// `[Lold;`, types inside method descriptors, and the annotation types,
+    /// enum types and class literals of annotations. `String` constants
+    /// sharing a Utf8 entry with a rewritten name keep their original text.
+    ///
+    /// Fails with `ConstantPoolFull`, leaving the class unchanged, if the
+    /// constant pool has no room for the copies those strings need.
+    pub fn rename(&mut self, old: &str, new: &str) -> error::Result<()> {
+        let mut names = BTreeSet::new();
+        let mut descriptors = BTreeSet::new();
+        for entry in &self.constant_pool.entries {
+            match entry {
+                ConstantPoolEntry::Class { name_index } => {
+                    names.insert(*name_index);
+                }
+                ConstantPoolEntry::NameAndType { descriptor_index, .. }
+                | ConstantPoolEntry::MethodType { descriptor_index } => {
+                    descriptors.insert(*descriptor_index);
+                }
+                _ => (),
+            }
+        }
+        descriptors.extend(self.attributes.signature_index());
+        descriptors.extend(self.attributes.annotation_descriptor_indices());
+        for field in &self.fields {
+            descriptors.insert(field.descriptor_index);
+            descriptors.extend(field.attributes.signature_index());
+            descriptors.extend(field.attributes.annotation_descriptor_indices());
+        }
+        for method in &self.methods {
+            descriptors.insert(method.descriptor_index);
+            descriptors.extend(method.attributes.signature_index());
+            descriptors.extend(method.attributes.annotation_descriptor_indices());
+            let Some(code) = method.code() else { continue };
+            descriptors.extend(code.attributes.annotation_descriptor_indices());
+            for (_, attribute) in code.attributes.iter_ordered() {
+                match attribute {
+                    Attributes::LocalVariableTable { local_variable_table } => {
+                        descriptors.extend(local_variable_table.iter().map(|v| v.descriptor_index));
+                    }
+                    Attributes::LocalVariableTypeTable { local_variable_type_table } => {
+                        descriptors.extend(local_variable_type_table.iter().map(|v| v.signature_index));
+                    }
+                    _ => (),
+                }
+            }
+        }
+        for component in self.attributes.get(attrtype::Record).flat_map(|v| match v {
+            Attributes::Record { components } => components.as_slice(),
+            _ => &[],
+        }) {
+            descriptors.insert(component.descriptor_index);
+            descriptors.extend(component.attributes.signature_index());
+            descriptors.extend(component.attributes.annotation_descriptor_indices());
+        }
+
+        let renamed = |index: u16| -> Option<String> {
+            let v = self.constant_pool.get_utf8_constant(index as usize).ok()?;
+            if names.contains(&index) && v == old {
+                return Some(new.to_string());
+            }
+            if descriptors.contains(&index) || (names.contains(&index) && v.starts_with('[')) {
+                return rename_in_descriptor(v, old, new);
+            }
+            None
+        };
+        let renamed: Vec<(u16, String)> = names
+            .iter()
+            .chain(descriptors.iter())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .filter_map(|index| Some((*index, renamed(*index)?)))
+            .collect();
+
+        // each rewritten entry shared with a string constant needs a copy,
+        // and the constant pool count is a u2 which includes the unused index 0
+        let copies = renamed
+            .iter()
+            .filter(|(index, _)| {
+                self.constant_pool
+                    .entries
+                    .iter()
+                    .any(|v| matches!(v, ConstantPoolEntry::String { string_index } if string_index == index))
+            })
+            .count();
+        if self.constant_pool.entries.len() + copies > u16::MAX as usize - 1 {
+            return Err(ClassFileError::ConstantPoolFull);
+        }
+
+        for (index, data) in renamed {
+            let original = core::mem::replace(
+                &mut self.constant_pool.entries[index as usize - 1],
+                ConstantPoolEntry::utf8(data),
+            );
+            // give string constants sharing this entry their own copy of the old text
+            let mut copy = None;
+            for i in 0..self.constant_pool.entries.len() {
+                if let ConstantPoolEntry::String { string_index } = &self.constant_pool.entries[i] {
+                    if *string_index == index {
+                        let copy = *copy.get_or_insert_with(|| {
+                            self.constant_pool.entries.push(original.clone());
+                            self.constant_pool.entries.len() as u16
+                        });
+                        self.constant_pool.entries[i] = ConstantPoolEntry::String { string_index: copy };
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The raw bytes consumed while parsing this class file, or `None`
+    /// unless it was parsed with [ParseOptions::capture_source].
+    pub fn source_bytes(&self) -> Option<&[u8]> {
+        self.source_bytes.as_deref()
+    }
+
+    /// A SHA-256 fingerprint of this class file, usable as a
+    /// cache key to detect whether a class has changed.
+    ///
+    /// This currently hashes the [source bytes](Self::source_bytes), so
+    /// a recompile which only reorders the constant pool changes the hash,
+    /// and is `None` when they weren't captured.
+    #[cfg(feature = "sha2")]
+    pub fn structural_hash(&self) -> Option<[u8; 32]> {
+        use sha2::{Digest, Sha256};
+        self.source_bytes.as_ref().map(|v| Sha256::digest(v).into())
+    }
 }
 
 impl ClassFileItem for ClassFile {
@@ -108,6 +903,183 @@ impl ClassFileItem for ClassFile {
     where
         Self: Sized,
     {
+        if !s.options().capture_source {
+            return Self::read_contents(s);
+        }
+        let mut options = s.options();
+        // the inner stream counts from zero, so only gets what is left of the limit
+        options.max_bytes = options.max_bytes.map(|max| max.saturating_sub(s.1));
+        let mut recorder = RecordingReader::new(s.0);
+        let mut inner = ClassFileStream::with_options(&mut recorder, options);
+        let class_file = Self::read_contents(&mut inner);
+        let read = inner.1;
+        let mut diagnostics = core::mem::take(&mut inner.3);
+        let mut recovered = core::mem::take(&mut inner.4);
+        let mut class_file = class_file?;
+        class_file.source_bytes = Some(recorder.into_bytes());
+        s.advance(read)?;
+        s.3.append(&mut diagnostics);
+        s.4.append(&mut recovered);
+        Ok(class_file)
+    }
+}
+
+/// Rewrite the class types `Lold;` in a descriptor or signature to `Lnew;`,
+/// returning `None` if there are none.
+fn rename_in_descriptor(descriptor: &str, old: &str, new: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut changed = false;
+    let mut rest = descriptor;
+    // the end of an identifier starting at the beginning of `v`
+    let identifier = |v: &str| v.find(['.', ';', '[', '/', '<', '>', ':']).unwrap_or(v.len());
+    while let Some(c) = rest.chars().next() {
+        let end = identifier(rest);
+        let consumed = if rest[end..].starts_with(':') {
+            // a type parameter name, such as `T` in `<T:Ljava/lang/Object;>`
+            end + 1
+        } else if c == 'L' {
+            let end = rest.find([';', '<', '.']).unwrap_or(rest.len());
+            out.push('L');
+            if &rest[1..end] == old {
+                out.push_str(new);
+                changed = true;
+            } else {
+                out.push_str(&rest[1..end]);
+            }
+            rest = &rest[end..];
+            continue;
+        } else if c == 'T' {
+            // a type variable
+            rest.find(';').map(|v| v + 1).unwrap_or(rest.len())
+        } else if c == '.' {
+            // the simple name of an inner class
+            1 + identifier(&rest[1..])
+        } else {
+            c.len_utf8()
+        };
+        out.push_str(&rest[..consumed]);
+        rest = &rest[consumed..];
+    }
+    changed.then_some(out)
+}
+
+/// Whether a value of the type `sub` can be assigned to the type `sup`.
+///
+/// Both are internal class names, or descriptors for array types (e.g.
+/// `[Ljava/lang/String;`). Superclasses and superinterfaces are found by
+/// loading classes through `resolver`; classes it can't resolve are
+/// treated as having no supertypes other than `java/lang/Object`.
+pub fn is_assignable<F: Fn(&str) -> Option<ClassFile>>(sub: &str, sup: &str, resolver: F) -> bool {
+    is_assignable_inner(sub, sup, &resolver)
+}
+
+fn is_assignable_inner(sub: &str, sup: &str, resolver: &dyn Fn(&str) -> Option<ClassFile>) -> bool {
+    if sub == sup || sup == "java/lang/Object" {
+        return true;
+    }
+
+    if let Some(sub_element) = sub.strip_prefix('[') {
+        return match sup.strip_prefix('[') {
+            Some(sup_element) => match (element_class(sub_element), element_class(sup_element)) {
+                (Some(sub_element), Some(sup_element)) => is_assignable_inner(sub_element, sup_element, resolver),
+                // primitive element types must match exactly
+                _ => sub_element == sup_element,
+            },
+            None => sup == "java/lang/Cloneable" || sup == "java/io/Serializable",
+        };
+    }
+    if sup.starts_with('[') {
+        return false;
+    }
+
+    let mut visited = BTreeSet::new();
+    let mut pending = vec![sub.to_string()];
+    while let Some(name) = pending.pop() {
+        if !visited.insert(name.clone()) {
+            continue;
+        }
+        let Some(class) = resolver(&name) else {
+            continue;
+        };
+        let supers = class.super_class_name().ok().flatten().into_iter();
+        let interfaces = class.interface_names().unwrap_or_default().into_iter();
+        for parent in supers.chain(interfaces) {
+            if parent == sup {
+                return true;
+            }
+            pending.push(parent.to_string());
+        }
+    }
+    false
+}
+
+/// The class name or array descriptor of an array's element type,
+/// or `None` if it is a primitive type.
+fn element_class(element: &str) -> Option<&str> {
+    if element.starts_with('[') {
+        return Some(element);
+    }
+    element.strip_prefix('L')?.strip_suffix(';')
+}
+
+/// Parse a field or method descriptor.
+fn parse_descriptor<T: Parseable>(descriptor: &str) -> error::Result<T> {
+    let mut stream = Lexer::stream(Lexer::new(), descriptor.to_string());
+    stream
+        .token::<T>()
+        .map(|v| v.token)
+        .map_err(|(e, _)| ClassFileError::MalformedDescriptor(e))
+}
+
+/// Read `count` fields or methods. With [ParseOptions::recover], each member
+/// is read on its own so a malformed one can be skipped, recorded as `item`.
+fn read_members<T: ClassFileItem, R: Read>(
+    s: &mut ClassFileStream<R>,
+    cp: &ConstantPool,
+    count: usize,
+    item: fn(usize) -> SkippedItem,
+) -> error::Result<Vec<T>> {
+    if !s.options().recover {
+        return s.read_sequence(Some(cp), count);
+    }
+    let mut members = vec![];
+    for index in 0..count {
+        let bytes = read_member_bytes(s)?;
+        // the bytes were already counted against any limit
+        let options = ParseOptions { max_bytes: None, ..s.options() };
+        let mut cursor = Cursor::new(&bytes);
+        let mut inner = ClassFileStream::with_options(&mut cursor, options);
+        let member = T::read_from_stream(&mut inner, Some(cp));
+        s.absorb(&mut inner);
+        match member {
+            Ok(v) => members.push(v),
+            Err(e) => s.recover(item(index), e)?,
+        }
+    }
+    Ok(members)
+}
+
+/// Read the raw bytes of a field or method: its access flags, name and
+/// descriptor indices, and its attribute table.
+fn read_member_bytes<R: Read>(s: &mut ClassFileStream<R>) -> error::Result<Vec<u8>> {
+    let mut bytes = s.read_dynamic(8)?;
+    let attributes_count = u16::from_be_bytes([bytes[6], bytes[7]]);
+    for _ in 0..attributes_count {
+        let header = s.read_dynamic(6)?;
+        let attribute_length = u32::from_be_bytes([header[2], header[3], header[4], header[5]]);
+        bytes.extend(header);
+        bytes.extend(s.read_dynamic(attribute_length as usize)?);
+    }
+    Ok(bytes)
+}
+
+/// The package of an internal class name, which is empty for the unnamed package.
+fn package_of(class_name: &str) -> &str {
+    class_name.rsplit_once('/').map_or("", |v| v.0)
+}
+
+impl ClassFile {
+    fn read_contents<R: Read>(s: &mut ClassFileStream<R>) -> error::Result<Self> {
         // check magic number
         let magic = s.read_u4()?;
         if magic != CLASS_MAGIC {
@@ -118,17 +1090,12 @@ impl ClassFileItem for ClassFile {
         let minor_version = s.read_u2()?;
         let major_version = s.read_u2()?;
 
-        println!("Major: {}", major_version);
-
-        println!("Minor: {}", minor_version);
-
 
         // read constant pool
         let constant_pool = ConstantPool::read_from_stream(s, None)?;
 
         // read access flags
-        let access_flags =
-            ClassAccessFlags::from_bits(s.read_u2()?).ok_or(ClassFileError::BadClassAccessFlags)?;
+        let access_flags = ClassAccessFlags::from_bits_checked(s.read_u2()?, s.options().tolerant)?;
 
         // read this class & super class
         let this_class = s.read_u2()?;
@@ -140,14 +1107,14 @@ impl ClassFileItem for ClassFile {
 
         // read fields
         let fields_count = s.read_u2()?;
-        let fields = s.read_sequence(Some(&constant_pool), fields_count as usize)?;
+        let fields = read_members(s, &constant_pool, fields_count as usize, SkippedItem::Field)?;
 
         // read methods
         let methods_count = s.read_u2()?;
-        let methods = s.read_sequence(Some(&constant_pool), methods_count as usize)?;
+        let methods = read_members(s, &constant_pool, methods_count as usize, SkippedItem::Method)?;
 
         // read attributes
-        let attributes = AttributesCollection::read_from_stream(s, Some(&constant_pool))?;
+        let attributes = AttributesCollection::read_in_context(s, Some(&constant_pool), AttributeContext::ClassFile)?;
 
         Ok(Self {
             version: (major_version, minor_version),
@@ -158,18 +1125,601 @@ impl ClassFileItem for ClassFile {
             interfaces,
             fields,
             methods,
-            attributes
+            attributes,
+            source_bytes: None,
         })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use std::io::Cursor;
+    use alloc::{string::ToString, vec, vec::Vec};
+
+    use exo_parser::Lexer;
+
+    use crate::item::{attribute_info::{attrtype, AttributeContext, Attributes, ElementValue}, ids::method::MethodDescriptor, opcodes::VMOpcode, ClassFileItem};
+
+    use crate::{
+        item::{
+            constant_pool::{BootstrapArgument, ConstantPool, ConstantPoolEntry, ConstantPoolVerificationError, RefKind},
+            ids::NameError,
+            attribute_info::{AttributesCollection, BootstrapMethodsElement, ClassArrayEntry},
+        },
+        error::ClassFileError,
+        io::Cursor,
+        stream::{ParseOptions, SkippedItem},
+    };
+
+    use super::{is_assignable, parse_descriptor, ClassAccessFlags, ClassFile, ClassFlagConflict};
+    use crate::item::methods::MethodAccessFlags;
+
+    /// A class named `name` with the given superclass and interfaces.
+    fn class(name: &str, super_class: Option<&str>, interfaces: &[&str]) -> ClassFile {
+        let mut entries = vec![];
+        let mut add_class = |name: &str| {
+            entries.push(ConstantPoolEntry::utf8(name.to_string()));
+            entries.push(ConstantPoolEntry::Class { name_index: entries.len() as u16 });
+            entries.len() as u16
+        };
+        let this_class = add_class(name);
+        let super_class = super_class.map(&mut add_class).unwrap_or(0);
+        let interfaces = interfaces.iter().map(|v| add_class(v)).collect();
+        ClassFile {
+            version: (52, 0),
+            constant_pool: ConstantPool { entries },
+            access_flags: ClassAccessFlags::ACC_PUBLIC,
+            this_class,
+            super_class,
+            interfaces,
+            fields: vec![],
+            methods: vec![],
+            attributes: AttributesCollection::new(),
+            source_bytes: None,
+        }
+    }
+
+    #[test]
+    fn resolve_invoke_dynamic_test() {
+        let mut class_file = class("A", None, &[]);
+        let utf8 = |s: &str| ConstantPoolEntry::utf8(s.to_string());
+        class_file.constant_pool.entries.extend([
+            utf8("java/lang/invoke/LambdaMetafactory"),
+            ConstantPoolEntry::Class { name_index: 3 },
+            utf8("metafactory"),
+            utf8("(Ljava/lang/invoke/MethodHandles$Lookup;)Ljava/lang/invoke/CallSite;"),
+            ConstantPoolEntry::NameAndType { name_index: 5, descriptor_index: 6 },
+            ConstantPoolEntry::Methodref { class_index: 4, name_and_type_index: 7 },
+            ConstantPoolEntry::MethodHandle { reference_kind: RefKind::REF_invokeStatic, reference_index: 8 },
+            utf8("run"),
+            utf8("()Ljava/lang/Runnable;"),
+            ConstantPoolEntry::NameAndType { name_index: 10, descriptor_index: 11 },
+            ConstantPoolEntry::InvokeDynamic { bootstrap_method_attr_index: 0, name_and_type_index: 12 },
+            utf8("()V"),
+            ConstantPoolEntry::MethodType { descriptor_index: 14 },
+            ConstantPoolEntry::Integer { bytes: 7 },
+        ]);
+        assert!(matches!(class_file.resolve_invoke_dynamic(13), Err(ClassFileError::BadBootstrapMethodIndex(0))));
+        assert!(class_file.bootstrap_methods().unwrap().is_empty());
+
+        class_file.attributes.push(
+            attrtype::BootstrapMethods,
+            Attributes::BootstrapMethods {
+                bootstrap_methods: vec![BootstrapMethodsElement { bootstrap_method_ref: 9, bootstrap_arguments: vec![15, 16] }],
+            },
+        );
+        let indy = class_file.resolve_invoke_dynamic(13).unwrap();
+        assert_eq!((indy.name, indy.descriptor), ("run", "()Ljava/lang/Runnable;"));
+        assert_eq!(indy.bootstrap_method.kind, RefKind::REF_invokeStatic);
+        assert_eq!(indy.bootstrap_method.class_name, "java/lang/invoke/LambdaMetafactory");
+        assert_eq!(indy.bootstrap_method.name, "metafactory");
+        assert_eq!(indy.static_arguments, [BootstrapArgument::MethodType("()V"), BootstrapArgument::Int(7)]);
+        assert!(matches!(class_file.resolve_invoke_dynamic(12), Err(ClassFileError::UnexpectedConstantType(12))));
+
+        let bootstrap_methods = class_file.bootstrap_methods().unwrap();
+        assert_eq!(bootstrap_methods.len(), 1);
+        assert_eq!(bootstrap_methods[0].method, indy.bootstrap_method);
+        assert_eq!(bootstrap_methods[0].arguments, indy.static_arguments);
+    }
+
+    #[test]
+    fn unknown_access_flags_test() {
+        assert_eq!(ClassAccessFlags::from_bits_checked(0x0021, false).unwrap(), ClassAccessFlags::ACC_PUBLIC | ClassAccessFlags::ACC_SUPER);
+        assert!(matches!(ClassAccessFlags::from_bits_checked(0x0041, false), Err(ClassFileError::UnknownClassAccessFlags(0x0040))));
+        let flags = ClassAccessFlags::from_bits_checked(0x0041, true).unwrap();
+        assert_eq!(flags.bits(), 0x0041);
+        assert!(flags.contains(ClassAccessFlags::ACC_PUBLIC));
+
+        // set a bit unassigned by the specification in a real class file
+        let file = include_bytes!("../../../local/Test.class");
+        let class_file = ClassFile::read_from_bytes(file).unwrap();
+        let mut tail = class_file.access_flags.bits().to_be_bytes().to_vec();
+        tail.extend(class_file.this_class.to_be_bytes());
+        tail.extend(class_file.super_class.to_be_bytes());
+        let offset = file.windows(tail.len()).position(|v| v == tail).unwrap();
+        let mut bytes = file.to_vec();
+        bytes[offset] |= 0x08;
+        assert!(matches!(ClassFile::read_from_bytes(&bytes), Err(ClassFileError::UnknownClassAccessFlags(0x0800))));
+        let tolerant = ClassFile::read_from_bytes_with_options(&bytes, ParseOptions::tolerant()).unwrap();
+        assert_eq!(tolerant.access_flags.bits(), class_file.access_flags.bits() | 0x0800);
+    }
+
+    #[test]
+    fn unknown_attribute_test() {
+        // rename the SourceFile attribute to a name the parser doesn't know
+        let file = include_bytes!("../../../local/Test.class");
+        let class_file = ClassFile::read_from_bytes(file).unwrap();
+        let Attributes::SourceFile { sourcefile_index } = class_file.attributes.first(attrtype::SourceFile).unwrap() else {
+            panic!("expected a SourceFile attribute");
+        };
+        let offset = file.windows(10).position(|v| v == b"SourceFile").unwrap();
+        let mut bytes = file.to_vec();
+        bytes[offset..offset + 10].copy_from_slice(b"VendorInfo");
+
+        assert!(matches!(ClassFile::read_from_bytes(&bytes), Err(ClassFileError::UnknownAttribute(v)) if v == "VendorInfo"));
+        let tolerant = ClassFile::read_from_bytes_with_options(&bytes, ParseOptions::tolerant()).unwrap();
+        assert!(tolerant.attributes.first(attrtype::SourceFile).is_none());
+        let [Attributes::Unknown { name, data }] = tolerant.attributes.get("VendorInfo").collect::<Vec<_>>()[..] else {
+            panic!("expected an unknown attribute");
+        };
+        assert_eq!(name, "VendorInfo");
+        assert_eq!(data, &sourcefile_index.to_be_bytes());
+    }
+
+    #[test]
+    fn rename_test() {
+        let file = include_bytes!("../../../local/Test.class");
+        let mut class_file = ClassFile::read_from_bytes(file).unwrap();
+        let cp = &mut class_file.constant_pool.entries;
+        let base = cp.len() as u16;
+        let utf8 = |v: &str| ConstantPoolEntry::utf8(v.to_string());
+        cp.extend([
+            utf8("com/foo/Bar"),
+            ConstantPoolEntry::Class { name_index: base + 1 },
+            ConstantPoolEntry::String { string_index: base + 1 },
+            utf8("[[Lcom/foo/Bar;"),
+            ConstantPoolEntry::Class { name_index: base + 4 },
+            utf8("(ILcom/foo/Bar;[Lcom/foo/BarBaz;)Lcom/foo/Bar;"),
+            ConstantPoolEntry::MethodType { descriptor_index: base + 6 },
+            utf8("<L:Lcom/foo/Bar;>(TL;Ljava/util/List<Lcom/foo/Bar;>;)Lcom/foo/Bar$In<TL;>.Lcom;"),
+            ConstantPoolEntry::MethodType { descriptor_index: base + 8 },
+        ]);
+        class_file.this_class = base + 2;
+
+        class_file.rename("com/foo/Bar", "org/baz/Bar").unwrap();
+        let cp = &class_file.constant_pool;
+        assert_eq!(class_file.class_name().unwrap(), "org/baz/Bar");
+        assert_eq!(cp.get_class_name(base as usize + 5).unwrap(), "[[Lorg/baz/Bar;");
+        assert_eq!(cp.get_utf8_constant(base as usize + 6).unwrap(), "(ILorg/baz/Bar;[Lcom/foo/BarBaz;)Lorg/baz/Bar;");
+        assert_eq!(
+            cp.get_utf8_constant(base as usize + 8).unwrap(),
+            "<L:Lorg/baz/Bar;>(TL;Ljava/util/List<Lorg/baz/Bar;>;)Lcom/foo/Bar$In<TL;>.Lcom;"
+        );
+        // the string constant keeps its text in a new entry
+        let ConstantPoolEntry::String { string_index } = cp.get_constant(base as usize + 3).unwrap() else {
+            panic!("expected a string constant");
+        };
+        assert_eq!(cp.get_utf8_constant(*string_index as usize).unwrap(), "com/foo/Bar");
+        assert_eq!(cp.entries.len(), base as usize + 10);
+    }
+
+    #[test]
+    fn rename_annotations_test() {
+        let mut class_file = ClassFile::read_from_bytes(include_bytes!("../../../local/Annotated.class")).unwrap();
+        class_file.rename("Marker", "p/Marker").unwrap();
+        class_file.rename("Kind", "p/Kind").unwrap();
+        class_file.rename("Annotated", "p/Renamed").unwrap();
+        let cp = &class_file.constant_pool;
+        let utf8 = |index: u16| cp.get_utf8_constant(index as usize).unwrap();
+
+        let Attributes::RuntimeVisibleAnnotations { annotations } = class_file.attributes.first(attrtype::RuntimeVisibleAnnotations).unwrap() else {
+            panic!("expected annotations");
+        };
+        let marker = &annotations[0];
+        assert_eq!(utf8(marker.type_index), "Lp/Marker;");
+        let values: Vec<_> = marker.element_value_pairs.iter().map(|v| &v.value).collect();
+        let [ElementValue::EnumConstValue { type_name_index, .. }, ElementValue::ClassInfoIndex { class_info_index }, ElementValue::AnnotationValue { annotation_value }] = values[..] else {
+            panic!("unexpected element values");
+        };
+        assert_eq!(utf8(*type_name_index), "Lp/Kind;");
+        assert_eq!(utf8(*class_info_index), "Lp/Renamed;");
+        // the nested annotation and its class literal
+        assert_eq!(utf8(annotation_value.type_index), "LInner;");
+        let ElementValue::ClassInfoIndex { class_info_index } = &annotation_value.element_value_pairs[0].value else {
+            panic!("expected a class literal");
+        };
+        assert_eq!(utf8(*class_info_index), "[Lp/Renamed;");
+    }
+
+    #[test]
+    fn rename_full_constant_pool_test() {
+        let file = include_bytes!("../../../local/Test.class");
+        let mut class_file = ClassFile::read_from_bytes(file).unwrap();
+        let cp = &mut class_file.constant_pool.entries;
+        let base = cp.len() as u16;
+        cp.extend([
+            ConstantPoolEntry::utf8("com/foo/Bar"),
+            ConstantPoolEntry::Class { name_index: base + 1 },
+            ConstantPoolEntry::String { string_index: base + 1 },
+        ]);
+        // the string constant needs a copy, which won't fit
+        cp.resize(u16::MAX as usize - 1, ConstantPoolEntry::Integer { bytes: 0 });
+        assert!(matches!(class_file.rename("com/foo/Bar", "org/baz/Bar"), Err(ClassFileError::ConstantPoolFull)));
+        assert_eq!(class_file.constant_pool.get_class_name(base as usize + 2).unwrap(), "com/foo/Bar");
+
+        class_file.constant_pool.entries.pop();
+        class_file.rename("com/foo/Bar", "org/baz/Bar").unwrap();
+        assert_eq!(class_file.constant_pool.get_class_name(base as usize + 2).unwrap(), "org/baz/Bar");
+        assert_eq!(class_file.constant_pool.entries.len(), u16::MAX as usize - 1);
+    }
+
+    #[test]
+    fn partial_test() {
+        let mut bytes = vec![0xca, 0xfe, 0xba, 0xbe, 0x00, 0x00, 0x00, 0x34, 0x00, 0x05];
+        for v in ["m", "()V", "Code", "SourceFile"] {
+            bytes.extend([0x01, 0x00, v.len() as u8]);
+            bytes.extend(v.as_bytes());
+        }
+        bytes.extend([0x00, 0x21, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x02]);
+        // a method with undefined access flags
+        bytes.extend([0x80, 0x00, 0x00, 0x01, 0x00, 0x02, 0x00, 0x00]);
+        // a method with a truncated Code attribute and a SourceFile attribute
+        bytes.extend([0x00, 0x09, 0x00, 0x01, 0x00, 0x02, 0x00, 0x02]);
+        bytes.extend([0x00, 0x03, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01]);
+        bytes.extend([0x00, 0x04, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01]);
+        // a valid SourceFile attribute on the class
+        bytes.extend([0x00, 0x01, 0x00, 0x04, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01]);
+
+        assert!(ClassFile::read_from_bytes(&bytes).is_err());
+        let partial = ClassFile::partial(&bytes, ParseOptions::default()).unwrap();
+        assert_eq!(partial.class_file.methods.len(), 1);
+        assert!(partial.class_file.methods[0].code().is_none());
+        assert_eq!(partial.class_file.attributes.get(attrtype::SourceFile).count(), 1);
+
+        let skipped: Vec<_> = partial.errors.iter().map(|v| v.item.clone()).collect();
+        assert_eq!(
+            skipped,
+            [
+                SkippedItem::Method(0),
+                SkippedItem::Attribute { name_index: 3, context: Some(AttributeContext::Method) },
+                SkippedItem::Attribute { name_index: 4, context: Some(AttributeContext::Method) },
+            ]
+        );
+        assert!(matches!(partial.errors[0].error, ClassFileError::BadMethodAccessFlags));
+        assert!(matches!(partial.errors[2].error, ClassFileError::AttributeInWrongContext(..)));
+
+        // errors before the fields are still fatal
+        assert!(ClassFile::partial(&bytes[..20], ParseOptions::default()).is_err());
+    }
+
+    #[test]
+    fn descriptor_error_position_test() {
+        let error = |v: &str| parse_descriptor::<MethodDescriptor>(v).unwrap_err().to_string();
+        assert_eq!(error("(IJ)X"), "unexpected 'X' at position 4 in descriptor");
+        assert_eq!(error("(IQ)V"), "unexpected 'Q' at position 2 in descriptor");
+        assert_eq!(error("([[I[Q)V"), "unexpected 'Q' at position 5 in descriptor");
+        assert!(parse_descriptor::<MethodDescriptor>("(IJ)V").is_ok());
+    }
+
+    #[test]
+    fn resolved_members_test() {
+        use crate::item::ids::{field::{BaseType, FieldType}, method::ReturnDescriptor};
+
+        let class_file = ClassFile::read_from_bytes(include_bytes!("../../../local/Test.class")).unwrap();
+        let methods = class_file.resolved_methods().unwrap();
+        let names: Vec<&str> = methods.iter().map(|m| m.name.as_str()).collect();
+        assert_eq!(names, ["<init>", "coolMethod", "epicBallsSex", "coolerMethod", "<clinit>"]);
+        let cool = &methods[1];
+        assert!(cool.access_flags.is_static());
+        assert!(cool.descriptor.parameters.is_empty());
+        assert_eq!(cool.descriptor.return_desc, ReturnDescriptor::Field(FieldType::BaseType(BaseType::Int)));
+        assert!(cool.code().is_some());
+        assert_eq!(methods[3].descriptor.parameters.len(), 1);
+
+        let fields = class_file.resolved_fields().unwrap();
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].name, "BALLS");
+        assert!(matches!(fields[0].descriptor, FieldType::ObjectType(_)));
+    }
+
+    #[test]
+    fn overrides_test() {
+        use crate::item::methods::MethodInfo;
+
+        /// Declare a method in `class_file`, and check whether it overrides one in `sup`.
+        fn declare(class_file: &mut ClassFile, name: &str, descriptor: &str, access_flags: MethodAccessFlags, sup: Option<&ClassFile>) -> bool {
+            let cp = &mut class_file.constant_pool.entries;
+            cp.push(ConstantPoolEntry::utf8(name.to_string()));
+            cp.push(ConstantPoolEntry::utf8(descriptor.to_string()));
+            let method = MethodInfo {
+                access_flags,
+                name_index: cp.len() as u16 - 1,
+                descriptor_index: cp.len() as u16,
+                attributes: AttributesCollection::new(),
+            };
+            let overrides = sup.is_some_and(|sup| class_file.overrides(&method, sup));
+            class_file.methods.push(method);
+            overrides
+        }
+        let public = MethodAccessFlags::ACC_PUBLIC;
+        let package = MethodAccessFlags::empty();
+
+        let mut sup = class("a/Base", None, &[]);
+        declare(&mut sup, "run", "(I)V", public, None);
+        declare(&mut sup, "get", "()Ljava/lang/Object;", public, None);
+        declare(&mut sup, "hidden", "()V", MethodAccessFlags::ACC_PRIVATE, None);
+        declare(&mut sup, "local", "()V", package, None);
+        declare(&mut sup, "<init>", "()V", public, None);
+
+        let mut sub = class("a/Sub", Some("a/Base"), &[]);
+        assert!(declare(&mut sub, "run", "(I)V", public, Some(&sup)));
+        assert!(!declare(&mut sub, "run", "(J)V", public, Some(&sup)));
+        assert!(!declare(&mut sub, "run", "(I)V", public | MethodAccessFlags::ACC_STATIC, Some(&sup)));
+        // a covariant return type is a different descriptor, bridged by javac
+        assert!(!declare(&mut sub, "get", "()Ljava/lang/String;", public, Some(&sup)));
+        assert!(!declare(&mut sub, "hidden", "()V", public, Some(&sup)));
+        assert!(!declare(&mut sub, "<init>", "()V", public, Some(&sup)));
+        assert!(declare(&mut sub, "local", "()V", package, Some(&sup)));
+
+        let mut other = class("b/Sub", Some("a/Base"), &[]);
+        assert!(!declare(&mut other, "local", "()V", package, Some(&sup)));
+        assert!(declare(&mut other, "run", "(I)V", public, Some(&sup)));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn shared_across_threads_test() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<ClassFile>();
+
+        let file = include_bytes!("../../../local/Test.class");
+        let class_file = std::sync::Arc::new(ClassFile::read_from_bytes(file).unwrap());
+        let expected = class_file.class_name().unwrap().to_string();
+        let threads: Vec<_> = (0..4)
+            .map(|_| {
+                let class_file = class_file.clone();
+                std::thread::spawn(move || {
+                    class_file.verify_all().unwrap();
+                    class_file.class_name().unwrap().to_string()
+                })
+            })
+            .collect();
+        for thread in threads {
+            assert_eq!(thread.join().unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn back_to_back_test() {
+        let file = include_bytes!("../../../local/Test.class");
+        let mut data = file.to_vec();
+        data.extend_from_slice(file);
+        let mut cursor = crate::io::Cursor::new(data);
+        let options = ParseOptions { capture_source: true, ..ParseOptions::default() };
+        let mut s = crate::stream::ClassFileStream::with_options(&mut cursor, options);
+        let first = ClassFile::read_from_stream(&mut s, None).unwrap();
+        assert_eq!(s.position(), file.len());
+        let second = ClassFile::read_from_stream(&mut s, None).unwrap();
+        assert_eq!(s.position(), file.len() * 2);
+        assert_eq!(first.source_bytes(), Some(&file[..]));
+        assert_eq!(second.source_bytes(), Some(&file[..]));
+    }
+
+    #[test]
+    fn class_flags_test() {
+        use ClassAccessFlags as F;
+        let file = include_bytes!("../../../local/Test.class");
+        let mut class_file = ClassFile::read_from_bytes(file).unwrap();
+        assert!(class_file.access_flags.is_public() && !class_file.access_flags.is_interface());
+        class_file.verify_class_flags().unwrap();
 
-    use crate::item::ClassFileItem;
+        let conflict = |class_file: &mut ClassFile, flags: F| {
+            class_file.access_flags = flags;
+            match class_file.verify_class_flags() {
+                Ok(()) => None,
+                Err(ClassFileError::BadClassFlagCombination(v)) => Some(v),
+                Err(e) => panic!("unexpected error {:?}", e),
+            }
+        };
+        assert_eq!(conflict(&mut class_file, F::ACC_PUBLIC | F::ACC_INTERFACE | F::ACC_ABSTRACT | F::ACC_ANNOTATION), None);
+        assert_eq!(conflict(&mut class_file, F::ACC_MODULE), None);
+        assert_eq!(conflict(&mut class_file, F::ACC_PUBLIC | F::ACC_FINAL | F::ACC_SUPER | F::ACC_ENUM), None);
+        assert_eq!(
+            conflict(&mut class_file, F::ACC_INTERFACE),
+            Some(ClassFlagConflict::Requires(F::ACC_INTERFACE, F::ACC_ABSTRACT))
+        );
+        assert_eq!(
+            conflict(&mut class_file, F::ACC_INTERFACE | F::ACC_ABSTRACT | F::ACC_SUPER),
+            Some(ClassFlagConflict::Excludes(F::ACC_INTERFACE, F::ACC_SUPER))
+        );
+        assert_eq!(
+            conflict(&mut class_file, F::ACC_ANNOTATION | F::ACC_ABSTRACT),
+            Some(ClassFlagConflict::Requires(F::ACC_ANNOTATION, F::ACC_INTERFACE))
+        );
+        assert_eq!(
+            conflict(&mut class_file, F::ACC_MODULE | F::ACC_PUBLIC),
+            Some(ClassFlagConflict::Excludes(F::ACC_MODULE, F::ACC_PUBLIC))
+        );
+        assert_eq!(
+            class_file.verify_class_flags().unwrap_err().to_string(),
+            "bad class access flags: ACC_MODULE can't be combined with ACC_PUBLIC"
+        );
+    }
+
+    #[test]
+    fn verify_all_test() {
+        let file = include_bytes!("../../../local/Test.class");
+        let mut class_file = ClassFile::read_from_bytes(file).unwrap();
+        class_file.verify_all().unwrap();
+
+        class_file.version = (44, 0);
+        class_file.access_flags = ClassAccessFlags::ACC_FINAL | ClassAccessFlags::ACC_ABSTRACT;
+        class_file.methods[0].access_flags = MethodAccessFlags::ACC_PUBLIC | MethodAccessFlags::ACC_PRIVATE;
+        let errors = class_file.verify_all().unwrap_err();
+        assert!(matches!(
+            errors[..],
+            [
+                ClassFileError::UnsupportedVersion(44, 0),
+                ClassFileError::BadClassFlagCombination(ClassFlagConflict::Excludes(ClassAccessFlags::ACC_FINAL, ClassAccessFlags::ACC_ABSTRACT)),
+                ClassFileError::BadMethodAccessFlags,
+            ]
+        ));
+        assert_eq!(class_file.verify_access_flags().unwrap_err().len(), 2);
+    }
 
-    use super::ClassFile;
+    #[test]
+    fn illegal_class_name_test() {
+        let file = include_bytes!("../../../local/Test.class");
+        let mut class_file = ClassFile::read_from_bytes(file).unwrap();
+        let ConstantPoolEntry::Class { name_index } = *class_file.constant_pool.get_constant(class_file.this_class as usize).unwrap() else {
+            panic!("this_class is not a class");
+        };
+        class_file.constant_pool.entries[name_index as usize - 1] = ConstantPoolEntry::utf8("local.Test".to_string());
+        let result = class_file.constant_pool.verify_structure(&class_file);
+        assert!(matches!(
+            result,
+            Err(ConstantPoolVerificationError::IllegalName(index, NameError::IllegalChar { index: 5, c: '.' })) if index == name_index
+        ));
+
+        // a NameAndType naming a member with an illegal name
+        let mut class_file = ClassFile::read_from_bytes(file).unwrap();
+        let descriptor_index = class_file.constant_pool.entries.iter().find_map(|v| match v {
+            ConstantPoolEntry::NameAndType { descriptor_index, .. } => Some(*descriptor_index),
+            _ => None,
+        }).unwrap();
+        let entries = &mut class_file.constant_pool.entries;
+        entries.push(ConstantPoolEntry::utf8("a;b".to_string()));
+        let name_index = entries.len() as u16;
+        entries.push(ConstantPoolEntry::NameAndType { name_index, descriptor_index });
+        let result = class_file.constant_pool.verify_structure(&class_file);
+        assert!(matches!(
+            result,
+            Err(ConstantPoolVerificationError::IllegalName(index, NameError::IllegalChar { index: 1, c: ';' })) if index == name_index
+        ));
+    }
+
+    #[test]
+    fn nesting_attributes_test() {
+        let file = include_bytes!("../../../local/Test.class");
+        let mut class_file = ClassFile::read_from_bytes(file).unwrap();
+        assert!(class_file.version.0 >= 51);
+        let class = class_file.this_class;
+        let ConstantPoolEntry::Class { name_index: name } = *class_file.constant_pool.get_constant(class as usize).unwrap() else {
+            panic!("this_class is not a class");
+        };
+        let name_and_type = class_file.constant_pool.entries.iter()
+            .position(|v| matches!(v, ConstantPoolEntry::NameAndType { .. }))
+            .unwrap() as u16 + 1;
+
+        let mut verify = |attribute: Attributes| {
+            class_file.attributes.take(attrtype::InnerClasses);
+            class_file.attributes.take(attrtype::EnclosingMethod);
+            let k = match attribute {
+                Attributes::InnerClasses { .. } => attrtype::InnerClasses,
+                _ => attrtype::EnclosingMethod,
+            };
+            class_file.attributes.push(k, attribute);
+            class_file.constant_pool.verify_structure(&class_file)
+        };
+        let inner_classes = |inner_class_info_index, outer_class_info_index, inner_name_index| Attributes::InnerClasses {
+            classes: vec![ClassArrayEntry {
+                inner_class_info_index,
+                outer_class_info_index,
+                inner_name_index,
+                inner_class_access_flags: ClassAccessFlags::ACC_PUBLIC,
+            }],
+        };
+
+        assert!(verify(inner_classes(class, class, name)).is_ok());
+        assert!(verify(inner_classes(class, 0, 0)).is_ok());
+        assert!(matches!(
+            verify(inner_classes(name, class, name)),
+            Err(ConstantPoolVerificationError::InnerClassesInnerClassInfoNotClass)
+        ));
+        assert!(matches!(
+            verify(inner_classes(class, name, name)),
+            Err(ConstantPoolVerificationError::InnerClassesOuterClassInfoNotClass)
+        ));
+        assert!(matches!(
+            verify(inner_classes(class, class, class)),
+            Err(ConstantPoolVerificationError::InnerClassesInnerNameNotUTF8)
+        ));
+        assert!(matches!(
+            verify(inner_classes(class, class, 0)),
+            Err(ConstantPoolVerificationError::InnerClassesOuterClassWithoutInnerName)
+        ));
+
+        assert!(verify(Attributes::EnclosingMethod { class_index: class, method_index: name_and_type }).is_ok());
+        assert!(verify(Attributes::EnclosingMethod { class_index: class, method_index: 0 }).is_ok());
+        assert!(matches!(
+            verify(Attributes::EnclosingMethod { class_index: name, method_index: 0 }),
+            Err(ConstantPoolVerificationError::EnclosingMethodClassIndexNotClass)
+        ));
+        assert!(matches!(
+            verify(Attributes::EnclosingMethod { class_index: class, method_index: class }),
+            Err(ConstantPoolVerificationError::EnclosingMethodMethodIndexNotNameAndType)
+        ));
+    }
+
+    #[test]
+    fn synthetic_deprecated_test() {
+        let mut class_file = class("A", None, &[]);
+        assert!(!class_file.is_synthetic() && !class_file.is_deprecated());
+        class_file.access_flags |= ClassAccessFlags::ACC_SYNTHETIC;
+        assert!(class_file.is_synthetic());
+
+        let mut class_file = class("B", None, &[]);
+        class_file.attributes.push(attrtype::Synthetic, Attributes::Synthetic);
+        class_file.attributes.push(attrtype::Deprecated, Attributes::Deprecated);
+        assert!(class_file.is_synthetic() && class_file.is_deprecated());
+    }
+
+    #[test]
+    fn generic_signature_test() {
+        let mut class_file = class("A", None, &[]);
+        assert!(class_file.generic_signature().is_none());
+
+        let signature = |class_file: &mut ClassFile, signature: &str| {
+            class_file.constant_pool.entries.push(ConstantPoolEntry::utf8(signature.to_string()));
+            let signature_index = class_file.constant_pool.entries.len() as u16;
+            class_file.attributes.take(attrtype::Signature);
+            class_file.attributes.push(attrtype::Signature, Attributes::Signature { signature_index });
+            signature_index
+        };
+        signature(&mut class_file, "<T:Ljava/lang/Object;>Ljava/lang/Object;Ljava/lang/Comparable<TT;>;");
+        let generic = class_file.generic_signature().unwrap().unwrap();
+        assert_eq!(generic.type_parameters[0].name, "T");
+        assert_eq!(generic.interfaces[0].class.name, "Comparable");
+
+        let index = signature(&mut class_file, "(TT;)V");
+        assert!(matches!(class_file.generic_signature(), Some(Err(ClassFileError::MalformedSignature(i))) if i == index));
+    }
+
+    #[test]
+    fn is_assignable_test() {
+        let resolver = |name: &str| match name {
+            "A" => Some(class("A", Some("java/lang/Object"), &["I"])),
+            "B" => Some(class("B", Some("A"), &[])),
+            "I" => Some(class("I", Some("java/lang/Object"), &["J"])),
+            "J" => Some(class("J", Some("java/lang/Object"), &[])),
+            _ => None,
+        };
+        assert!(is_assignable("B", "A", resolver));
+        assert!(is_assignable("B", "J", resolver));
+        assert!(is_assignable("B", "java/lang/Object", resolver));
+        assert!(!is_assignable("A", "B", resolver));
+        assert!(!is_assignable("Unknown", "A", resolver));
+
+        assert!(is_assignable("[LB;", "[LA;", resolver));
+        assert!(is_assignable("[[LB;", "[[LJ;", resolver));
+        assert!(is_assignable("[[LB;", "[Ljava/lang/Object;", resolver));
+        assert!(!is_assignable("[LA;", "[LB;", resolver));
+        assert!(is_assignable("[I", "[I", resolver));
+        assert!(!is_assignable("[I", "[J", resolver));
+        assert!(!is_assignable("[I", "[Ljava/lang/Object;", resolver));
+        assert!(is_assignable("[I", "java/lang/Cloneable", resolver));
+        assert!(is_assignable("[I", "java/lang/Object", resolver));
+        assert!(!is_assignable("A", "[LA;", resolver));
+    }
 
     #[test]
     fn class_file_test() {
@@ -182,4 +1732,301 @@ mod tests {
         class_file.constant_pool.verify_structure(&class_file).unwrap();
         // panic!("File: {:#?}", class_file);
     }
+
+    #[test]
+    fn argument_slot_count_test() {
+        let file = include_bytes!("../../../local/Test.class");
+        let class_file = ClassFile::read_from_stream(&mut crate::stream::ClassFileStream::new(
+            &mut Cursor::new(file),
+        ), None)
+        .unwrap();
+
+        for method in class_file.methods.iter() {
+            let slots = class_file.argument_slot_count(method).unwrap();
+            if let Some(code) = method.code() {
+                assert!(slots <= code.max_locals as usize);
+            }
+        }
+
+        let descriptor = Lexer::stream(Lexer::new(), "(IJLjava/lang/String;[DD)V".to_string())
+            .token::<MethodDescriptor>()
+            .unwrap()
+            .token;
+        assert_eq!(descriptor.argument_slot_count(), 7);
+    }
+
+    #[test]
+    fn class_identity_test() {
+        let file = include_bytes!("../../../local/Test.class");
+        let mut class_file = ClassFile::read_from_bytes(file).unwrap();
+        let cp = &mut class_file.constant_pool.entries;
+        let base = cp.len() as u16;
+        cp.extend([
+            ConstantPoolEntry::utf8("java/lang/Runnable".to_string()),
+            ConstantPoolEntry::Class { name_index: base + 1 },
+            ConstantPoolEntry::utf8("java/io/Serializable".to_string()),
+            ConstantPoolEntry::Class { name_index: base + 3 },
+        ]);
+        class_file.interfaces = vec![base + 2, base + 4];
+
+        assert_eq!(class_file.class_name().unwrap(), "Test");
+        assert_eq!(class_file.super_class_name().unwrap(), Some("java/lang/Object"));
+        assert_eq!(class_file.interface_names().unwrap(), ["java/lang/Runnable", "java/io/Serializable"]);
+
+        // java/lang/Object has no superclass
+        class_file.super_class = 0;
+        assert_eq!(class_file.super_class_name().unwrap(), None);
+        // an interface must name a Class constant
+        class_file.interfaces.push(base + 1);
+        assert!(class_file.interface_names().is_err());
+    }
+
+    #[test]
+    fn select_invokespecial_test() {
+        // a class `name` extending `super_name`, maybe declaring `m()V`, with a Methodref to `A.m()V` at 10
+        let class = |name: &str, super_name: &str, declares_m: bool, flags: u16, major: u8| {
+            let mut bytes = vec![0xca, 0xfe, 0xba, 0xbe, 0x00, 0x00, 0x00, major, 0x00, 0x0b];
+            let utf8 = |bytes: &mut Vec<u8>, v: &str| {
+                bytes.extend([0x01, 0x00, v.len() as u8]);
+                bytes.extend(v.as_bytes());
+            };
+            utf8(&mut bytes, name);
+            bytes.extend([0x07, 0x00, 0x01]);
+            utf8(&mut bytes, super_name);
+            bytes.extend([0x07, 0x00, 0x03]);
+            utf8(&mut bytes, "m");
+            utf8(&mut bytes, "()V");
+            utf8(&mut bytes, "A");
+            bytes.extend([0x07, 0x00, 0x07, 0x0c, 0x00, 0x05, 0x00, 0x06, 0x0a, 0x00, 0x08, 0x00, 0x09]);
+            bytes.extend(flags.to_be_bytes());
+            bytes.extend([0x00, 0x02, 0x00, 0x04, 0x00, 0x00, 0x00, 0x00, 0x00, declares_m as u8]);
+            if declares_m {
+                bytes.extend([0x00, 0x01, 0x00, 0x05, 0x00, 0x06, 0x00, 0x00]);
+            }
+            bytes.extend([0x00, 0x00]);
+            ClassFile::read_from_bytes(&bytes).unwrap()
+        };
+        let public_super = (ClassAccessFlags::ACC_PUBLIC | ClassAccessFlags::ACC_SUPER).bits();
+        let public = ClassAccessFlags::ACC_PUBLIC.bits();
+        // C extends B extends A, and B overrides A.m
+        let resolver = |name: &str| match name {
+            "A" => Some(class("A", "java/lang/Object", true, public_super, 50)),
+            "B" => Some(class("B", "A", true, public_super, 50)),
+            _ => None,
+        };
+        let selected = |c: &ClassFile| c.select_invokespecial(10, false, resolver).unwrap().unwrap().class_name;
+        let selected_legacy = |c: &ClassFile| c.select_invokespecial(10, true, resolver).unwrap().unwrap().class_name;
+
+        let with_flag = class("C", "B", false, public_super, 50);
+        assert!(with_flag.access_flags.is_super());
+        assert_eq!(selected(&with_flag), "B");
+        assert_eq!(selected_legacy(&with_flag), "B");
+        // Java 8 treats every class as having the flag, whatever its version
+        let without_flag = class("C", "B", false, public, 50);
+        assert!(without_flag.has_super_semantics(false));
+        assert_eq!(selected(&without_flag), "B");
+        // older JVMs search from the class named
+        assert!(!without_flag.has_super_semantics(true));
+        assert_eq!(selected_legacy(&without_flag), "A");
+
+        // A isn't a superclass of an unrelated class, so it's searched directly
+        let unrelated = class("C", "java/lang/Object", false, public_super, 50);
+        assert_eq!(selected(&unrelated), "A");
+        // nothing declares m when the hierarchy can't be loaded
+        let unknown = class("C", "D", false, public_super, 50);
+        assert_eq!(unknown.select_invokespecial(10, false, |_| None).unwrap(), None);
+        // a malformed hierarchy where A and B extend each other, and neither declares m
+        let cyclic = |name: &str| match name {
+            "A" => Some(class("A", "B", false, public_super, 50)),
+            "B" => Some(class("B", "A", false, public_super, 50)),
+            _ => None,
+        };
+        let in_cycle = class("C", "B", false, public_super, 50);
+        assert_eq!(in_cycle.select_invokespecial(10, false, cyclic).unwrap(), None);
+    }
+
+    #[test]
+    fn read_from_bytes_test() {
+        let owned = include_bytes!("../../../local/Test.class").to_vec();
+        let borrowed: &[u8] = &owned;
+        let class_file = ClassFile::read_from_bytes(borrowed).unwrap();
+        assert_eq!(class_file.class_name().unwrap(), "Test");
+        drop(class_file);
+        drop(owned);
+    }
+
+    #[test]
+    fn version_test() {
+        let mut class_file = class("A", None, &[]);
+        assert_eq!(class_file.java_version(), 8);
+        assert!(!class_file.is_preview());
+
+        class_file.version = (65, 0xFFFF);
+        assert_eq!(class_file.java_version(), 21);
+        assert!(class_file.is_preview());
+
+        class_file.version = (45, 3);
+        assert_eq!(class_file.java_version(), 1);
+    }
+
+    #[test]
+    fn legacy_version_test() {
+        // a 45.3 class written by a JDK 1.1-era compiler, from Apache
+        // Jakarta-Regexp: no stack maps, ACC_SUPER set, and a `finally`
+        // compiled to a `jsr` subroutine
+        let class_file = ClassFile::read_from_bytes(include_bytes!("../../../local/RETest.class")).unwrap();
+        assert_eq!(class_file.version, (45, 3));
+        assert_eq!(class_file.java_version(), 1);
+        assert!(class_file.has_super_semantics(true));
+        assert_eq!(class_file.class_name().unwrap(), "com/oracle/wls/shaded/org/apache/regexp/RETest");
+        let mut jsr_count = 0;
+        for method in class_file.methods.iter() {
+            let code = method.code().unwrap();
+            assert!(code.attributes.first(attrtype::StackMapTable).is_none());
+            jsr_count += code.code.opcodes.iter().filter(|op| matches!(op, VMOpcode::jsr(_))).count();
+        }
+        assert_eq!(jsr_count, 2);
+        class_file.verify_all().unwrap();
+    }
+
+    #[test]
+    fn string_constants_test() {
+        // javac writes NUL as `0xC0 0x80` and the clef as a surrogate pair
+        let class_file = ClassFile::read_from_bytes(include_bytes!("../../../local/Strings.class")).unwrap();
+        assert_eq!(class_file.string_constants(), ["nul\0byte", "clef \u{1d11e}", "plain", "lone \u{fffd}"]);
+        // the unpaired surrogate is only replaced in the decoded string
+        let cp = &class_file.constant_pool;
+        let index = |s: &str| 1 + cp.entries.iter().position(|v| matches!(v, ConstantPoolEntry::Utf8 { data, .. } if data == s)).unwrap();
+        assert_eq!(&*cp.get_utf8_bytes(index("lone \u{fffd}")).unwrap(), b"lone \xed\xa0\xb4");
+        assert_eq!(&*cp.get_utf8_bytes(index("nul\0byte")).unwrap(), b"nul\xc0\x80byte");
+        class_file.verify_all().unwrap();
+
+        let mut class_file = class("A", None, &[]);
+        let entries = &mut class_file.constant_pool.entries;
+        entries.push(ConstantPoolEntry::utf8("x".to_string()));
+        let index = entries.len() as u16;
+        entries.push(ConstantPoolEntry::String { string_index: index });
+        entries.push(ConstantPoolEntry::utf8("y".to_string()));
+        entries.push(ConstantPoolEntry::String { string_index: index + 2 });
+        entries.push(ConstantPoolEntry::String { string_index: index });
+        // refers to the `Class` constant for `A`
+        entries.push(ConstantPoolEntry::String { string_index: 2 });
+        assert_eq!(class_file.string_constants(), ["x", "y", "x"]);
+        assert_eq!(class_file.unique_string_constants(), ["x", "y"]);
+    }
+
+    #[test]
+    fn wide_constants_test() {
+        // a long or double takes two constant pool slots, so the string after them is #11
+        let class_file = ClassFile::read_from_bytes(include_bytes!("../../../local/Longs.class")).unwrap();
+        let cp = &class_file.constant_pool;
+        assert!(matches!(cp.get_constant(7), Ok(ConstantPoolEntry::Long { bytes: 123456789012 })));
+        assert!(matches!(cp.get_constant(8), Err(ClassFileError::InvalidConstant(8))));
+        assert!(matches!(cp.get_constant(9), Ok(ConstantPoolEntry::Double { bytes }) if f64::from_bits(*bytes) == 2.5));
+        assert!(matches!(cp.get_constant(11), Ok(ConstantPoolEntry::String { string_index: 12 })));
+        assert_eq!(class_file.string_constants(), ["after"]);
+        class_file.verify_all().unwrap();
+    }
+
+    #[test]
+    fn source_bytes_test() {
+        let file = include_bytes!("../../../local/Test.class");
+        let options = ParseOptions { capture_source: true, ..ParseOptions::default() };
+        let mut stream_data = Cursor::new(file);
+        let mut stream = crate::stream::ClassFileStream::with_options(&mut stream_data, options);
+        let class_file = ClassFile::read_from_stream(&mut stream, None).unwrap();
+        assert_eq!(class_file.source_bytes(), Some(&file[..]));
+        assert_eq!(stream.1, file.len());
+
+        // the bytes are only kept when asked for
+        let uncaptured = ClassFile::read_from_bytes(file).unwrap();
+        assert_eq!(uncaptured.source_bytes(), None);
+
+        #[cfg(feature = "sha2")]
+        {
+            let again = ClassFile::read_from_bytes_with_options(file, options).unwrap();
+            assert!(class_file.structural_hash().is_some());
+            assert_eq!(class_file.structural_hash(), again.structural_hash());
+            assert_eq!(uncaptured.structural_hash(), None);
+        }
+    }
+
+    #[test]
+    fn malformed_input_test() {
+        let file = include_bytes!("../../../local/Test.class");
+        let check = |bytes: &[u8]| {
+            if let Ok(v) = ClassFile::read_from_bytes(bytes) {
+                let _ = v.constant_pool.verify_cp_index_types();
+                let _ = v.constant_pool.verify_structure(&v);
+            }
+        };
+        for len in 0..file.len() {
+            assert!(ClassFile::read_from_bytes(&file[..len]).is_err());
+        }
+        let mut bytes = file.to_vec();
+        for i in 0..bytes.len() {
+            for flip in [0x01, 0x80, 0xff] {
+                bytes[i] ^= flip;
+                check(&bytes);
+                bytes[i] ^= flip;
+            }
+        }
+    }
+
+    #[test]
+    fn semantically_eq_test() {
+        let test = include_bytes!("../../../local/Test.class");
+        assert!(ClassFile::read_from_bytes(test).unwrap().semantically_eq(&ClassFile::read_from_bytes(test).unwrap()));
+
+        let utf8 = |v: &str| [&[0x01, 0x00, v.len() as u8], v.as_bytes()].concat();
+        let class = |pool: &[Vec<u8>], name: u8, descriptor: u8, code_name: u8, code: &[u8]| {
+            let mut bytes = vec![0xca, 0xfe, 0xba, 0xbe, 0x00, 0x00, 0x00, 0x34, 0x00, pool.len() as u8 + 1];
+            bytes.extend(pool.concat());
+            bytes.extend([0x00, 0x21, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01]);
+            bytes.extend([0x00, 0x09, 0x00, name, 0x00, descriptor, 0x00, 0x01, 0x00, code_name]);
+            bytes.extend((12 + code.len() as u32).to_be_bytes());
+            bytes.extend([0x00, 0x01, 0x00, 0x00]);
+            bytes.extend((code.len() as u32).to_be_bytes());
+            bytes.extend(code);
+            bytes.extend([0x00, 0x00, 0x00, 0x00, 0x00, 0x00]);
+            ClassFile::read_from_bytes(&bytes).unwrap()
+        };
+
+        // ldc "s"; pop; iconst_0; ifeq return; nop; return
+        let a = class(
+            &[utf8("m"), utf8("()V"), utf8("Code"), utf8("s"), vec![0x08, 0x00, 0x04]],
+            1,
+            2,
+            3,
+            &[0x12, 0x05, 0x57, 0x03, 0x99, 0x00, 0x04, 0x00, 0xb1],
+        );
+        // the same with the pool reordered, loading the string with ldc_w
+        let b = class(
+            &[utf8("s"), vec![0x08, 0x00, 0x01], utf8("Code"), utf8("()V"), utf8("m")],
+            5,
+            4,
+            3,
+            &[0x13, 0x00, 0x02, 0x57, 0x03, 0x99, 0x00, 0x04, 0x00, 0xb1],
+        );
+        assert!(a.semantically_eq(&b));
+        assert!(b.semantically_eq(&a));
+
+        let different_string = class(
+            &[utf8("t"), vec![0x08, 0x00, 0x01], utf8("Code"), utf8("()V"), utf8("m")],
+            5,
+            4,
+            3,
+            &[0x13, 0x00, 0x02, 0x57, 0x03, 0x99, 0x00, 0x04, 0x00, 0xb1],
+        );
+        assert!(!a.semantically_eq(&different_string));
+        // ifeq lands on the nop instead
+        let different_branch = class(
+            &[utf8("s"), vec![0x08, 0x00, 0x01], utf8("Code"), utf8("()V"), utf8("m")],
+            5,
+            4,
+            3,
+            &[0x13, 0x00, 0x02, 0x57, 0x03, 0x99, 0x00, 0x03, 0x00, 0xb1],
+        );
+        assert!(!a.semantically_eq(&different_branch));
+    }
 }
@@ -1,4 +1,5 @@
-use std::fmt::Debug;
+use alloc::{boxed::Box, string::String, vec, vec::Vec};
+use core::fmt::Debug;
 
 use exo_parser::{
     error::{ParsingError, ParsingErrorType},
@@ -48,7 +49,6 @@ pub struct ClassName {
 
 impl Parseable for ClassName {
     fn parse(s: &mut exo_parser::LexerStream) -> exo_parser::error::Result<Self> {
-        println!("Values: {:?}", s.chars());
         let mut list = vec![];
         let mut inner_class = None;
         loop {
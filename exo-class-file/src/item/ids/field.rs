@@ -1,3 +1,4 @@
+use alloc::boxed::Box;
 use exo_parser::{multi_choice, tokenimpl::Char, Parseable, Token};
 
 use super::class::ClassName;
@@ -88,6 +89,17 @@ pub enum FieldType {
     ArrayType(ArrayType)
 }
 
+impl FieldType {
+    /// The number of local variable slots a value of this type occupies.
+    ///
+    /// `long` and `double` take two slots, all other types take one.
+    pub fn slot_size(&self) -> usize {
+        match self {
+            Self::BaseType(BaseType::Long | BaseType::Double) => 2,
+            _ => 1,
+        }
+    }
+}
 
 impl Parseable for FieldType {
     fn parse(s: &mut exo_parser::LexerStream) -> exo_parser::error::Result<Self> {
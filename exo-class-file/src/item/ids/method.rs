@@ -1,3 +1,4 @@
+use alloc::{vec, vec::Vec};
 use exo_parser::{tokenimpl::Char, Token, Parseable, multi_choice, parse_err, error::ParsingErrorType};
 
 use super::{field::FieldType, UnqualifiedName};
@@ -54,6 +55,22 @@ impl Parseable for MethodDescriptor {
     }
 }
 
+impl MethodDescriptor {
+    /// The number of local variable slots taken by the parameters,
+    /// not counting `this`.
+    pub fn argument_slot_count(&self) -> usize {
+        self.parameters.iter().map(ParameterDescriptor::slot_size).sum()
+    }
+
+    /// Whether both descriptors have exactly the same parameters and return type.
+    ///
+    /// The JVM matches overriding methods by descriptor alone, so unlike the
+    /// Java language this makes no allowance for covariant return types.
+    pub fn same_signature(&self, other: &MethodDescriptor) -> bool {
+        self.parameters == other.parameters && self.return_desc == other.return_desc
+    }
+}
+
 /// Method name.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum MethodName {
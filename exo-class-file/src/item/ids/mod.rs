@@ -1,15 +1,27 @@
-use std::rc::Rc;
+use alloc::{rc::Rc, string::String};
 
 use exo_parser::{Parseable, parse_err, error::ParsingErrorType, Lexer, LexerRef};
 
 pub mod class;
 pub mod field;
 pub mod method;
+pub mod signature;
 
 /// Characters banned in identifiers.
 pub const BANNED_IDENT_CHARS: [char; 4] = ['.', ';', '[', '/'];
 
 
+/// Errors returned when validating names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameError {
+    /// Returned when the name is empty.
+    Empty,
+    /// Returned when a character not allowed in the name is found at the given byte offset.
+    IllegalChar { index: usize, c: char },
+    /// Returned when a package separator is not followed by a name, at the given byte offset.
+    EmptySegment(usize),
+}
+
 /// Unqualified name.
 #[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
 pub struct UnqualifiedName(pub Rc<String>);
@@ -19,6 +31,60 @@ impl UnqualifiedName {
         let lexer = Lexer::new();
         Lexer::stream(lexer, s).token().ok().map(|v| v.token)
     }
+
+    /// Check that `name` is a legal unqualified name, as used for fields
+    /// and local variables (JVMS §4.2.2).
+    pub fn validate(name: &str) -> Result<(), NameError> {
+        if name.is_empty() {
+            return Err(NameError::Empty);
+        }
+        match name.char_indices().find(|(_, c)| BANNED_IDENT_CHARS.contains(c)) {
+            Some((index, c)) => Err(NameError::IllegalChar { index, c }),
+            None => Ok(()),
+        }
+    }
+
+    /// Check that `name` is a legal method name: either `<init>`, `<clinit>`,
+    /// or an unqualified name which contains neither `<` nor `>`.
+    pub fn validate_method(name: &str) -> Result<(), NameError> {
+        if name == "<init>" || name == "<clinit>" {
+            return Ok(());
+        }
+        Self::validate(name)?;
+        match name.char_indices().find(|(_, c)| matches!(c, '<' | '>')) {
+            Some((index, c)) => Err(NameError::IllegalChar { index, c }),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Binary name of a class or interface in internal form,
+/// with `/` separating package segments (JVMS §4.2.1).
+#[derive(Debug, Clone, Hash, PartialEq, Eq, PartialOrd, Ord)]
+pub struct BinaryName(pub Rc<String>);
+
+impl BinaryName {
+    pub fn new(s: String) -> Option<Self> {
+        Self::validate(&s).ok().map(|_| Self(Rc::new(s)))
+    }
+
+    /// Check that `name` is a legal binary name in internal form,
+    /// where every segment between `/`s is an unqualified name.
+    pub fn validate(name: &str) -> Result<(), NameError> {
+        if name.is_empty() {
+            return Err(NameError::Empty);
+        }
+        let mut start = 0;
+        for segment in name.split('/') {
+            UnqualifiedName::validate(segment).map_err(|e| match e {
+                NameError::Empty => NameError::EmptySegment(start),
+                NameError::IllegalChar { index, c } => NameError::IllegalChar { index: start + index, c },
+                e => e,
+            })?;
+            start += segment.len() + 1;
+        }
+        Ok(())
+    }
 }
 
 impl Parseable for UnqualifiedName {
@@ -34,4 +100,33 @@ impl Parseable for UnqualifiedName {
         }
         Ok(Self(Rc::new(str)))
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::{BinaryName, NameError, UnqualifiedName};
+
+    #[test]
+    fn validate_names_test() {
+        assert_eq!(UnqualifiedName::validate("value$1"), Ok(()));
+        assert_eq!(UnqualifiedName::validate("<weird>"), Ok(()));
+        assert_eq!(UnqualifiedName::validate(""), Err(NameError::Empty));
+        assert_eq!(UnqualifiedName::validate("a;b"), Err(NameError::IllegalChar { index: 1, c: ';' }));
+
+        assert_eq!(UnqualifiedName::validate_method("<init>"), Ok(()));
+        assert_eq!(UnqualifiedName::validate_method("<clinit>"), Ok(()));
+        assert_eq!(UnqualifiedName::validate_method("<main>"), Err(NameError::IllegalChar { index: 0, c: '<' }));
+        assert_eq!(UnqualifiedName::validate_method("a.b"), Err(NameError::IllegalChar { index: 1, c: '.' }));
+
+        assert_eq!(BinaryName::validate("java/lang/Object"), Ok(()));
+        assert_eq!(BinaryName::validate("Outer$Inner"), Ok(()));
+        assert_eq!(BinaryName::validate("java.lang.Object"), Err(NameError::IllegalChar { index: 4, c: '.' }));
+        assert_eq!(BinaryName::validate("java//Object"), Err(NameError::EmptySegment(5)));
+        assert_eq!(BinaryName::validate("java/"), Err(NameError::EmptySegment(5)));
+        assert_eq!(BinaryName::validate("/Object"), Err(NameError::EmptySegment(0)));
+        assert_eq!(BinaryName::validate("[I"), Err(NameError::IllegalChar { index: 0, c: '[' }));
+        assert!(BinaryName::new("java/util/List".to_string()).is_some());
+    }
+}
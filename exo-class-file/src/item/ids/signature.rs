@@ -0,0 +1,349 @@
+//! Generic signatures, as stored in `Signature` attributes (JVMS §4.7.9.1).
+
+use alloc::{boxed::Box, string::{String, ToString}, vec, vec::Vec};
+use exo_parser::{error::ParsingErrorType, parse_err, tokenimpl::Char, Lexer, Parseable};
+
+use super::field::BaseType;
+
+/// Characters which end an identifier in a signature.
+const SIGNATURE_DELIMITERS: [char; 8] = ['.', ';', '[', '/', '<', '>', ':', '^'];
+
+/// Parse all of `s` as a `T`, failing if anything is left over.
+fn parse_complete<T: Parseable>(s: &str) -> Option<T> {
+    let lexer = Lexer::new();
+    let mut stream = Lexer::stream(lexer, s.to_string());
+    let v = stream.token::<T>().ok()?.token;
+    stream.ended().then_some(v)
+}
+
+/// Read an identifier, stopping before any delimiter.
+fn identifier(s: &mut exo_parser::LexerStream) -> exo_parser::error::Result<String> {
+    let mut str = String::new();
+    while let Ok(c) = s.char() {
+        if SIGNATURE_DELIMITERS.contains(&c) {
+            s.position -= 1;
+            break;
+        }
+        str.push(c);
+    }
+    if str.is_empty() {
+        return Err(parse_err!(s, "empty identifier"));
+    }
+    Ok(str)
+}
+
+/// A type argument of a parameterized class type.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TypeArgument {
+    /// `*`, any type.
+    Wildcard,
+    /// `+`, the type or any subtype of it.
+    Extends(ReferenceTypeSignature),
+    /// `-`, the type or any supertype of it.
+    Super(ReferenceTypeSignature),
+    /// Exactly the type.
+    Exact(ReferenceTypeSignature),
+}
+
+impl Parseable for TypeArgument {
+    fn parse(s: &mut exo_parser::LexerStream) -> exo_parser::error::Result<Self> {
+        if s.token::<Char<'*'>>().is_ok() {
+            Ok(Self::Wildcard)
+        } else if s.token::<Char<'+'>>().is_ok() {
+            Ok(Self::Extends(s.token()?.token))
+        } else if s.token::<Char<'-'>>().is_ok() {
+            Ok(Self::Super(s.token()?.token))
+        } else {
+            Ok(Self::Exact(s.token()?.token))
+        }
+    }
+}
+
+/// A class name with its type arguments, if any.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimpleClassTypeSignature {
+    pub name: String,
+    pub type_arguments: Vec<TypeArgument>,
+}
+
+impl Parseable for SimpleClassTypeSignature {
+    fn parse(s: &mut exo_parser::LexerStream) -> exo_parser::error::Result<Self> {
+        let name = identifier(s)?;
+        let mut type_arguments = vec![];
+        if s.token::<Char<'<'>>().is_ok() {
+            loop {
+                type_arguments.push(s.token::<TypeArgument>()?.token);
+                if s.token::<Char<'>'>>().is_ok() {
+                    break;
+                }
+            }
+        }
+        Ok(Self { name, type_arguments })
+    }
+}
+
+/// A possibly parameterized class or interface type.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClassTypeSignature {
+    /// The package of the outermost class.
+    pub package: Vec<String>,
+    /// The outermost class.
+    pub class: SimpleClassTypeSignature,
+    /// Inner classes, outermost first.
+    pub inner: Vec<SimpleClassTypeSignature>,
+}
+
+impl Parseable for ClassTypeSignature {
+    fn parse(s: &mut exo_parser::LexerStream) -> exo_parser::error::Result<Self> {
+        s.token::<Char<'L'>>()?;
+        let mut package = vec![];
+        loop {
+            let start = s.position;
+            let name = identifier(s)?;
+            if s.token::<Char<'/'>>().is_ok() {
+                package.push(name);
+            } else {
+                s.position = start;
+                break;
+            }
+        }
+        let class = s.token::<SimpleClassTypeSignature>()?.token;
+        let mut inner = vec![];
+        while s.token::<Char<'.'>>().is_ok() {
+            inner.push(s.token::<SimpleClassTypeSignature>()?.token);
+        }
+        s.token::<Char<';'>>()?;
+        Ok(Self { package, class, inner })
+    }
+}
+
+/// `T` followed by the name of a type variable.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TypeVariableSignature(pub String);
+
+impl Parseable for TypeVariableSignature {
+    fn parse(s: &mut exo_parser::LexerStream) -> exo_parser::error::Result<Self> {
+        s.token::<Char<'T'>>()?;
+        let name = identifier(s)?;
+        s.token::<Char<';'>>()?;
+        Ok(Self(name))
+    }
+}
+
+/// A reference type: a class, type variable or array.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ReferenceTypeSignature {
+    Class(ClassTypeSignature),
+    TypeVariable(TypeVariableSignature),
+    Array(Box<JavaTypeSignature>),
+}
+
+impl Parseable for ReferenceTypeSignature {
+    fn parse(s: &mut exo_parser::LexerStream) -> exo_parser::error::Result<Self> {
+        if let Ok(v) = s.token::<ClassTypeSignature>() {
+            Ok(Self::Class(v.token))
+        } else if let Ok(v) = s.token::<TypeVariableSignature>() {
+            Ok(Self::TypeVariable(v.token))
+        } else {
+            s.token::<Char<'['>>()?;
+            Ok(Self::Array(Box::new(s.token()?.token)))
+        }
+    }
+}
+
+/// Any type which a value may have.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum JavaTypeSignature {
+    Base(BaseType),
+    Reference(ReferenceTypeSignature),
+}
+
+impl Parseable for JavaTypeSignature {
+    fn parse(s: &mut exo_parser::LexerStream) -> exo_parser::error::Result<Self> {
+        match s.token::<BaseType>() {
+            Ok(v) => Ok(Self::Base(v.token)),
+            Err(_) => Ok(Self::Reference(s.token()?.token)),
+        }
+    }
+}
+
+/// A type parameter declared by a generic class or method.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TypeParameter {
+    pub name: String,
+    pub class_bound: Option<ReferenceTypeSignature>,
+    pub interface_bounds: Vec<ReferenceTypeSignature>,
+}
+
+impl Parseable for TypeParameter {
+    fn parse(s: &mut exo_parser::LexerStream) -> exo_parser::error::Result<Self> {
+        let name = identifier(s)?;
+        s.token::<Char<':'>>()?;
+        let class_bound = s.token::<ReferenceTypeSignature>().ok().map(|v| v.token);
+        let mut interface_bounds = vec![];
+        while s.token::<Char<':'>>().is_ok() {
+            interface_bounds.push(s.token()?.token);
+        }
+        Ok(Self { name, class_bound, interface_bounds })
+    }
+}
+
+/// Type parameters, enclosed in `<>`. Absent type parameters are empty.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct TypeParameters(Vec<TypeParameter>);
+
+impl Parseable for TypeParameters {
+    fn parse(s: &mut exo_parser::LexerStream) -> exo_parser::error::Result<Self> {
+        let mut parameters = vec![];
+        if s.token::<Char<'<'>>().is_ok() {
+            loop {
+                parameters.push(s.token::<TypeParameter>()?.token);
+                if s.token::<Char<'>'>>().is_ok() {
+                    break;
+                }
+            }
+        }
+        Ok(Self(parameters))
+    }
+}
+
+/// The generic signature of a class or interface.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ClassSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub superclass: ClassTypeSignature,
+    pub interfaces: Vec<ClassTypeSignature>,
+}
+
+impl ClassSignature {
+    pub fn new(s: &str) -> Option<Self> {
+        parse_complete(s)
+    }
+}
+
+impl Parseable for ClassSignature {
+    fn parse(s: &mut exo_parser::LexerStream) -> exo_parser::error::Result<Self> {
+        let type_parameters = s.token::<TypeParameters>()?.token.0;
+        let superclass = s.token()?.token;
+        let mut interfaces = vec![];
+        while !s.ended() {
+            interfaces.push(s.token()?.token);
+        }
+        Ok(Self { type_parameters, superclass, interfaces })
+    }
+}
+
+/// A type a method may declare it throws.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ThrowsSignature {
+    Class(ClassTypeSignature),
+    TypeVariable(TypeVariableSignature),
+}
+
+impl Parseable for ThrowsSignature {
+    fn parse(s: &mut exo_parser::LexerStream) -> exo_parser::error::Result<Self> {
+        s.token::<Char<'^'>>()?;
+        match s.token::<ClassTypeSignature>() {
+            Ok(v) => Ok(Self::Class(v.token)),
+            Err(_) => Ok(Self::TypeVariable(s.token()?.token)),
+        }
+    }
+}
+
+/// The generic signature of a method.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct MethodSignature {
+    pub type_parameters: Vec<TypeParameter>,
+    pub parameters: Vec<JavaTypeSignature>,
+    /// The return type, `None` for `void`.
+    pub result: Option<JavaTypeSignature>,
+    pub throws: Vec<ThrowsSignature>,
+}
+
+impl MethodSignature {
+    pub fn new(s: &str) -> Option<Self> {
+        parse_complete(s)
+    }
+}
+
+impl Parseable for MethodSignature {
+    fn parse(s: &mut exo_parser::LexerStream) -> exo_parser::error::Result<Self> {
+        let type_parameters = s.token::<TypeParameters>()?.token.0;
+        s.token::<Char<'('>>()?;
+        let mut parameters = vec![];
+        while s.token::<Char<')'>>().is_err() {
+            parameters.push(s.token()?.token);
+        }
+        let result = match s.token::<Char<'V'>>() {
+            Ok(_) => None,
+            Err(_) => Some(s.token()?.token),
+        };
+        let mut throws = vec![];
+        while !s.ended() {
+            throws.push(s.token()?.token);
+        }
+        Ok(Self { type_parameters, parameters, result, throws })
+    }
+}
+
+/// The generic signature of a field, which is always a reference type.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct FieldSignature(pub ReferenceTypeSignature);
+
+impl FieldSignature {
+    pub fn new(s: &str) -> Option<Self> {
+        parse_complete(s)
+    }
+}
+
+impl Parseable for FieldSignature {
+    fn parse(s: &mut exo_parser::LexerStream) -> exo_parser::error::Result<Self> {
+        Ok(Self(s.token()?.token))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::{
+        ClassSignature, ClassTypeSignature, FieldSignature, JavaTypeSignature, MethodSignature, ReferenceTypeSignature,
+        ThrowsSignature, TypeArgument, TypeVariableSignature,
+    };
+    use crate::item::ids::field::BaseType;
+
+    #[test]
+    fn signature_test() {
+        let class = ClassSignature::new("<K:Ljava/lang/Object;V::Ljava/lang/Comparable<TV;>;>Ljava/util/AbstractMap<TK;TV;>;Ljava/io/Serializable;").unwrap();
+        assert_eq!(class.type_parameters.len(), 2);
+        assert_eq!(class.type_parameters[0].name, "K");
+        assert!(class.type_parameters[1].class_bound.is_none());
+        assert_eq!(class.type_parameters[1].interface_bounds.len(), 1);
+        assert_eq!(class.superclass.package, ["java", "util"]);
+        assert_eq!(class.superclass.class.name, "AbstractMap");
+        assert_eq!(
+            class.superclass.class.type_arguments[1],
+            TypeArgument::Exact(ReferenceTypeSignature::TypeVariable(TypeVariableSignature("V".to_string())))
+        );
+        assert_eq!(class.interfaces[0].class.name, "Serializable");
+
+        let method = MethodSignature::new("<T:Ljava/lang/Throwable;>(I[TT;Ljava/util/List<+Ljava/lang/Number;>;)V^TT;^Ljava/io/IOException;").unwrap();
+        assert_eq!(method.parameters.len(), 3);
+        assert_eq!(method.parameters[0], JavaTypeSignature::Base(BaseType::Int));
+        assert!(matches!(&method.parameters[2], JavaTypeSignature::Reference(ReferenceTypeSignature::Class(ClassTypeSignature { class, .. }))
+            if matches!(class.type_arguments[..], [TypeArgument::Extends(_)])));
+        assert_eq!(method.result, None);
+        assert!(matches!(method.throws[..], [ThrowsSignature::TypeVariable(_), ThrowsSignature::Class(_)]));
+
+        let field = FieldSignature::new("Ljava/util/Map<*Ljava/lang/String;>.Inner<-TT;>;").unwrap();
+        let FieldSignature(ReferenceTypeSignature::Class(class)) = field else {
+            panic!("expected class type");
+        };
+        assert_eq!(class.inner[0].name, "Inner");
+        assert!(matches!(class.class.type_arguments[..], [TypeArgument::Wildcard, TypeArgument::Exact(_)]));
+
+        assert!(FieldSignature::new("I").is_none());
+        assert!(MethodSignature::new("()Ljava/lang/String;junk").is_none());
+        assert!(ClassSignature::new("<>Ljava/lang/Object;").is_none());
+    }
+}
@@ -1,8 +1,9 @@
-use std::io::Read;
+use alloc::vec::Vec;
+use crate::io::Read;
 
 use crate::{error::{self, ClassFileError}, stream::ClassFileStream};
 
-use super::{attribute_info::{Attributes, AttributesCollection}, ClassFileItem, ConstantPool};
+use super::{attribute_info::{attrtype, AttributeContext, Attributes, AttributesCollection, CodeAttribute}, ids::signature::MethodSignature, ClassFileItem, ConstantPool};
 
 /// Method info.
 #[derive(Debug)]
@@ -49,11 +50,94 @@ impl ClassFileItem for MethodInfo {
             access_flags,
             name_index,
             descriptor_index,
-            attributes: AttributesCollection::read_from_stream(s, cp)?,
+            attributes: AttributesCollection::read_in_context(s, cp, AttributeContext::Method)?,
         })
     }
 }
 
+impl MethodInfo {
+    /// Whether this method is synthetic, marked by either
+    /// the `ACC_SYNTHETIC` flag or a `Synthetic` attribute.
+    pub fn is_synthetic(&self) -> bool {
+        self.access_flags.is_synthetic() || self.attributes.first(attrtype::Synthetic).is_some()
+    }
+
+    /// Whether this method is a bridge method, a synthetic forwarder
+    /// the compiler generates for covariant or generic overrides.
+    /// Tools reconstructing the source-level API should skip these.
+    pub fn is_bridge(&self) -> bool {
+        self.access_flags.is_bridge()
+    }
+
+    /// Whether this method takes a variable number of arguments.
+    pub fn is_varargs(&self) -> bool {
+        self.access_flags.is_varargs()
+    }
+
+    /// Whether this method has a `Deprecated` attribute. There is
+    /// no access flag for deprecation.
+    pub fn is_deprecated(&self) -> bool {
+        self.attributes.first(attrtype::Deprecated).is_some()
+    }
+
+    /// Parse this method's `Signature` attribute, returning `None`
+    /// if the method has no generic signature.
+    pub fn generic_signature(&self, cp: &ConstantPool) -> Option<error::Result<MethodSignature>> {
+        let index = self.attributes.signature_index()?;
+        Some(cp.get_utf8_constant(index as usize).and_then(|v| {
+            MethodSignature::new(v).ok_or(ClassFileError::MalformedSignature(index))
+        }))
+    }
+
+    /// The internal names of the checked exceptions this method declares
+    /// in its `Exceptions` attribute, its `throws` clause, in declaration order.
+    pub fn thrown_exceptions<'a>(&self, cp: &'a ConstantPool) -> error::Result<Vec<&'a str>> {
+        self.attributes
+            .get(attrtype::Exceptions)
+            .filter_map(|v| match v {
+                Attributes::Exceptions { exception_index_table } => Some(exception_index_table),
+                _ => None,
+            })
+            .flatten()
+            .map(|v| cp.get_class_name(*v as usize))
+            .collect()
+    }
+
+    /// The `Code` attribute of this method, if it has one.
+    /// Abstract and native methods have no code.
+    pub fn code(&self) -> Option<&CodeAttribute> {
+        self.attributes.get(attrtype::Code).find_map(|v| match v {
+            Attributes::Code(code) => Some(code),
+            _ => None,
+        })
+    }
+
+    /// Find the source line of the instruction at `byte_offset` in this method's code.
+    ///
+    /// Consults every `LineNumberTable` of the `Code` attribute, picking the
+    /// entry with the greatest `start_pc` not after the offset.
+    pub fn source_line_for_offset(&self, byte_offset: u16) -> Option<u16> {
+        let mut entries: Vec<_> = self
+            .attributes
+            .get(attrtype::Code)
+            .filter_map(|code| match code {
+                Attributes::Code(code) => Some(code.attributes.get(attrtype::LineNumberTable)),
+                _ => None,
+            })
+            .flatten()
+            .filter_map(|table| match table {
+                Attributes::LineNumberTable { line_number_table } => Some(line_number_table),
+                _ => None,
+            })
+            .flatten()
+            .collect();
+        entries.sort_by_key(|entry| entry.start_pc);
+
+        let i = entries.partition_point(|entry| entry.start_pc <= byte_offset);
+        Some(entries[i.checked_sub(1)?].line_number)
+    }
+}
+
 bitflags::bitflags! {
     pub struct MethodAccessFlags: u16 {
         /// Declared public; may be accessed from outside its package.
@@ -82,3 +166,264 @@ bitflags::bitflags! {
         const ACC_SYNTHETIC = 0x1000;
     }
 }
+
+impl MethodAccessFlags {
+    /// Whether the method is declared public.
+    pub fn is_public(&self) -> bool {
+        self.contains(Self::ACC_PUBLIC)
+    }
+
+    /// Whether the method is declared private.
+    pub fn is_private(&self) -> bool {
+        self.contains(Self::ACC_PRIVATE)
+    }
+
+    /// Whether the method is declared protected.
+    pub fn is_protected(&self) -> bool {
+        self.contains(Self::ACC_PROTECTED)
+    }
+
+    /// Whether the method is declared static.
+    pub fn is_static(&self) -> bool {
+        self.contains(Self::ACC_STATIC)
+    }
+
+    /// Whether the method is declared final.
+    pub fn is_final(&self) -> bool {
+        self.contains(Self::ACC_FINAL)
+    }
+
+    /// Whether the method is declared synchronized.
+    pub fn is_synchronized(&self) -> bool {
+        self.contains(Self::ACC_SYNCHRONIZED)
+    }
+
+    /// Whether the method is a compiler-generated bridge method.
+    pub fn is_bridge(&self) -> bool {
+        self.contains(Self::ACC_BRIDGE)
+    }
+
+    /// Whether the method takes a variable number of arguments.
+    pub fn is_varargs(&self) -> bool {
+        self.contains(Self::ACC_VARARGS)
+    }
+
+    /// Whether the method is declared native.
+    pub fn is_native(&self) -> bool {
+        self.contains(Self::ACC_NATIVE)
+    }
+
+    /// Whether the method is declared abstract.
+    pub fn is_abstract(&self) -> bool {
+        self.contains(Self::ACC_ABSTRACT)
+    }
+
+    /// Whether the method is declared strictfp.
+    pub fn is_strict(&self) -> bool {
+        self.contains(Self::ACC_STRICT)
+    }
+
+    /// Whether the method is synthetic.
+    pub fn is_synthetic(&self) -> bool {
+        self.contains(Self::ACC_SYNTHETIC)
+    }
+
+    /// Check the flags against the combination rules of §4.6 which apply to
+    /// every method: at most one of public, private and protected may be set,
+    /// and abstract methods can't also be private, static, final,
+    /// synchronized, native or strictfp.
+    pub fn is_valid_combination(&self) -> bool {
+        let visibility = *self & (Self::ACC_PUBLIC | Self::ACC_PRIVATE | Self::ACC_PROTECTED);
+        if visibility.bits().count_ones() > 1 {
+            return false;
+        }
+        !self.is_abstract()
+            || !self.intersects(
+                Self::ACC_PRIVATE
+                    | Self::ACC_STATIC
+                    | Self::ACC_FINAL
+                    | Self::ACC_SYNCHRONIZED
+                    | Self::ACC_NATIVE
+                    | Self::ACC_STRICT,
+            )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{string::ToString, vec};
+
+    use crate::{
+        error::ClassFileError,
+        io::Cursor,
+        item::{
+            attribute_info::{attrtype, AttributeContext, Attributes, AttributesCollection, CodeAttribute, LineNumberTableEntry},
+            constant_pool::{ConstantPool, ConstantPoolEntry},
+            file::ClassFile,
+            opcodes::InstructionList,
+            ClassFileItem,
+        },
+        stream::{ClassFileStream, ParseDiagnostic, ParseOptions},
+        IntMap,
+    };
+
+    use super::{MethodAccessFlags, MethodInfo};
+
+    #[test]
+    fn attribute_context_test() {
+        let cp = ConstantPool { entries: vec![ConstantPoolEntry::utf8("SourceFile".to_string())] };
+        // public method, one SourceFile attribute pointing at entry 1
+        let data = [0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01];
+        let result = MethodInfo::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(data)), Some(&cp));
+        assert!(matches!(
+            result,
+            Err(ClassFileError::AttributeInWrongContext(name, AttributeContext::Method)) if name == attrtype::SourceFile
+        ));
+
+        assert!(AttributeContext::ClassFile.allows(attrtype::SourceFile));
+        assert!(AttributeContext::Method.allows(attrtype::Code));
+        assert!(!AttributeContext::Field.allows(attrtype::Code));
+        assert!(!AttributeContext::Code.allows(attrtype::Signature));
+        assert!(AttributeContext::Code.allows(attrtype::RuntimeVisibleTypeAnnotations));
+        assert!(AttributeContext::Field.allows("SomethingCustom"));
+    }
+
+    #[test]
+    fn diagnostics_test() {
+        let cp = ConstantPool {
+            entries: vec![
+                ConstantPoolEntry::utf8("Signature".to_string()),
+                ConstantPoolEntry::utf8("Code".to_string()),
+                ConstantPoolEntry::utf8("LineNumberTable".to_string()),
+            ],
+        };
+        // two Signature attributes, and a one byte Code attribute with a line number at pc 5
+        let data = [
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x03,
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01,
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x02, 0x00, 0x01,
+            0x00, 0x02, 0x00, 0x00, 0x00, 0x19, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0xb1, 0x00, 0x00, 0x00, 0x01,
+            0x00, 0x03, 0x00, 0x00, 0x00, 0x06, 0x00, 0x01, 0x00, 0x05, 0x00, 0x01,
+        ];
+        let mut cursor = Cursor::new(data);
+        let mut s = ClassFileStream::new(&mut cursor);
+        MethodInfo::read_from_stream(&mut s, Some(&cp)).unwrap();
+        assert_eq!(
+            s.diagnostics(),
+            [
+                ParseDiagnostic::DuplicateAttribute {
+                    name: attrtype::Signature.to_string(),
+                    context: Some(AttributeContext::Method),
+                },
+                ParseDiagnostic::LineNumberOutOfRange { start_pc: 5, code_length: 1 },
+            ]
+        );
+
+        let options = ParseOptions { diagnostics_as_errors: true, ..ParseOptions::default() };
+        let result = MethodInfo::read_from_stream(&mut ClassFileStream::with_options(&mut Cursor::new(data), options), Some(&cp));
+        assert!(matches!(
+            result,
+            Err(ClassFileError::Diagnostic(ParseDiagnostic::DuplicateAttribute { name, .. })) if name == attrtype::Signature
+        ));
+    }
+
+    #[test]
+    fn thrown_exceptions_test() {
+        let file = include_bytes!("../../../../local/Throwing.class");
+        let class_file = ClassFile::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(file)), None).unwrap();
+        let cp = &class_file.constant_pool;
+        let thrown = |name: &str| {
+            let method = class_file.methods.iter().find(|v| cp.get_utf8_constant(v.name_index as usize).unwrap() == name).unwrap();
+            method.thrown_exceptions(cp).unwrap()
+        };
+        assert_eq!(
+            thrown("load"),
+            ["java/io/IOException", "java/lang/InterruptedException", "java/util/concurrent/TimeoutException"]
+        );
+        assert!(thrown("quiet").is_empty());
+    }
+
+    #[test]
+    fn bridge_test() {
+        let method = |bits| MethodInfo {
+            access_flags: MethodAccessFlags::from_bits(bits).unwrap(),
+            name_index: 0,
+            descriptor_index: 0,
+            attributes: AttributesCollection::new(),
+        };
+        // public synthetic bridge, as javac emits for a covariant override
+        let bridge = method(0x1041);
+        assert!(bridge.is_bridge() && bridge.is_synthetic() && !bridge.is_varargs());
+        let varargs = method(0x0089);
+        assert!(varargs.is_varargs() && !varargs.is_bridge());
+    }
+
+    #[test]
+    fn nested_code_test() {
+        let cp = ConstantPool { entries: vec![ConstantPoolEntry::utf8("Code".to_string())] };
+        // a Code attribute whose own attribute table holds another, empty, Code attribute
+        let data = [
+            0x00, 0x01, 0x00, 0x01, 0x00, 0x01, 0x00, 0x01,
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x1f, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01, 0xb1, 0x00, 0x00, 0x00, 0x01,
+            0x00, 0x01, 0x00, 0x00, 0x00, 0x0c, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+        ];
+        let result = MethodInfo::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(data)), Some(&cp));
+        assert!(matches!(result, Err(ClassFileError::IllegalNestedAttribute(name)) if name == attrtype::Code));
+    }
+
+    #[test]
+    fn source_line_test() {
+        let table = |entries: &[(u16, u16)]| Attributes::LineNumberTable {
+            line_number_table: entries
+                .iter()
+                .map(|(start_pc, line_number)| LineNumberTableEntry {
+                    start_pc: *start_pc,
+                    line_number: *line_number,
+                })
+                .collect(),
+        };
+        let code = Attributes::Code(CodeAttribute {
+            max_stack: 0,
+            max_locals: 0,
+            code: InstructionList {
+                opcodes: vec![],
+                byte_to_code: IntMap::default(),
+                code_to_byte: IntMap::default(),
+            },
+            raw_code: vec![],
+            exception_table: vec![],
+            attributes: {
+                let mut attributes = AttributesCollection::new();
+                attributes.push(attrtype::LineNumberTable, table(&[(10, 7), (2, 5)]));
+                attributes.push(attrtype::LineNumberTable, table(&[(6, 6)]));
+                attributes
+            },
+        });
+        let method = MethodInfo {
+            access_flags: MethodAccessFlags::empty(),
+            name_index: 0,
+            descriptor_index: 0,
+            attributes: {
+                let mut attributes = AttributesCollection::new();
+                attributes.push(attrtype::Code, code);
+                attributes
+            },
+        };
+
+        assert_eq!(method.source_line_for_offset(0), None);
+        assert_eq!(method.source_line_for_offset(2), Some(5));
+        assert_eq!(method.source_line_for_offset(7), Some(6));
+        assert_eq!(method.source_line_for_offset(200), Some(7));
+        assert_eq!(method.code().unwrap().max_stack, 0);
+    }
+
+    #[test]
+    fn access_flags_test() {
+        let flags = MethodAccessFlags::ACC_PUBLIC | MethodAccessFlags::ACC_STATIC;
+        assert!(flags.is_public() && flags.is_static() && !flags.is_final());
+        assert!(flags.is_valid_combination());
+        assert!(!(MethodAccessFlags::ACC_PUBLIC | MethodAccessFlags::ACC_PRIVATE).is_valid_combination());
+        assert!((MethodAccessFlags::ACC_PROTECTED | MethodAccessFlags::ACC_ABSTRACT).is_valid_combination());
+        assert!(!(MethodAccessFlags::ACC_ABSTRACT | MethodAccessFlags::ACC_NATIVE).is_valid_combination());
+    }
+}
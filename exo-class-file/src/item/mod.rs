@@ -1,4 +1,4 @@
-use std::io::Read;
+use crate::io::Read;
 
 pub mod file;
 pub mod constant_pool;
@@ -7,6 +7,7 @@ pub mod fields;
 pub mod methods;
 pub mod opcodes;
 pub mod ids;
+mod semantic;
 
 use crate::{error, stream::ClassFileStream};
 
@@ -17,5 +18,5 @@ pub trait ClassFileItem {
     /// Read this item from a class file stream.
     fn read_from_stream<R: Read>(s: &mut ClassFileStream<R>, cp: Option<&ConstantPool>) -> error::Result<Self>
     where
-        Self: std::marker::Sized;
+        Self: core::marker::Sized;
 }
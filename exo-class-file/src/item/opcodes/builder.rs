@@ -0,0 +1,99 @@
+//! Building code with labelled branch targets.
+
+use alloc::{vec, vec::Vec};
+
+use super::{edit::set_branch_offsets, CodeVerificationError, InstructionList, VMOpcode};
+
+/// A branch target in a [CodeBuilder], placed with [CodeBuilder::bind].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Label(usize);
+
+/// Builds an [InstructionList], resolving branches to labels in to byte
+/// offsets once all the code is known.
+#[derive(Debug, Default)]
+pub struct CodeBuilder {
+    opcodes: Vec<VMOpcode>,
+    /// The instruction index each label is bound to.
+    labels: Vec<Option<usize>>,
+    /// The labels each branching instruction targets, in the
+    /// order they're returned by `branch_offsets`.
+    branches: Vec<(usize, Vec<Label>)>,
+}
+
+impl CodeBuilder {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Create a label, not yet bound to an instruction.
+    pub fn new_label(&mut self) -> Label {
+        self.labels.push(None);
+        Label(self.labels.len() - 1)
+    }
+
+    /// Bind `label` to the next instruction added.
+    ///
+    /// Panics if `label` wasn't created by this builder.
+    pub fn bind(&mut self, label: Label) -> &mut Self {
+        self.labels[label.0] = Some(self.opcodes.len());
+        self
+    }
+
+    /// Add an instruction. Branch offsets in it are kept as they are.
+    pub fn push(&mut self, op: VMOpcode) -> &mut Self {
+        self.opcodes.push(op);
+        self
+    }
+
+    /// Add a branch to `target`, such as `branch(VMOpcode::ifeq, target)`.
+    pub fn branch(&mut self, op: fn(i16) -> VMOpcode, target: Label) -> &mut Self {
+        self.push_branch(op(0), vec![target])
+    }
+
+    /// Add a `goto_w` to `target`.
+    pub fn goto_w(&mut self, target: Label) -> &mut Self {
+        self.push_branch(VMOpcode::goto_w(0), vec![target])
+    }
+
+    /// Add a `tableswitch` jumping to `targets[i]` for the key `low + i`.
+    pub fn tableswitch(&mut self, low: i32, default: Label, targets: &[Label]) -> &mut Self {
+        let high = low + targets.len() as i32 - 1;
+        let op = VMOpcode::tableswitch(0, low, high, vec![0; targets.len()]);
+        self.push_branch(op, [&[default], targets].concat())
+    }
+
+    /// Add a `lookupswitch`. The pairs are sorted by key, as the format requires.
+    pub fn lookupswitch(&mut self, default: Label, pairs: &[(i32, Label)]) -> &mut Self {
+        let mut pairs = pairs.to_vec();
+        pairs.sort_by_key(|(key, _)| *key);
+        let op = VMOpcode::lookupswitch(0, pairs.iter().map(|(key, _)| (*key, 0)).collect());
+        let targets = [default].into_iter().chain(pairs.into_iter().map(|(_, label)| label)).collect();
+        self.push_branch(op, targets)
+    }
+
+    fn push_branch(&mut self, op: VMOpcode, targets: Vec<Label>) -> &mut Self {
+        self.branches.push((self.opcodes.len(), targets));
+        self.push(op)
+    }
+
+    /// Lay out the code and resolve every label.
+    ///
+    /// Fails with `UnboundLabel` if a branch targets a label that was never
+    /// bound, and `BranchLocOutOfBounds` if one is bound past the last instruction.
+    pub fn build(self) -> core::result::Result<InstructionList, CodeVerificationError> {
+        let mut list = InstructionList::from_opcodes(self.opcodes);
+        for (index, targets) in self.branches {
+            let start = list.code_to_byte[&index] as isize;
+            let offsets = targets
+                .iter()
+                .map(|label| {
+                    let target = self.labels.get(label.0).copied().flatten().ok_or(CodeVerificationError::UnboundLabel)?;
+                    let byte = list.code_to_byte.get(&target).ok_or(CodeVerificationError::BranchLocOutOfBounds)?;
+                    Ok(*byte as isize - start)
+                })
+                .collect::<core::result::Result<Vec<_>, _>>()?;
+            set_branch_offsets(&mut list.opcodes[index], &offsets)?;
+        }
+        Ok(list)
+    }
+}
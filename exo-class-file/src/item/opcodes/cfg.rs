@@ -0,0 +1,235 @@
+//! Control-flow graphs over decoded instructions.
+
+use alloc::{vec, vec::Vec};
+use crate::item::attribute_info::ExceptionTableEntry;
+
+use super::{CodeVerificationError, InstructionList, VMOpcode};
+
+/// The kind of a control-flow edge.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum EdgeKind {
+    /// Execution falls through to the next block.
+    Fallthrough,
+    /// A conditional or unconditional branch.
+    Branch,
+    /// A case (or the default) of a `tableswitch` or `lookupswitch`.
+    Switch,
+    /// An exception handler covering the block.
+    Exception,
+}
+
+/// An edge to a successor block.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Edge {
+    /// Index of the successor block.
+    pub target: usize,
+    /// The kind of the edge.
+    pub kind: EdgeKind,
+}
+
+/// A basic block, spanning the instruction indices `start..end`.
+#[derive(Clone, Debug)]
+pub struct BasicBlock {
+    /// Index of the first instruction in the block.
+    pub start: usize,
+    /// Index one past the last instruction in the block.
+    pub end: usize,
+    /// Successors of this block.
+    pub successors: Vec<Edge>,
+}
+
+/// A control-flow graph. The entry block is always the first block.
+#[derive(Clone, Debug)]
+pub struct Cfg {
+    /// The basic blocks, ordered by their starting instruction.
+    pub blocks: Vec<BasicBlock>,
+}
+
+impl Cfg {
+    /// Get the index of the block containing the instruction at `index`.
+    pub fn block_of(&self, index: usize) -> Option<usize> {
+        let i = self.blocks.partition_point(|b| b.start <= index);
+        let block = i.checked_sub(1)?;
+        (index < self.blocks[block].end).then_some(block)
+    }
+}
+
+/// Whether an instruction never continues to the next instruction.
+fn ends_flow(op: &VMOpcode) -> bool {
+    matches!(
+        op,
+        VMOpcode::goto(_)
+            | VMOpcode::goto_w(_)
+            | VMOpcode::tableswitch(..)
+            | VMOpcode::lookupswitch(..)
+            | VMOpcode::ireturn()
+            | VMOpcode::lreturn()
+            | VMOpcode::freturn()
+            | VMOpcode::dreturn()
+            | VMOpcode::areturn()
+            | VMOpcode::r#return()
+            | VMOpcode::athrow()
+            | VMOpcode::ret(_)
+    ) || matches!(op, VMOpcode::wide_format1(op, _) if matches!(**op, VMOpcode::ret(_)))
+}
+
+/// The byte offsets, relative to the instruction, that an instruction may branch to.
+fn branch_offsets(op: &VMOpcode) -> (Vec<isize>, EdgeKind) {
+    match op {
+        VMOpcode::goto(v)
+        | VMOpcode::ifeq(v)
+        | VMOpcode::ifne(v)
+        | VMOpcode::ifle(v)
+        | VMOpcode::iflt(v)
+        | VMOpcode::ifge(v)
+        | VMOpcode::ifgt(v)
+        | VMOpcode::ifnull(v)
+        | VMOpcode::ifnonnull(v)
+        | VMOpcode::if_icmpeq(v)
+        | VMOpcode::if_icmpne(v)
+        | VMOpcode::if_icmple(v)
+        | VMOpcode::if_icmplt(v)
+        | VMOpcode::if_icmpge(v)
+        | VMOpcode::if_icmpgt(v)
+        | VMOpcode::if_acmpeq(v)
+        | VMOpcode::if_acmpne(v)
+        | VMOpcode::jsr(v) => (vec![*v as isize], EdgeKind::Branch),
+        VMOpcode::goto_w(v) | VMOpcode::jsr_w(v) => (vec![*v as i32 as isize], EdgeKind::Branch),
+        VMOpcode::tableswitch(default, _, _, offsets) => (
+            core::iter::once(default)
+                .chain(offsets.iter())
+                .map(|v| *v as isize)
+                .collect(),
+            EdgeKind::Switch,
+        ),
+        VMOpcode::lookupswitch(default, pairs) => (
+            core::iter::once(*default)
+                .chain(pairs.iter().map(|v| v.1))
+                .map(|v| v as isize)
+                .collect(),
+            EdgeKind::Switch,
+        ),
+        _ => (vec![], EdgeKind::Branch),
+    }
+}
+
+impl InstructionList {
+    /// Length of the code in bytes.
+    fn byte_length(&self) -> usize {
+        self.byte_to_code.keys().max().map(|v| v + 1).unwrap_or(0)
+    }
+
+    /// Get the instruction index starting at the byte offset `byte`.
+    fn instruction_at(&self, byte: usize) -> core::result::Result<usize, CodeVerificationError> {
+        let index = *self
+            .byte_to_code
+            .get(&byte)
+            .ok_or(CodeVerificationError::BranchLocOutOfBounds)?;
+        if self.code_to_byte.get(&index) != Some(&byte) {
+            return Err(CodeVerificationError::BranchLocOutOfBounds);
+        }
+        Ok(index)
+    }
+
+    /// Get the instruction indices an instruction branches to.
+    pub(super) fn branch_targets(&self, index: usize) -> core::result::Result<(Vec<usize>, EdgeKind), CodeVerificationError> {
+        let (offsets, kind) = branch_offsets(&self.opcodes[index]);
+        let start = self.code_to_byte[&index];
+        let targets = offsets
+            .into_iter()
+            .map(|off| {
+                let byte = start
+                    .checked_add_signed(off)
+                    .ok_or(CodeVerificationError::BranchLocOutOfBounds)?;
+                self.instruction_at(byte)
+            })
+            .collect::<core::result::Result<_, _>>()?;
+        Ok((targets, kind))
+    }
+
+    /// Build a control-flow graph of this code.
+    ///
+    /// Blocks are split at branch targets, exception handler boundaries, and
+    /// after any instruction which branches, returns or throws.
+    pub fn build_cfg(
+        &self,
+        exception_table: &[ExceptionTableEntry],
+    ) -> core::result::Result<Cfg, CodeVerificationError> {
+        let len = self.opcodes.len();
+        let byte_length = self.byte_length();
+        let mut leaders = vec![false; len + 1];
+        if len > 0 {
+            leaders[0] = true;
+        }
+        leaders[len] = true;
+
+        for (index, op) in self.opcodes.iter().enumerate() {
+            let (targets, _) = self.branch_targets(index)?;
+            if !targets.is_empty() || ends_flow(op) {
+                leaders[index + 1] = true;
+            }
+            for target in targets {
+                leaders[target] = true;
+            }
+        }
+
+        // the end of `pc_range` is the exclusive `end_pc`
+        for entry in exception_table {
+            let end = *entry.pc_range.end() as usize;
+            leaders[self.instruction_at(*entry.pc_range.start() as usize)?] = true;
+            leaders[self.instruction_at(entry.handler_pc as usize)?] = true;
+            if end != byte_length {
+                leaders[self.instruction_at(end)?] = true;
+            }
+        }
+
+        let starts: Vec<usize> = (0..len).filter(|i| leaders[*i]).collect();
+        let mut cfg = Cfg {
+            blocks: starts
+                .iter()
+                .enumerate()
+                .map(|(i, start)| BasicBlock {
+                    start: *start,
+                    end: starts.get(i + 1).copied().unwrap_or(len),
+                    successors: vec![],
+                })
+                .collect(),
+        };
+
+        for block in 0..cfg.blocks.len() {
+            let (start, end) = (cfg.blocks[block].start, cfg.blocks[block].end);
+            let last = end - 1;
+            let (targets, kind) = self.branch_targets(last)?;
+            let mut successors = vec![];
+            for target in targets {
+                successors.push(Edge {
+                    target: cfg.block_of(target).unwrap(),
+                    kind,
+                });
+            }
+            if !ends_flow(&self.opcodes[last]) && end < len {
+                successors.push(Edge {
+                    target: block + 1,
+                    kind: EdgeKind::Fallthrough,
+                });
+            }
+
+            let start_byte = self.code_to_byte[&start];
+            let end_byte = self.code_to_byte.get(&end).copied().unwrap_or(byte_length);
+            for entry in exception_table {
+                let (range_start, range_end) = (*entry.pc_range.start() as usize, *entry.pc_range.end() as usize);
+                if range_start < end_byte && start_byte < range_end {
+                    let edge = Edge {
+                        target: cfg.block_of(self.instruction_at(entry.handler_pc as usize)?).unwrap(),
+                        kind: EdgeKind::Exception,
+                    };
+                    if !successors.contains(&edge) {
+                        successors.push(edge);
+                    }
+                }
+            }
+            cfg.blocks[block].successors = successors;
+        }
+        Ok(cfg)
+    }
+}
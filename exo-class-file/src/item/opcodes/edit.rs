@@ -0,0 +1,147 @@
+//! Editing decoded instructions.
+
+use alloc::{vec, vec::Vec};
+
+use super::{CodeVerificationError, InstructionList, VMOpcode};
+
+/// Set the byte offsets an instruction branches to, in the
+/// order they're returned by `branch_offsets`.
+pub(super) fn set_branch_offsets(op: &mut VMOpcode, offsets: &[isize]) -> core::result::Result<(), CodeVerificationError> {
+    let short = |v: isize| i16::try_from(v).map_err(|_| CodeVerificationError::BranchOffsetOverflow);
+    let wide = |v: isize| i32::try_from(v).map_err(|_| CodeVerificationError::BranchOffsetOverflow);
+    match op {
+        VMOpcode::goto(v)
+        | VMOpcode::ifeq(v)
+        | VMOpcode::ifne(v)
+        | VMOpcode::ifle(v)
+        | VMOpcode::iflt(v)
+        | VMOpcode::ifge(v)
+        | VMOpcode::ifgt(v)
+        | VMOpcode::ifnull(v)
+        | VMOpcode::ifnonnull(v)
+        | VMOpcode::if_icmpeq(v)
+        | VMOpcode::if_icmpne(v)
+        | VMOpcode::if_icmple(v)
+        | VMOpcode::if_icmplt(v)
+        | VMOpcode::if_icmpge(v)
+        | VMOpcode::if_icmpgt(v)
+        | VMOpcode::if_acmpeq(v)
+        | VMOpcode::if_acmpne(v)
+        | VMOpcode::jsr(v) => *v = short(offsets[0])?,
+        VMOpcode::goto_w(v) | VMOpcode::jsr_w(v) => *v = wide(offsets[0])? as u32,
+        VMOpcode::tableswitch(default, _, _, table) => {
+            *default = wide(offsets[0])?;
+            for (v, off) in table.iter_mut().zip(&offsets[1..]) {
+                *v = wide(*off)?;
+            }
+        }
+        VMOpcode::lookupswitch(default, pairs) => {
+            *default = wide(offsets[0])?;
+            for (v, off) in pairs.iter_mut().zip(&offsets[1..]) {
+                v.1 = wide(*off)?;
+            }
+        }
+        _ => (),
+    }
+    Ok(())
+}
+
+impl InstructionList {
+    /// The instruction at `index` with its branch offsets zeroed, and the
+    /// indices of the instructions it branches to.
+    pub(crate) fn normalized_instruction(&self, index: usize) -> core::result::Result<(VMOpcode, Vec<usize>), CodeVerificationError> {
+        let (targets, _) = self.branch_targets(index)?;
+        let mut op = self.opcodes[index].clone();
+        set_branch_offsets(&mut op, &vec![0; targets.len()])?;
+        Ok((op, targets))
+    }
+
+    /// Replace the instructions with `opcodes`, where `old_index` maps a new
+    /// index to the instruction's old index and `remap` maps an old branch
+    /// target to its new index, then fix up every branch offset.
+    fn rewrite(
+        &mut self,
+        opcodes: Vec<VMOpcode>,
+        old_index: impl Fn(usize) -> Option<usize>,
+        remap: impl Fn(usize) -> Option<usize>,
+    ) -> core::result::Result<(), CodeVerificationError> {
+        let mut targets = Vec::with_capacity(self.opcodes.len());
+        for index in 0..self.opcodes.len() {
+            let (branch_targets, _) = self.branch_targets(index)?;
+            targets.push(
+                branch_targets
+                    .into_iter()
+                    .map(|v| remap(v).ok_or(CodeVerificationError::BranchLocOutOfBounds))
+                    .collect::<core::result::Result<Vec<_>, _>>()?,
+            );
+        }
+
+        let mut list = InstructionList::from_opcodes(opcodes);
+        for index in 0..list.opcodes.len() {
+            let Some(old) = old_index(index) else {
+                continue;
+            };
+            if targets[old].is_empty() {
+                continue;
+            }
+            let start = list.code_to_byte[&index] as isize;
+            let offsets: Vec<isize> = targets[old]
+                .iter()
+                .map(|target| list.code_to_byte[target] as isize - start)
+                .collect();
+            set_branch_offsets(&mut list.opcodes[index], &offsets)?;
+        }
+        *self = list;
+        Ok(())
+    }
+
+    /// Insert an instruction before the instruction at `at`.
+    ///
+    /// Branches which targeted the instruction at `at` now target the
+    /// inserted one, and all branch offsets are adjusted so they still
+    /// reach the same instructions. Exception tables and other attributes
+    /// referring to byte offsets are not updated.
+    pub fn insert_instruction(&mut self, at: usize, op: VMOpcode) -> core::result::Result<(), CodeVerificationError> {
+        if at > self.opcodes.len() {
+            return Err(CodeVerificationError::BranchLocOutOfBounds);
+        }
+        let mut opcodes = self.opcodes.clone();
+        opcodes.insert(at, op);
+        self.rewrite(
+            opcodes,
+            |new| match new {
+                v if v < at => Some(v),
+                v if v == at => None,
+                v => Some(v - 1),
+            },
+            |old| Some(if old > at { old + 1 } else { old }),
+        )
+    }
+
+    /// Remove the instruction at `index`.
+    ///
+    /// Branches to the removed instruction are moved to the instruction
+    /// following it, and all branch offsets are adjusted so they still
+    /// reach the same instructions. Exception tables and other attributes
+    /// referring to byte offsets are not updated.
+    pub fn remove_instruction(&mut self, index: usize) -> core::result::Result<VMOpcode, CodeVerificationError> {
+        if index >= self.opcodes.len() {
+            return Err(CodeVerificationError::BranchLocOutOfBounds);
+        }
+        let len = self.opcodes.len();
+        let mut opcodes = self.opcodes.clone();
+        let removed = opcodes.remove(index);
+        self.rewrite(
+            opcodes,
+            |new| Some(if new >= index { new + 1 } else { new }),
+            |old| match old {
+                v if v < index => Some(v),
+                // a branch to the removed last instruction has nowhere to go
+                v if v == index && index + 1 == len => None,
+                v if v == index => Some(v),
+                v => Some(v - 1),
+            },
+        )?;
+        Ok(removed)
+    }
+}
@@ -1,23 +1,31 @@
-use std::io::Read;
+use alloc::{boxed::Box, string::ToString, vec, vec::Vec};
+use crate::io::Read;
 
 use exo_parser::{error::ParsingError, Lexer};
-use fnv::FnvHashMap;
 
 use super::{
     constant_pool::ConstantPoolEntry,
     file::ClassFile,
     ids::{
         class::ClassName,
-        field::{FieldDescriptor, FieldType},
-        method::MethodDescriptor,
+        field::{BaseType, FieldDescriptor, FieldType},
+        method::{MethodDescriptor, ReturnDescriptor},
     },
     ClassFileItem, ConstantPool,
 };
 use crate::{
+    IntMap,
     error::{self, ClassFileError},
     stream::ClassFileStream,
 };
 
+pub use self::builder::{CodeBuilder, Label};
+pub use self::cfg::{BasicBlock, Cfg, Edge, EdgeKind};
+
+mod builder;
+mod cfg;
+mod edit;
+
 macro_rules! numerical_enum {
     (
         $(#[$inner:ident $($args:tt)*])*
@@ -28,12 +36,22 @@ macro_rules! numerical_enum {
         }
     ) => {
         $(#[$inner $($args)*])*
-        #[derive(Clone, Copy, Debug)]
+        #[derive(Clone, Copy, Debug, PartialEq, Eq)]
         pub enum $name {
             $(
                 $vident
             ),*
         }
+        impl $name {
+            /// The encoded value of this variant.
+            pub fn value(&self) -> $vartype {
+                match self {
+                    $(
+                        Self::$vident => $val,
+                    )*
+                }
+            }
+        }
         impl ClassFileItem for $name {
             fn read_from_stream<R: Read>(s: &mut ClassFileStream<R>, _cp: Option<&ConstantPool>) -> error::Result<Self> {
                 match <$vartype>::read_from_stream(s, _cp)? {
@@ -48,6 +66,9 @@ macro_rules! numerical_enum {
 }
 numerical_enum! {
     /// Types used in `newarray` opcode.
+    ///
+    /// Any other value is rejected with [ClassFileError::UnknownEnumVariant]
+    /// when the instruction is read, so `newarray` needs no further verification.
     ArrayTypeCode: u8 {
         T_BOOLEAN = 4,
         T_CHAR = 5,
@@ -60,6 +81,47 @@ numerical_enum! {
     }
 }
 
+/// A coarse category of instructions, from [VMOpcode::group].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum OpcodeGroup {
+    /// Instructions pushing a constant, including `ldc`.
+    Constant,
+    /// Loads from local variables and arrays.
+    Load,
+    /// Stores into local variables and arrays.
+    Store,
+    /// Instructions rearranging the operand stack, like `pop`, `dup` and `swap`.
+    Stack,
+    /// Arithmetic, bitwise and shift instructions, and `iinc`.
+    Arithmetic,
+    /// Conversions between primitive types.
+    Conversion,
+    /// `lcmp` and the floating point comparisons.
+    Comparison,
+    /// Conditional and unconditional jumps, switches, `jsr` and `ret`.
+    Branch,
+    /// The `return` family.
+    Return,
+    /// `getfield`, `putfield`, `getstatic` and `putstatic`.
+    FieldAccess,
+    /// The `invoke` family.
+    Invoke,
+    /// Object and array creation, `arraylength`, type checks and monitors.
+    Object,
+    /// `nop`, `athrow` and the reserved opcodes.
+    Other,
+}
+
+/// Stack effect of an opcode definition, if it has a fixed one.
+macro_rules! stack_delta {
+    () => {
+        None
+    };
+    ($pop:literal, $push:literal) => {
+        Some(($pop, $push))
+    };
+}
+
 #[macro_use]
 /// Macro for defining an opcode enum.
 /// Automatically implements parsing.
@@ -68,11 +130,11 @@ macro_rules! def_opcode {
         $opcodename:ident {
             $(
                 $(#[$inner:ident $($args:tt)*])*
-                ($code:expr) = $name:ident($($part:ty),*)
+                ($code:expr) = $name:ident($($part:ty),*) $(=> ($pop:literal, $push:literal))?
             ),*
         }
     ) => {
-        #[derive(Debug, Clone)]
+        #[derive(Debug, Clone, PartialEq, Eq)]
         pub enum $opcodename {
             /// Access jump table by key match and jump
             ///
@@ -108,6 +170,78 @@ macro_rules! def_opcode {
         }
 
         impl $opcodename {
+            /// The opcode byte of this instruction. For `wide`
+            /// instructions this is the `wide` opcode itself.
+            pub fn opcode(&self) -> u8 {
+                match self {
+                    Self::lookupswitch(..) => 0xab,
+                    Self::tableswitch(..) => 0xaa,
+                    Self::wide_format1(..) | Self::wide_format2(..) => 0xc4,
+                    $(
+                        Self::$name(..) => $code,
+                    )*
+                }
+            }
+
+            /// The mnemonic of this instruction.
+            pub fn mnemonic(&self) -> &'static str {
+                match self {
+                    Self::lookupswitch(..) => "lookupswitch",
+                    Self::tableswitch(..) => "tableswitch",
+                    Self::wide_format1(..) | Self::wide_format2(..) => "wide",
+                    $(
+                        Self::$name(..) => stringify!($name).trim_start_matches("r#"),
+                    )*
+                }
+            }
+
+            /// The number of operands following the opcode, not counting
+            /// switch padding. The modified opcode of `wide` counts as one.
+            pub fn operand_count(&self) -> usize {
+                match self {
+                    // default, npairs, and a match and offset per pair
+                    Self::lookupswitch(_, pairs) => 2 + pairs.len() * 2,
+                    // default, low, high, and the jump offsets
+                    Self::tableswitch(_, _, _, offsets) => 3 + offsets.len(),
+                    Self::wide_format1(..) => 2,
+                    Self::wide_format2(..) => 3,
+                    $(
+                        Self::$name(..) => 0 $(+ { let _ = stringify!($part); 1 })*,
+                    )*
+                }
+            }
+
+            /// The number of operand stack slots this instruction pops and pushes,
+            /// with `long` and `double` values taking two slots.
+            ///
+            /// Returns `None` for instructions whose effect depends on a
+            /// descriptor or operand, like field access and invocations.
+            pub fn stack_delta(&self) -> Option<(u8, u8)> {
+                match self {
+                    Self::lookupswitch(..) | Self::tableswitch(..) => Some((1, 0)),
+                    Self::wide_format1(op, _) | Self::wide_format2(op, _, _) => op.stack_delta(),
+                    $(
+                        Self::$name(..) => stack_delta!($($pop, $push)?),
+                    )*
+                }
+            }
+
+            /// The encoded length of this instruction in bytes,
+            /// when it starts at `byte_offset` in the code.
+            pub fn byte_length(&self, byte_offset: usize) -> usize {
+                // switch operands are padded to a multiple of 4 from the start of the code
+                let pad_count = (4 - ((byte_offset + 1) % 4)) % 4;
+                match self {
+                    Self::lookupswitch(_, pairs) => 1 + pad_count + 8 + pairs.len() * 8,
+                    Self::tableswitch(_, _, _, offsets) => 1 + pad_count + 12 + offsets.len() * 4,
+                    Self::wide_format1(..) => 4,
+                    Self::wide_format2(..) => 6,
+                    $(
+                        Self::$name(..) => 1 $(+ core::mem::size_of::<$part>())*,
+                    )*
+                }
+            }
+
             fn read_from_stream<R: Read>(s: &mut ClassFileStream<R>, _cp: Option<&ConstantPool>, current_byte_offset: usize) -> error::Result<(Self, usize)> {
                 let start = s.1;
                 let v = match s.read_u1()? {
@@ -115,7 +249,8 @@ macro_rules! def_opcode {
                         $code => Self::$name($(<$part>::read_from_stream(s, _cp)?),*),
                     )*
                     0xab => { // lookupswitch special case
-                        let pad_count = 4 - (current_byte_offset % 4);
+                        // pad so the operands start at a multiple of 4 from the start of the code
+                        let pad_count = (4 - ((current_byte_offset % 4 + 1) % 4)) % 4;
                         s.read_dynamic(pad_count)?;
                         let default = s.read_u4()? as i32;
                         let npairs = s.read_u4()?;
@@ -128,7 +263,8 @@ macro_rules! def_opcode {
                         Self::lookupswitch(default, pairs)
                     },
                     0xaa => { // tableswitch special case
-                        let pad_count = 4 - (current_byte_offset % 4);
+                        // pad so the operands start at a multiple of 4 from the start of the code
+                        let pad_count = (4 - ((current_byte_offset % 4 + 1) % 4)) % 4;
                         s.read_dynamic(pad_count)?;
                         let default = s.read_u4()? as i32;
                         let low = s.read_u4()? as i32;
@@ -136,17 +272,34 @@ macro_rules! def_opcode {
 
                         let mut offsets = vec![];
 
-                        for _ in 0..(high.checked_sub(low + 1).ok_or(ClassFileError::ArithmeticError)?) {
+                        let count = high.checked_sub(low).and_then(|v| v.checked_add(1)).ok_or(ClassFileError::ArithmeticError)?;
+                        for _ in 0..count {
                             offsets.push(s.read_u4()? as i32);
                         }
                         Self::tableswitch(default, low, high, offsets)
                     },
                     0xc4 => { // wide special case
-                        let opcode = $opcodename::read_from_stream(s, _cp, current_byte_offset)?;
-                        if matches!(opcode.0, $opcodename::iinc( .. )) {
-                            Self::wide_format2(Box::new(opcode.0), s.read_u2()?, s.read_u2()?)
+                        // the operands of the modified opcode are replaced by the
+                        // wide operands, so only the opcode byte is read here
+                        let opcode = match s.read_u1()? {
+                            0x84 => $opcodename::iinc(0, 0),
+                            0x15 => $opcodename::iload(0),
+                            0x16 => $opcodename::lload(0),
+                            0x17 => $opcodename::fload(0),
+                            0x18 => $opcodename::dload(0),
+                            0x19 => $opcodename::aload(0),
+                            0x36 => $opcodename::istore(0),
+                            0x37 => $opcodename::lstore(0),
+                            0x38 => $opcodename::fstore(0),
+                            0x39 => $opcodename::dstore(0),
+                            0x3a => $opcodename::astore(0),
+                            0xa9 => $opcodename::ret(0),
+                            v => return Err(ClassFileError::UnknownOpcodeError(v))
+                        };
+                        if matches!(opcode, $opcodename::iinc( .. )) {
+                            Self::wide_format2(Box::new(opcode), s.read_u2()?, s.read_u2()?)
                         } else {
-                            Self::wide_format1(Box::new(opcode.0), s.read_u2()?)
+                            Self::wide_format1(Box::new(opcode), s.read_u2()?)
                         }
                     }
                     v => return Err(ClassFileError::UnknownOpcodeError(v))
@@ -178,8 +331,8 @@ macro_rules! def_opcode {
 #[derive(Debug, Clone)]
 pub struct InstructionList {
     pub opcodes: Vec<VMOpcode>,
-    pub byte_to_code: FnvHashMap<usize, usize>,
-    pub code_to_byte: FnvHashMap<usize, usize>
+    pub byte_to_code: IntMap<usize, usize>,
+    pub code_to_byte: IntMap<usize, usize>
 }
 
 /// Possible errors to come from code verification.
@@ -221,6 +374,13 @@ pub enum CodeVerificationError {
     /// Bad method name
     BadMethodName,
 
+    /// Returned when the descriptor of a field referenced by a field instruction is malformed.
+    BadFieldDescriptor,
+
+    /// Returned when an `<init>` method called by `invokespecial` has a
+    /// malformed descriptor or one which doesn't return `void`.
+    BadInitDescriptor,
+
     /// `new` not referencing a class
     NewNotRefClass,
 
@@ -230,18 +390,79 @@ pub enum CodeVerificationError {
     /// Bad `multianewarray`
     BadMultiANewArray,
 
+    /// Returned when a branch offset is too large for its instruction.
+    BranchOffsetOverflow,
+
     /// Local index out of range
-    LocalIndexOutOfRange
+    LocalIndexOutOfRange,
+
+    /// Returned when code contains one of the reserved opcodes
+    /// `breakpoint`, `impdep1` or `impdep2`.
+    ReservedOpcode,
+
+    /// Returned when `jsr` or `jsr_w` appears in a class file of version
+    /// 51.0 or above, where subroutines are no longer allowed.
+    JsrInModernClass,
+
+    /// Returned when a return instruction doesn't match the method's
+    /// return type, holding the index of the instruction.
+    ReturnTypeMismatch(usize),
+
+    /// Returned when `max_locals` is too small to hold the method's
+    /// arguments or a local used by its code, holding the smallest
+    /// valid `max_locals`.
+    MaxLocalsTooSmall(usize),
+
+    /// Returned when a [CodeBuilder] branches to a label that was never bound.
+    UnboundLabel,
+
+    /// Returned when a `StackMapTable` frame chops more locals than the
+    /// previous frame has or leaves more than `max_locals`, holding the
+    /// index of the frame.
+    BadStackMapFrame(usize),
+}
+
+/// The local variable index of a non-`wide` instruction which accesses one.
+fn local_index(op: &VMOpcode) -> Option<usize> {
+    Some(match op {
+        VMOpcode::iload(v) | VMOpcode::lload(v) | VMOpcode::fload(v) | VMOpcode::dload(v) | VMOpcode::aload(v) => *v as usize,
+        VMOpcode::istore(v) | VMOpcode::lstore(v) | VMOpcode::fstore(v) | VMOpcode::dstore(v) | VMOpcode::astore(v) => *v as usize,
+        VMOpcode::iinc(v, _) | VMOpcode::ret(v) => *v as usize,
+        VMOpcode::iload_0() | VMOpcode::lload_0() | VMOpcode::fload_0() | VMOpcode::dload_0() | VMOpcode::aload_0() => 0,
+        VMOpcode::iload_1() | VMOpcode::lload_1() | VMOpcode::fload_1() | VMOpcode::dload_1() | VMOpcode::aload_1() => 1,
+        VMOpcode::iload_2() | VMOpcode::lload_2() | VMOpcode::fload_2() | VMOpcode::dload_2() | VMOpcode::aload_2() => 2,
+        VMOpcode::iload_3() | VMOpcode::lload_3() | VMOpcode::fload_3() | VMOpcode::dload_3() | VMOpcode::aload_3() => 3,
+        VMOpcode::istore_0() | VMOpcode::lstore_0() | VMOpcode::fstore_0() | VMOpcode::dstore_0() | VMOpcode::astore_0() => 0,
+        VMOpcode::istore_1() | VMOpcode::lstore_1() | VMOpcode::fstore_1() | VMOpcode::dstore_1() | VMOpcode::astore_1() => 1,
+        VMOpcode::istore_2() | VMOpcode::lstore_2() | VMOpcode::fstore_2() | VMOpcode::dstore_2() | VMOpcode::astore_2() => 2,
+        VMOpcode::istore_3() | VMOpcode::lstore_3() | VMOpcode::fstore_3() | VMOpcode::dstore_3() | VMOpcode::astore_3() => 3,
+        _ => return None,
+    })
+}
+
+/// The number of local variables accessed by an instruction
+/// which accesses one, two for a `long` or `double`.
+fn local_width(op: &VMOpcode) -> Option<usize> {
+    Some(match op {
+        VMOpcode::lload(_) | VMOpcode::dload(_) | VMOpcode::lstore(_) | VMOpcode::dstore(_) => 2,
+        VMOpcode::lload_0() | VMOpcode::lload_1() | VMOpcode::lload_2() | VMOpcode::lload_3() => 2,
+        VMOpcode::dload_0() | VMOpcode::dload_1() | VMOpcode::dload_2() | VMOpcode::dload_3() => 2,
+        VMOpcode::lstore_0() | VMOpcode::lstore_1() | VMOpcode::lstore_2() | VMOpcode::lstore_3() => 2,
+        VMOpcode::dstore_0() | VMOpcode::dstore_1() | VMOpcode::dstore_2() | VMOpcode::dstore_3() => 2,
+        op => {
+            local_index(op)?;
+            1
+        }
+    })
 }
 
 /// Check that an entry in the constant pool matches some pattern `p`.
+///
+/// Indices are 1-based, so `0`, anything past `entries.len()` and the
+/// unusable slot after a `Long` or `Double` are all `BadConstantPoolIndex`.
 macro_rules! check_constant_pool {
     ($v:expr, $cp:expr, $p:pat) => {{
-        if $v as usize > $cp.entries.len() {
-            return Err(CodeVerificationError::BadConstantPoolIndex);
-        }
-
-        let entry = $cp.get_constant($v as usize).map_err(CodeVerificationError::ClassFileError)?;
+        let entry = $cp.get_constant($v as usize).map_err(|_| CodeVerificationError::BadConstantPoolIndex)?;
         match entry {
             $p => Ok(()),
             _ => Err(CodeVerificationError::BadConstantPoolType),
@@ -249,12 +470,19 @@ macro_rules! check_constant_pool {
     }};
 }
 
+/// Get the name and descriptor of the member reference at `index`.
 macro_rules! get_name_and_type {
     ($index:expr, $cp:expr) => {{
+        let name_and_type_index = match $cp.get_constant($index as usize).map_err(CodeVerificationError::ClassFileError)? {
+            ConstantPoolEntry::Fieldref { name_and_type_index, .. }
+            | ConstantPoolEntry::Methodref { name_and_type_index, .. }
+            | ConstantPoolEntry::InterfaceMethodref { name_and_type_index, .. } => *name_and_type_index,
+            _ => return Err(CodeVerificationError::WrongConstantType),
+        };
         let (name_index, descriptor_index) = if let ConstantPoolEntry::NameAndType {
             name_index,
             descriptor_index,
-        } = $cp.get_constant($index as usize).map_err(CodeVerificationError::ClassFileError)?
+        } = $cp.get_constant(name_and_type_index as usize).map_err(CodeVerificationError::ClassFileError)?
         {
             (*name_index, *descriptor_index)
         } else {
@@ -291,6 +519,32 @@ macro_rules! get_class {
     }};
 }
 
+impl VMOpcode {
+    /// The category of this instruction, following the grouping of opcodes
+    /// in JVMS chapter 7. A `wide` instruction takes the group of the
+    /// instruction it modifies.
+    pub fn group(&self) -> OpcodeGroup {
+        if let Self::wide_format1(op, _) | Self::wide_format2(op, _, _) = self {
+            return op.group();
+        }
+        match self.opcode() {
+            0x01..=0x14 => OpcodeGroup::Constant,
+            0x15..=0x35 => OpcodeGroup::Load,
+            0x36..=0x56 => OpcodeGroup::Store,
+            0x57..=0x5f => OpcodeGroup::Stack,
+            0x60..=0x84 => OpcodeGroup::Arithmetic,
+            0x85..=0x93 => OpcodeGroup::Conversion,
+            0x94..=0x98 => OpcodeGroup::Comparison,
+            0x99..=0xab | 0xc6..=0xc9 => OpcodeGroup::Branch,
+            0xac..=0xb1 => OpcodeGroup::Return,
+            0xb2..=0xb5 => OpcodeGroup::FieldAccess,
+            0xb6..=0xba => OpcodeGroup::Invoke,
+            0xbb..=0xbe | 0xc0..=0xc3 | 0xc5 => OpcodeGroup::Object,
+            _ => OpcodeGroup::Other,
+        }
+    }
+}
+
 impl InstructionList {
     /// Verify code based on the constraints detailed
     /// in the [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-4.html#jvms-4.9.1)
@@ -298,9 +552,71 @@ impl InstructionList {
         &self,
         file: &ClassFile,
         max_locals: usize,
-    ) -> std::result::Result<(), CodeVerificationError> {
+    ) -> core::result::Result<(), CodeVerificationError> {
         for ins in self.opcodes.iter().enumerate() {
-            self.static_verify_inst(file, ins.1, ins.0, max_locals, None)?;
+            // every branch must land on the start of an instruction
+            self.branch_targets(ins.0)?;
+            self.static_verify_inst(file, ins.1, max_locals, None)?;
+        }
+        Ok(())
+    }
+
+    /// Check that every return instruction matches the return type in
+    /// `descriptor`, the descriptor of the method this code belongs to.
+    ///
+    /// `ireturn` returns any of `boolean`, `byte`, `char`, `short` and `int`,
+    /// and `return` is only valid in `void` methods.
+    pub fn verify_return_types(&self, descriptor: &MethodDescriptor) -> core::result::Result<(), CodeVerificationError> {
+        let expected = match &descriptor.return_desc {
+            ReturnDescriptor::Void(_) => VMOpcode::r#return(),
+            ReturnDescriptor::Field(FieldType::BaseType(v)) => match v {
+                BaseType::Long => VMOpcode::lreturn(),
+                BaseType::Float => VMOpcode::freturn(),
+                BaseType::Double => VMOpcode::dreturn(),
+                BaseType::Boolean | BaseType::Byte | BaseType::Char | BaseType::Short | BaseType::Int => VMOpcode::ireturn(),
+            },
+            ReturnDescriptor::Field(FieldType::ObjectType(_) | FieldType::ArrayType(_)) => VMOpcode::areturn(),
+        };
+        for (index, op) in self.opcodes.iter().enumerate() {
+            let is_return = matches!(
+                op,
+                VMOpcode::ireturn() | VMOpcode::lreturn() | VMOpcode::freturn() | VMOpcode::dreturn() | VMOpcode::areturn() | VMOpcode::r#return()
+            );
+            if is_return && core::mem::discriminant(op) != core::mem::discriminant(&expected) {
+                return Err(CodeVerificationError::ReturnTypeMismatch(index));
+            }
+        }
+        Ok(())
+    }
+
+    /// The number of local variables used by load, store, `iinc` and `ret`
+    /// instructions, counting two for a `long` or `double`.
+    pub fn implied_max_locals(&self) -> usize {
+        self.opcodes
+            .iter()
+            .filter_map(|op| {
+                let (index, width) = match op {
+                    VMOpcode::wide_format1(op, index) => (*index as usize, local_width(op)?),
+                    VMOpcode::wide_format2(_, index, _) => (*index as usize, 1),
+                    op => (local_index(op)?, local_width(op)?),
+                };
+                Some(index + width)
+            })
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Check that `max_locals` can hold the arguments in `descriptor`,
+    /// with `this` for instance methods, and every local this code uses.
+    pub fn verify_max_locals(
+        &self,
+        descriptor: &MethodDescriptor,
+        is_static: bool,
+        max_locals: usize,
+    ) -> core::result::Result<(), CodeVerificationError> {
+        let required = (descriptor.argument_slot_count() + !is_static as usize).max(self.implied_max_locals());
+        if max_locals < required {
+            return Err(CodeVerificationError::MaxLocalsTooSmall(required));
         }
         Ok(())
     }
@@ -309,53 +625,14 @@ impl InstructionList {
         &self,
         file: &ClassFile,
         inst: &VMOpcode,
-        position: usize,
         max_locals: usize,
         wide_index: Option<u16>,
-    ) -> std::result::Result<(), CodeVerificationError> {
-        if max_locals == 0 {
-            return Err(CodeVerificationError::ClassFileError(ClassFileError::ArithmeticError));
-        }
+    ) -> core::result::Result<(), CodeVerificationError> {
         match inst {
-            VMOpcode::goto(v)
-            | VMOpcode::ifeq(v)
-            | VMOpcode::ifne(v)
-            | VMOpcode::ifle(v)
-            | VMOpcode::iflt(v)
-            | VMOpcode::ifge(v)
-            | VMOpcode::ifgt(v)
-            | VMOpcode::ifnull(v)
-            | VMOpcode::ifnonnull(v)
-            | VMOpcode::if_icmpeq(v)
-            | VMOpcode::if_icmpne(v)
-            | VMOpcode::if_icmple(v)
-            | VMOpcode::if_icmplt(v)
-            | VMOpcode::if_icmpge(v)
-            | VMOpcode::if_icmpgt(v)
-            | VMOpcode::if_acmpeq(v)
-            | VMOpcode::if_acmpne(v) => {
-                if *v as usize > self.opcodes.len() {
-                    return Err(CodeVerificationError::BranchLocOutOfBounds);
-                }
-            }
-            VMOpcode::goto_w(v) => {
-                if *v as usize > self.opcodes.len() {
-                    return Err(CodeVerificationError::BranchLocOutOfBounds);
-                }
-            }
-            VMOpcode::tableswitch(default, low, high, jump_offsets) => {
-                if ((position as isize) + (*default as isize)) as usize > self.opcodes.len() {
-                    return Err(CodeVerificationError::BranchLocOutOfBounds);
-                }
+            VMOpcode::tableswitch(_, low, high, _) => {
                 if *low > *high {
                     return Err(CodeVerificationError::TableSwitchLowGtHigh);
                 }
-                for off in jump_offsets.iter().copied() {
-                    let pos = ((position as isize) + (off as isize)) as usize;
-                    if pos > self.opcodes.len() {
-                        return Err(CodeVerificationError::BranchLocOutOfBounds);
-                    }
-                }
             }
             VMOpcode::wide_format1(op, index) => {
                 match &**op {
@@ -372,27 +649,37 @@ impl InstructionList {
                     | VMOpcode::ret(_) => (),
                     _ => return Err(CodeVerificationError::BadWideOp),
                 }
-                self.static_verify_inst(file, op, position, max_locals, Some(*index))?;
+                self.static_verify_inst(file, op, max_locals, Some(*index))?;
             }
-            VMOpcode::wide_format2(iinc, index, constant) => {}
-            VMOpcode::lookupswitch(default, match_offset_pairs) => {
-                if ((position as isize) + (*default as isize)) as usize > self.opcodes.len() {
-                    return Err(CodeVerificationError::BranchLocOutOfBounds);
+            VMOpcode::wide_format2(_, index, _) => {
+                if *index as usize >= max_locals {
+                    return Err(CodeVerificationError::LocalIndexOutOfRange);
                 }
-
+            }
+            VMOpcode::iload(v) | VMOpcode::fload(v) | VMOpcode::aload(v) | VMOpcode::istore(v) | VMOpcode::fstore(v) | VMOpcode::astore(v) | VMOpcode::iinc(v, _) | VMOpcode::ret(v) => {
+                let index = wide_index.unwrap_or(*v as u16);
+                if index as usize >= max_locals {
+                    return Err(CodeVerificationError::LocalIndexOutOfRange);
+                }
+            }
+            VMOpcode::lload(v) | VMOpcode::dload(v) | VMOpcode::lstore(v) | VMOpcode::dstore(v) => {
+                // long and double values occupy two local variables
+                let index = wide_index.unwrap_or(*v as u16);
+                if index as usize + 1 >= max_locals {
+                    return Err(CodeVerificationError::LocalIndexOutOfRange);
+                }
+            }
+            VMOpcode::lookupswitch(_, match_offset_pairs) => {
                 let mut last = i32::MIN;
-                for (v, offset) in match_offset_pairs.iter().copied() {
+                for (v, _) in match_offset_pairs.iter().copied() {
                     if last > v {
                         return Err(CodeVerificationError::LookupSwitchBadSort);
                     }
                     last = v;
-                    if ((position as isize) + (offset as isize)) as usize > self.opcodes.len() {
-                        return Err(CodeVerificationError::BranchLocOutOfBounds);
-                    }
                 }
             }
             VMOpcode::ldc(v) => {
-                self.static_verify_inst(file, &&VMOpcode::ldc_w(*v as u16), position, max_locals, wide_index)?;
+                self.static_verify_inst(file, &&VMOpcode::ldc_w(*v as u16), max_locals, wide_index)?;
             }
             VMOpcode::ldc_w(v) => {
                 check_constant_pool!(
@@ -418,6 +705,12 @@ impl InstructionList {
             | VMOpcode::getstatic(v)
             | VMOpcode::putstatic(v) => {
                 check_constant_pool!(*v, file.constant_pool, ConstantPoolEntry::Fieldref { .. })?;
+                let (_, descriptor) = get_name_and_type!(*v, file.constant_pool);
+                let lexer = Lexer::new();
+                let mut stream = Lexer::stream(lexer, descriptor.to_string());
+                if stream.token::<FieldDescriptor>().is_err() || !stream.ended() {
+                    return Err(CodeVerificationError::BadFieldDescriptor);
+                }
             }
             VMOpcode::invokevirtual(v) => {
                 check_constant_pool!(*v, file.constant_pool, ConstantPoolEntry::Methodref { .. })?;
@@ -439,6 +732,16 @@ impl InstructionList {
                     if name.starts_with("<") && !(name == "<init>") {
                         return Err(CodeVerificationError::BadMethodName);
                     }
+                    if name == "<init>" {
+                        let lexer = Lexer::new();
+                        let mut stream = Lexer::stream(lexer, descriptor.to_string());
+                        let returns_void = stream
+                            .token::<MethodDescriptor>()
+                            .is_ok_and(|v| matches!(v.token.return_desc, ReturnDescriptor::Void(_)));
+                        if !returns_void || !stream.ended() {
+                            return Err(CodeVerificationError::BadInitDescriptor);
+                        }
+                    }
                 } else {
                     if name.starts_with("<") {
                         return Err(CodeVerificationError::BadMethodName);
@@ -508,20 +811,18 @@ impl InstructionList {
                             return Err(CodeVerificationError::BadMultiANewArray);
                         }
                     }
-                    VMOpcode::wide_format2(_, index, constant) => {
-                        if *index as usize > (max_locals - 1) {
-                            return Err(CodeVerificationError::LocalIndexOutOfRange);
-                        }
-                    }
-                    VMOpcode::iload(v) | VMOpcode::fload(v) | VMOpcode::aload(v) | VMOpcode::istore(v) | VMOpcode::fstore(v) | VMOpcode::astore(v) | VMOpcode::iinc(v, _) | VMOpcode::ret(v) => {
-                        if *v as usize > (max_locals - 1) {
-                            return Err(CodeVerificationError::LocalIndexOutOfRange);
-                        }
-                    }
                     // TODO rest of static assertions
                     _ => (),
                 }
             },
+            VMOpcode::jsr(_) | VMOpcode::jsr_w(_) if file.version.0 >= 51 => {
+                return Err(CodeVerificationError::JsrInModernClass);
+            }
+
+            // parsed so that tools can see them, but never valid in a class file
+            VMOpcode::breakpoint() | VMOpcode::impdep1() | VMOpcode::impdep2() => {
+                return Err(CodeVerificationError::ReservedOpcode);
+            }
 
             _ => (),
         }
@@ -529,6 +830,40 @@ impl InstructionList {
     }
 }
 
+impl InstructionList {
+    /// Create an instruction list from decoded instructions,
+    /// computing the byte offset maps.
+    pub fn from_opcodes(opcodes: Vec<VMOpcode>) -> Self {
+        let mut list = Self {
+            opcodes,
+            byte_to_code: IntMap::default(),
+            code_to_byte: IntMap::default(),
+        };
+        list.recompute_offset_maps();
+        list
+    }
+
+    /// Rebuild `byte_to_code` and `code_to_byte` from the instructions.
+    ///
+    /// The maps are entirely derived from `opcodes`, so passes which clone
+    /// code often can clone just the instructions and call this (or
+    /// [from_opcodes](Self::from_opcodes)) once they need byte offsets,
+    /// at the cost of one pass over the code.
+    pub fn recompute_offset_maps(&mut self) {
+        self.byte_to_code.clear();
+        self.code_to_byte.clear();
+        let mut off = 0;
+        for (index, op) in self.opcodes.iter().enumerate() {
+            let len = op.byte_length(off);
+            self.code_to_byte.insert(index, off);
+            for i in off..off + len {
+                self.byte_to_code.insert(i, index);
+            }
+            off += len;
+        }
+    }
+}
+
 impl ClassFileItem for InstructionList {
     fn read_from_stream<R: Read>(
         s: &mut ClassFileStream<R>,
@@ -539,15 +874,30 @@ impl ClassFileItem for InstructionList {
     {
         let mut off = 0;
         let mut list = vec![];
-        let mut byte_to_code = FnvHashMap::default();
-        let mut code_to_byte = FnvHashMap::default();
-        while let Ok(c) = VMOpcode::read_from_stream(s, cp, off) {
+        let mut byte_to_code = IntMap::default();
+        let mut code_to_byte = IntMap::default();
+        loop {
+            let start = s.1;
+            let c = match VMOpcode::read_from_stream(s, cp, off).and_then(|c| match c.0 {
+                VMOpcode::invokeinterface(_, _, zero) if s.options().check_operands && zero != 0 => {
+                    Err(ClassFileError::BadInstructionOperand(off))
+                }
+                VMOpcode::invokedynamic(_, zero) if s.options().check_operands && zero != 0 => {
+                    Err(ClassFileError::BadInstructionOperand(off))
+                }
+                _ => Ok(c),
+            }) {
+                Ok(c) => c,
+                // running out of input between instructions is the end of the code
+                Err(ClassFileError::IoError(_)) if s.1 == start || s.options().eof_tolerant => break,
+                Err(e) => return Err(e),
+            };
+            let end = off.checked_add(c.1).ok_or(ClassFileError::ArithmeticError)?;
             code_to_byte.insert(list.len(), off);
-            for i in off..off + c.1 {
-                println!("I {:?} is {:?}", i, list.len());
+            for i in off..end {
                 byte_to_code.insert(i, list.len());
             }
-            off += c.1;
+            off = end;
             list.push(c.0);
         }
         Ok(Self { opcodes: list, byte_to_code, code_to_byte })
@@ -561,217 +911,217 @@ def_opcode! {
         /// Format: `aaload`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.aaload)
-        (0x32) = aaload(),
+        (0x32) = aaload() => (2, 1),
 
         /// Store into `reference` array
         ///
         /// Format: `aastore`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.aastore)
-        (0x53) = aastore(),
+        (0x53) = aastore() => (3, 0),
 
         /// Push `null`
         ///
         /// Format: `aconst_null`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.aconst_null)
-        (0x1) = aconst_null(),
+        (0x1) = aconst_null() => (0, 1),
 
         /// Load `reference` from local variable
         ///
         /// Format: `aload index`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.aload)
-        (0x19) = aload(u8),
+        (0x19) = aload(u8) => (0, 1),
 
         /// Load `reference` from local variable
         ///
         /// Format: `aload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.aload_n)
-        (0x2a) = aload_0(),
+        (0x2a) = aload_0() => (0, 1),
 
         /// Load `reference` from local variable
         ///
         /// Format: `aload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.aload_n)
-        (0x2b) = aload_1(),
+        (0x2b) = aload_1() => (0, 1),
 
         /// Load `reference` from local variable
         ///
         /// Format: `aload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.aload_n)
-        (0x2c) = aload_2(),
+        (0x2c) = aload_2() => (0, 1),
 
         /// Load `reference` from local variable
         ///
         /// Format: `aload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.aload_n)
-        (0x2d) = aload_3(),
+        (0x2d) = aload_3() => (0, 1),
 
         /// Create new array of `reference`
         ///
         /// Format: `anewarray indexbyte1 indexbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.anewarray)
-        (0xbd) = anewarray(u16),
+        (0xbd) = anewarray(u16) => (1, 1),
 
         /// Return `reference` from method
         ///
         /// Format: `areturn`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.areturn)
-        (0xb0) = areturn(),
+        (0xb0) = areturn() => (1, 0),
 
         /// Get length of array
         ///
         /// Format: `arraylength`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.arraylength)
-        (0xbe) = arraylength(),
+        (0xbe) = arraylength() => (1, 1),
 
         /// Store `reference` into local variable
         ///
         /// Format: `astore index`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.astore)
-        (0x3a) = astore(u8),
+        (0x3a) = astore(u8) => (1, 0),
 
         /// Store `reference` into local variable
         ///
         /// Format: `astore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.astore_n)
-        (0x4b) = astore_0(),
+        (0x4b) = astore_0() => (1, 0),
 
         /// Store `reference` into local variable
         ///
         /// Format: `astore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.astore_n)
-        (0x4c) = astore_1(),
+        (0x4c) = astore_1() => (1, 0),
 
         /// Store `reference` into local variable
         ///
         /// Format: `astore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.astore_n)
-        (0x4d) = astore_2(),
+        (0x4d) = astore_2() => (1, 0),
 
         /// Store `reference` into local variable
         ///
         /// Format: `astore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.astore_n)
-        (0x4e) = astore_3(),
+        (0x4e) = astore_3() => (1, 0),
 
         /// Throw exception or error
         ///
         /// Format: `athrow`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.athrow)
-        (0xbf) = athrow(),
+        (0xbf) = athrow() => (1, 0),
 
         /// Load `byte` or `boolean` from array
         ///
         /// Format: `baload`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.baload)
-        (0x33) = baload(),
+        (0x33) = baload() => (2, 1),
 
         /// Store into `byte` or `boolean` array
         ///
         /// Format: `bastore`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.bastore)
-        (0x54) = bastore(),
+        (0x54) = bastore() => (3, 0),
 
         /// Push `byte`
         ///
         /// Format: `bipush byte`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.bipush)
-        (0x10) = bipush(u8),
+        (0x10) = bipush(u8) => (0, 1),
 
         /// Load `char` from array
         ///
         /// Format: `caload`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.caload)
-        (0x34) = caload(),
+        (0x34) = caload() => (2, 1),
 
         /// Store into `char` array
         ///
         /// Format: `castore`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.castore)
-        (0x55) = castore(),
+        (0x55) = castore() => (3, 0),
 
         /// Check whether object is of given type
         ///
         /// Format: `checkcast indexbyte1 indexbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.checkcast)
-        (0xc0) = checkcast(u16),
+        (0xc0) = checkcast(u16) => (1, 1),
 
         /// Convert `double` to `float`
         ///
         /// Format: `d2f`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.d2f)
-        (0x90) = d2f(),
+        (0x90) = d2f() => (2, 1),
 
         /// Convert `double` to `int`
         ///
         /// Format: `d2i`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.d2i)
-        (0x8e) = d2i(),
+        (0x8e) = d2i() => (2, 1),
 
         /// Convert `double` to `long`
         ///
         /// Format: `d2l`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.d2l)
-        (0x8f) = d2l(),
+        (0x8f) = d2l() => (2, 2),
 
         /// Add `double`
         ///
         /// Format: `dadd`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dadd)
-        (0x63) = dadd(),
+        (0x63) = dadd() => (4, 2),
 
         /// Load `double` from array
         ///
         /// Format: `daload`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.daload)
-        (0x31) = daload(),
+        (0x31) = daload() => (2, 2),
 
         /// Store into `double` array
         ///
         /// Format: `dastore`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dastore)
-        (0x52) = dastore(),
+        (0x52) = dastore() => (4, 0),
 
         /// Compare `double`
         ///
         /// Format: `dcmp<op>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dcmp_op)
-        (0x98) = dcmpg(),
+        (0x98) = dcmpg() => (4, 1),
 
         /// Compare `double`
         ///
         /// Format: `dcmp<op>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dcmp_op)
-        (0x97) = dcmpl(),
+        (0x97) = dcmpl() => (4, 1),
 
 
         /// Push `double`
@@ -779,357 +1129,357 @@ def_opcode! {
         /// Format: `dconst_<d>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dconst_d)
-        (0xe) = dconst_0(),
+        (0xe) = dconst_0() => (0, 2),
 
         /// Push `double`
         ///
         /// Format: `dconst_<d>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dconst_d)
-        (0xf) = dconst_1(),
+        (0xf) = dconst_1() => (0, 2),
 
         /// Divide `double`
         ///
         /// Format: `ddiv`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.ddiv)
-        (0x6f) = ddiv(),
+        (0x6f) = ddiv() => (4, 2),
 
         /// Load `double` from local variable
         ///
         /// Format: `dload`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dload)
-        (0x18) = dload(u8),
+        (0x18) = dload(u8) => (0, 2),
 
         /// Load `double` from local variable
         ///
         /// Format: `dload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dload_n)
-        (0x26) = dload_0(),
+        (0x26) = dload_0() => (0, 2),
 
         /// Load `double` from local variable
         ///
         /// Format: `dload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dload_n)
-        (0x27) = dload_1(),
+        (0x27) = dload_1() => (0, 2),
 
         /// Load `double` from local variable
         ///
         /// Format: `dload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dload_n)
-        (0x28) = dload_2(),
+        (0x28) = dload_2() => (0, 2),
 
         /// Load `double` from local variable
         ///
         /// Format: `dload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dload_n)
-        (0x29) = dload_3(),
+        (0x29) = dload_3() => (0, 2),
 
         /// Multiply `double`
         ///
         /// Format: `dmul`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dmul)
-        (0x6b) = dmul(),
+        (0x6b) = dmul() => (4, 2),
 
         /// Negate `double`
         ///
         /// Format: `dneg`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dneg)
-        (0x77) = dneg(),
+        (0x77) = dneg() => (2, 2),
 
         /// Remainder `double`
         ///
         /// Format: `drem`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.drem)
-        (0x73) = drem(),
+        (0x73) = drem() => (4, 2),
 
         /// Return `double` from method
         ///
         /// Format: `dreturn`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dreturn)
-        (0xaf) = dreturn(),
+        (0xaf) = dreturn() => (2, 0),
 
         /// Store `double` into local variable
         ///
         /// Format: `dstore index`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dstore)
-        (0x39) = dstore(u8),
+        (0x39) = dstore(u8) => (2, 0),
 
         /// Store `double` into local variable
         ///
         /// Format: `dstore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dstore_n)
-        (0x47) = dstore_0(),
+        (0x47) = dstore_0() => (2, 0),
 
         /// Store `double` into local variable
         ///
         /// Format: `dstore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dstore_n)
-        (0x48) = dstore_1(),
+        (0x48) = dstore_1() => (2, 0),
 
         /// Store `double` into local variable
         ///
         /// Format: `dstore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dstore_n)
-        (0x49) = dstore_2(),
+        (0x49) = dstore_2() => (2, 0),
 
         /// Store `double` into local variable
         ///
         /// Format: `dstore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dstore_n)
-        (0x4a) = dstore_3(),
+        (0x4a) = dstore_3() => (2, 0),
 
         /// Subtract `double`
         ///
         /// Format: `dsub`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dsub)
-        (0x67) = dsub(),
+        (0x67) = dsub() => (4, 2),
 
         /// Duplicate the top operand stack value
         ///
         /// Format: `dup`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dup)
-        (0x59) = dup(),
+        (0x59) = dup() => (1, 2),
 
         /// Duplicate the top operand stack value and insert two values down
         ///
         /// Format: `dup_x1`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dup_x1)
-        (0x5a) = dup_x1(),
+        (0x5a) = dup_x1() => (2, 3),
 
         /// Duplicate the top operand stack value and insert two or three values down
         ///
         /// Format: `dup_x2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dup_x2)
-        (0x5b) = dup_x2(),
+        (0x5b) = dup_x2() => (3, 4),
 
         /// Duplicate the top one or two operand stack values
         ///
         /// Format: `dup2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dup2)
-        (0x5c) = dup2(),
+        (0x5c) = dup2() => (2, 4),
 
         /// Duplicate the top one or two operand stack values and insert two or three values down
         ///
         /// Format: `dup2_x1`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dup2_x1)
-        (0x5d) = dup2_x1(),
+        (0x5d) = dup2_x1() => (3, 5),
 
         /// Duplicate the top one or two operand stack values and insert two, three, or four values down
         ///
         /// Format: `dup2_x2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.dup2_x2)
-        (0x5e) = dup2_x2(),
+        (0x5e) = dup2_x2() => (4, 6),
 
         /// Convert `float` to `double`
         ///
         /// Format: `f2d`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.f2d)
-        (0x8d) = f2d(),
+        (0x8d) = f2d() => (1, 2),
 
         /// Convert `float` to `int`
         ///
         /// Format: `f2i`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.f2i)
-        (0x8b) = f2i(),
+        (0x8b) = f2i() => (1, 1),
 
         /// Convert `float` to `long`
         ///
         /// Format: `f2l`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.f2l)
-        (0x8c) = f2l(),
+        (0x8c) = f2l() => (1, 2),
 
         /// Add `float`
         ///
         /// Format: `fadd`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fadd)
-        (0x62) = fadd(),
+        (0x62) = fadd() => (2, 1),
 
         /// Load `float` from array
         ///
         /// Format: `faload`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.faload)
-        (0x30) = faload(),
+        (0x30) = faload() => (2, 1),
 
         /// Store into `float` array
         ///
         /// Format: `fastore`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fastore)
-        (0x51) = fastore(),
+        (0x51) = fastore() => (3, 0),
 
         /// Compare `float`
         ///
         /// Format: `fcmp<op>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fcmp_op)
-        (0x96) = fcmpg(),
+        (0x96) = fcmpg() => (2, 1),
 
         /// Compare `float`
         ///
         /// Format: `fcmp<op>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fcmp_op)
-        (0x95) = fcmpl(),
+        (0x95) = fcmpl() => (2, 1),
 
         /// Push `float`
         ///
         /// Format: `fconst_<f>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fconst_f)
-        (0xb) = fconst_0(),
+        (0xb) = fconst_0() => (0, 1),
 
         /// Push `float`
         ///
         /// Format: `fconst_<f>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fconst_f)
-        (0xc) = fconst_1(),
+        (0xc) = fconst_1() => (0, 1),
 
         /// Push `float`
         ///
         /// Format: `fconst_<f>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fconst_f)
-        (0xd) = fconst_2(),
+        (0xd) = fconst_2() => (0, 1),
 
         /// Divide `float`
         ///
         /// Format: `fdiv`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fdiv)
-        (0x6e) = fdiv(),
+        (0x6e) = fdiv() => (2, 1),
 
         /// Load `float` from local variable
         ///
         /// Format: `fload index`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fload)
-        (0x17) = fload(u8),
+        (0x17) = fload(u8) => (0, 1),
 
         /// Load `float` from local variable
         ///
         /// Format: `fload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fload_n)
-        (0x22) = fload_0(),
+        (0x22) = fload_0() => (0, 1),
 
         /// Load `float` from local variable
         ///
         /// Format: `fload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fload_n)
-        (0x23) = fload_1(),
+        (0x23) = fload_1() => (0, 1),
 
         /// Load `float` from local variable
         ///
         /// Format: `fload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fload_n)
-        (0x24) = fload_2(),
+        (0x24) = fload_2() => (0, 1),
 
         /// Load `float` from local variable
         ///
         /// Format: `fload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fload_n)
-        (0x25) = fload_3(),
+        (0x25) = fload_3() => (0, 1),
 
         /// Multiply `float`
         ///
         /// Format: `fmul`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fmul)
-        (0x6a) = fmul(),
+        (0x6a) = fmul() => (2, 1),
 
         /// Negate `float`
         ///
         /// Format: `fneg`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fneg)
-        (0x76) = fneg(),
+        (0x76) = fneg() => (1, 1),
 
         /// Remainder `float`
         ///
         /// Format: `frem`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.frem)
-        (0x72) = frem(),
+        (0x72) = frem() => (2, 1),
 
         /// Return `float` from method
         ///
         /// Format: `freturn`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.freturn)
-        (0xae) = freturn(),
+        (0xae) = freturn() => (1, 0),
 
         /// Store `float` into local variable
         ///
         /// Format: `fstore index`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fstore)
-        (0x38) = fstore(u8),
+        (0x38) = fstore(u8) => (1, 0),
 
         /// Store `float` into local variable
         ///
         /// Format: `fstore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fstore_n)
-        (0x43) = fstore_0(),
+        (0x43) = fstore_0() => (1, 0),
 
         /// Store `float` into local variable
         ///
         /// Format: `fstore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fstore_n)
-        (0x44) = fstore_1(),
+        (0x44) = fstore_1() => (1, 0),
 
         /// Store `float` into local variable
         ///
         /// Format: `fstore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fstore_n)
-        (0x45) = fstore_2(),
+        (0x45) = fstore_2() => (1, 0),
 
         /// Store `float` into local variable
         ///
         /// Format: `fstore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fstore_n)
-        (0x46) = fstore_3(),
+        (0x46) = fstore_3() => (1, 0),
 
         /// Subtract `float`
         ///
         /// Format: `fsub`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.fsub)
-        (0x66) = fsub(),
+        (0x66) = fsub() => (2, 1),
 
         /// Fetch field from object
         ///
@@ -1150,315 +1500,329 @@ def_opcode! {
         /// Format: `goto branchbyte1 branchbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.goto)
-        (0xa7) = goto(i16),
+        (0xa7) = goto(i16) => (0, 0),
 
         /// Branch always (wide index)
         ///
         /// Format: `goto branchbyte1 branchbyte2 branchbyte3 branchbyte4`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.goto_w)
-        (0xc8) = goto_w(u32),
+        (0xc8) = goto_w(u32) => (0, 0),
+
+        /// Jump subroutine
+        ///
+        /// Format: `jsr branchbyte1 branchbyte2`
+        ///
+        /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.jsr)
+        (0xa8) = jsr(i16) => (0, 1),
+
+        /// Jump subroutine (wide index)
+        ///
+        /// Format: `jsr_w branchbyte1 branchbyte2 branchbyte3 branchbyte4`
+        ///
+        /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.jsr_w)
+        (0xc9) = jsr_w(u32) => (0, 1),
 
         /// Convert `int` to `byte`
         ///
         /// Format: `i2b`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.i2b)
-        (0x91) = i2b(),
+        (0x91) = i2b() => (1, 1),
 
         /// Convert `int` to `char`
         ///
         /// Format: `i2c`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.i2c)
-        (0x92) = i2c(),
+        (0x92) = i2c() => (1, 1),
 
         /// Convert `int` to `double`
         ///
         /// Format: `i2d`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.i2d)
-        (0x87) = i2d(),
+        (0x87) = i2d() => (1, 2),
 
         /// Convert `int` to `float`
         ///
         /// Format: `i2f`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.i2f)
-        (0x86) = i2f(),
+        (0x86) = i2f() => (1, 1),
 
         /// Convert `int` to `long`
         ///
         /// Format: `i2l`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.i2l)
-        (0x85) = i2l(),
+        (0x85) = i2l() => (1, 2),
 
         /// Convert `int` to `short`
         ///
         /// Format: `i2s`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.i2s)
-        (0x93) = i2s(),
+        (0x93) = i2s() => (1, 1),
 
         /// Add `int`
         ///
         /// Format: `iadd`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.iadd)
-        (0x60) = iadd(),
+        (0x60) = iadd() => (2, 1),
 
         /// Load `int` from array
         ///
         /// Format: `iaload`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.iaload)
-        (0x2e) = iaload(),
+        (0x2e) = iaload() => (2, 1),
 
         /// Boolean AND `int`
         ///
         /// Format: `iand`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.iand)
-        (0x7e) = iand(),
+        (0x7e) = iand() => (2, 1),
 
         /// Store into `int` array
         ///
         /// Format: `iastore`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.iastore)
-        (0x4f) = iastore(),
+        (0x4f) = iastore() => (3, 0),
 
         /// Push `int` constant
         ///
         /// Format: `iconst_<i>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.iconst_i)
-        (0x2) = iconst_m1(),
+        (0x2) = iconst_m1() => (0, 1),
 
         /// Push `int` constant
         ///
         /// Format: `iconst_<i>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.iconst_i)
-        (0x3) = iconst_0(),
+        (0x3) = iconst_0() => (0, 1),
 
         /// Push `int` constant
         ///
         /// Format: `iconst_<i>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.iconst_i)
-        (0x4) = iconst_1(),
+        (0x4) = iconst_1() => (0, 1),
 
         /// Push `int` constant
         ///
         /// Format: `iconst_<i>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.iconst_i)
-        (0x5) = iconst_2(),
+        (0x5) = iconst_2() => (0, 1),
 
         /// Push `int` constant
         ///
         /// Format: `iconst_<i>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.iconst_i)
-        (0x6) = iconst_3(),
+        (0x6) = iconst_3() => (0, 1),
 
         /// Push `int` constant
         ///
         /// Format: `iconst_<i>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.iconst_i)
-        (0x7) = iconst_4(),
+        (0x7) = iconst_4() => (0, 1),
 
         /// Push `int` constant
         ///
         /// Format: `iconst_<i>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.iconst_i)
-        (0x8) = iconst_5(),
+        (0x8) = iconst_5() => (0, 1),
 
         /// Divide `int`
         ///
         /// Format: `idiv`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.idiv)
-        (0x6c) = idiv(),
+        (0x6c) = idiv() => (2, 1),
 
         /// Branch if `reference` comparison succeeds
         ///
         /// Format: `if_acmp<cond> branchbyte1 branchbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.if_acmp_cond)
-        (0xa5) = if_acmpeq(i16),
+        (0xa5) = if_acmpeq(i16) => (2, 0),
 
         /// Branch if `reference` comparison succeeds
         ///
         /// Format: `if_acmp<cond> branchbyte1 branchbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.if_acmp_cond)
-        (0xa6) = if_acmpne(i16),
+        (0xa6) = if_acmpne(i16) => (2, 0),
 
         /// Branch if `int` comparison succeeds
         ///
         /// Format: `if_icmp<cond> branchbyte1 branchbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.if_icmp_cond)
-        (0x9f) = if_icmpeq(i16),
+        (0x9f) = if_icmpeq(i16) => (2, 0),
 
         /// Branch if `int` comparison succeeds
         ///
         /// Format: `if_icmp<cond> branchbyte1 branchbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.if_icmp_cond)
-        (0xa0) = if_icmpne(i16),
+        (0xa0) = if_icmpne(i16) => (2, 0),
 
         /// Branch if `int` comparison succeeds
         ///
         /// Format: `if_icmp<cond> branchbyte1 branchbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.if_icmp_cond)
-        (0xa1) = if_icmplt(i16),
+        (0xa1) = if_icmplt(i16) => (2, 0),
 
         /// Branch if `int` comparison succeeds
         ///
         /// Format: `if_icmp<cond> branchbyte1 branchbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.if_icmp_cond)
-        (0xa2) = if_icmpge(i16),
+        (0xa2) = if_icmpge(i16) => (2, 0),
 
         /// Branch if `int` comparison succeeds
         ///
         /// Format: `if_icmp<cond> branchbyte1 branchbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.if_icmp_cond)
-        (0xa3) = if_icmpgt(i16),
+        (0xa3) = if_icmpgt(i16) => (2, 0),
 
         /// Branch if `int` comparison succeeds
         ///
         /// Format: `if_icmp<cond> branchbyte1 branchbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.if_icmp_cond)
-        (0xa4) = if_icmple(i16),
+        (0xa4) = if_icmple(i16) => (2, 0),
 
         /// Branch if `int` comparison with zero succeeds
         ///
         /// Format: `if<cond> branchbyte1 branchbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.if_cond)
-        (0x99) = ifeq(i16),
+        (0x99) = ifeq(i16) => (1, 0),
 
         /// Branch if `int` comparison with zero succeeds
         ///
         /// Format: `if<cond> branchbyte1 branchbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.if_cond)
-        (0x9a) = ifne(i16),
+        (0x9a) = ifne(i16) => (1, 0),
 
         /// Branch if `int` comparison with zero succeeds
         ///
         /// Format: `if<cond> branchbyte1 branchbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.if_cond)
-        (0x9b) = iflt(i16),
+        (0x9b) = iflt(i16) => (1, 0),
 
         /// Branch if `int` comparison with zero succeeds
         ///
         /// Format: `if<cond> branchbyte1 branchbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.if_cond)
-        (0x9c) = ifge(i16),
+        (0x9c) = ifge(i16) => (1, 0),
 
         /// Branch if `int` comparison with zero succeeds
         ///
         /// Format: `if<cond> branchbyte1 branchbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.if_cond)
-        (0x9d) = ifgt(i16),
+        (0x9d) = ifgt(i16) => (1, 0),
 
         /// Branch if `int` comparison with zero succeeds
         ///
         /// Format: `if<cond> branchbyte1 branchbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.if_cond)
-        (0x9e) = ifle(i16),
+        (0x9e) = ifle(i16) => (1, 0),
 
         /// Branch if `reference` not `null`
         ///
         /// Format: `ifnonnull branchbyte1 branchbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.ifnonnull)
-        (0xc7) = ifnonnull(i16),
+        (0xc7) = ifnonnull(i16) => (1, 0),
 
         /// Branch if `reference` is `null`
         ///
         /// Format: `ifnull branchbyte1 branchbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.ifnull)
-        (0xc6) = ifnull(i16),
+        (0xc6) = ifnull(i16) => (1, 0),
 
         /// Increment local variable by constant
         ///
         /// Format: `iinc index const`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.iinc)
-        (0x84) = iinc(u8, u8),
+        (0x84) = iinc(u8, u8) => (0, 0),
 
         /// Load `int` from local variable
         ///
         /// Format: `iload index`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.iload)
-        (0x15) = iload(u8),
+        (0x15) = iload(u8) => (0, 1),
 
         /// Load `int` from local variable
         ///
         /// Format: `iload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.iload_n)
-        (0x1a) = iload_0(),
+        (0x1a) = iload_0() => (0, 1),
 
         /// Load `int` from local variable
         ///
         /// Format: `iload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.iload_n)
-        (0x1b) = iload_1(),
+        (0x1b) = iload_1() => (0, 1),
 
         /// Load `int` from local variable
         ///
         /// Format: `iload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.iload_n)
-        (0x1c) = iload_2(),
+        (0x1c) = iload_2() => (0, 1),
 
         /// Load `int` from local variable
         ///
         /// Format: `iload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.iload_n)
-        (0x1d) = iload_3(),
+        (0x1d) = iload_3() => (0, 1),
 
         /// Multiply `int`
         ///
         /// Format: `imul`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.imul)
-        (0x68) = imul(),
+        (0x68) = imul() => (2, 1),
 
         /// Negate `int`
         ///
         /// Format: `ineg`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.ineg)
-        (0x74) = ineg(),
+        (0x74) = ineg() => (1, 1),
 
         /// Determine if object is of given type
         ///
         /// Format: `instanceof indexbyte1 indexbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.instanceof)
-        (0xc1) = instanceof(u16),
+        (0xc1) = instanceof(u16) => (1, 1),
 
         /// Invoke dynamic method
         ///
@@ -1500,343 +1864,343 @@ def_opcode! {
         /// Format: `ior`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.ior)
-        (0x80) = ior(),
+        (0x80) = ior() => (2, 1),
 
         /// Remainder `int`
         ///
         /// Format: `irem`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.irem)
-        (0x70) = irem(),
+        (0x70) = irem() => (2, 1),
 
         /// Return `int` from method
         ///
         /// Format: `ireturn`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.ireturn)
-        (0xac) = ireturn(),
+        (0xac) = ireturn() => (1, 0),
 
         /// Shift left `int`
         ///
         /// Format: `ishl`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.ishl)
-        (0x78) = ishl(),
+        (0x78) = ishl() => (2, 1),
 
         /// Arithmetic shift right `int`
         ///
         /// Format: `ishr`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.ishr)
-        (0x7a) = ishr(),
+        (0x7a) = ishr() => (2, 1),
 
         /// Store `int` into local variable
         ///
         /// Format: `istore index`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.istore)
-        (0x36) = istore(u8),
+        (0x36) = istore(u8) => (1, 0),
 
         /// Store `int` into local variable
         ///
         /// Format: `istore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.istore_n)
-        (0x3b) = istore_0(),
+        (0x3b) = istore_0() => (1, 0),
 
         /// Store `int` into local variable
         ///
         /// Format: `istore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.istore_n)
-        (0x3c) = istore_1(),
+        (0x3c) = istore_1() => (1, 0),
 
         /// Store `int` into local variable
         ///
         /// Format: `istore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.istore_n)
-        (0x3d) = istore_2(),
+        (0x3d) = istore_2() => (1, 0),
 
         /// Store `int` into local variable
         ///
         /// Format: `istore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.istore_n)
-        (0x3e) = istore_3(),
+        (0x3e) = istore_3() => (1, 0),
 
         /// Subtract `int`
         ///
         /// Format: `isub`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.isub)
-        (0x64) = isub(),
+        (0x64) = isub() => (2, 1),
 
         /// Logical shift right `int`
         ///
         /// Format: `iushr`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.iushr)
-        (0x7c) = iushr(),
+        (0x7c) = iushr() => (2, 1),
 
         /// Boolean XOR `int`
         ///
         /// Format: `ixor`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.ixor)
-        (0x82) = ixor(),
+        (0x82) = ixor() => (2, 1),
 
         /// Convert `long` to `double`
         ///
         /// Format: `l2d`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.l2d)
-        (0x8a) = l2d(),
+        (0x8a) = l2d() => (2, 2),
 
         /// Convert `long` to `float`
         ///
         /// Format: `l2f`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.l2f)
-        (0x89) = l2f(),
+        (0x89) = l2f() => (2, 1),
 
         /// Convert `long` to `int`
         ///
         /// Format: `l2i`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.l2i)
-        (0x88) = l2i(),
+        (0x88) = l2i() => (2, 1),
 
         /// Add `long`
         ///
         /// Format: `ladd`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.ladd)
-        (0x61) = ladd(),
+        (0x61) = ladd() => (4, 2),
 
         /// Load `long` from array
         ///
         /// Format: `laload`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.laload)
-        (0x2f) = laload(),
+        (0x2f) = laload() => (2, 2),
 
         /// Boolean AND `long`
         ///
         /// Format: `land`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.land)
-        (0x7f) = land(),
+        (0x7f) = land() => (4, 2),
 
         /// Store into `long` array
         ///
         /// Format: `lastore`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lastore)
-        (0x50) = lastore(),
+        (0x50) = lastore() => (4, 0),
 
         /// Compare `long`
         ///
         /// Format: `lcmp`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lcmp)
-        (0x94) = lcmp(),
+        (0x94) = lcmp() => (4, 1),
 
         /// Push `long` constant
         ///
         /// Format: `lconst_<l>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lconst_l)
-        (0x9) = lconst_0(),
+        (0x9) = lconst_0() => (0, 2),
 
         /// Push `long` constant
         ///
         /// Format: `lconst_<l>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lconst_l)
-        (0xa) = lconst_1(),
+        (0xa) = lconst_1() => (0, 2),
 
         /// Push item from run-time constant pool
         ///
         /// Format: `ldc index`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.ldc)
-        (0x12) = ldc(u8),
+        (0x12) = ldc(u8) => (0, 1),
 
         /// Push item from run-time constant pool (wide index)
         ///
         /// Format: `ldc_w indexbyte1 indexbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.ldc_w)
-        (0x13) = ldc_w(u16),
+        (0x13) = ldc_w(u16) => (0, 1),
 
         /// Push `long` or `double` from run-time constant pool (wide index)
         ///
         /// Format: `ldc2_w indexbyte1 indexbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.ldc2_w)
-        (0x14) = ldc2_w(u16),
+        (0x14) = ldc2_w(u16) => (0, 2),
 
         /// Divide `long`
         ///
         /// Format: `ldiv`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.ldiv)
-        (0x6d) = ldiv(),
+        (0x6d) = ldiv() => (4, 2),
 
         /// Load `long` from local variable
         ///
         /// Format: `lload index`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lload)
-        (0x16) = lload(u8),
+        (0x16) = lload(u8) => (0, 2),
 
         /// Load `long` from local variable
         ///
         /// Format: `lload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lload_n)
-        (0x1e) = lload_0(),
+        (0x1e) = lload_0() => (0, 2),
 
         /// Load `long` from local variable
         ///
         /// Format: `lload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lload_n)
-        (0x1f) = lload_1(),
+        (0x1f) = lload_1() => (0, 2),
 
         /// Load `long` from local variable
         ///
         /// Format: `lload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lload_n)
-        (0x20) = lload_2(),
+        (0x20) = lload_2() => (0, 2),
 
         /// Load `long` from local variable
         ///
         /// Format: `lload_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lload_n)
-        (0x21) = lload_3(),
+        (0x21) = lload_3() => (0, 2),
 
         /// Multiply `long`
         ///
         /// Format: `lmul`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lmul)
-        (0x69) = lmul(),
+        (0x69) = lmul() => (4, 2),
 
         /// Negate `long`
         ///
         /// Format: `lneg`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lneg)
-        (0x75) = lneg(),
+        (0x75) = lneg() => (2, 2),
 
         /// Boolean OR `long`
         ///
         /// Format: `lor`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lor)
-        (0x81) = lor(),
+        (0x81) = lor() => (4, 2),
 
         /// Remainder `long`
         ///
         /// Format: `lrem`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lrem)
-        (0x71) = lrem(),
+        (0x71) = lrem() => (4, 2),
 
         /// Return `long` from method
         ///
         /// Format: `lreturn`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lreturn)
-        (0xad) = lreturn(),
+        (0xad) = lreturn() => (2, 0),
 
         /// Shift left `long`
         ///
         /// Format: `lshl`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lshl)
-        (0x79) = lshl(),
+        (0x79) = lshl() => (3, 2),
 
         /// Arithmetic shift right `long`
         ///
         /// Format: `lshr`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lshr)
-        (0x7b) = lshr(),
+        (0x7b) = lshr() => (3, 2),
 
         /// Store `long` into local variable
         ///
         /// Format: `lstore`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lstore)
-        (0x37) = lstore(u8),
+        (0x37) = lstore(u8) => (2, 0),
 
         /// Store `long` into local variable
         ///
         /// Format: `lstore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lstore_n)
-        (0x3f) = lstore_0(),
+        (0x3f) = lstore_0() => (2, 0),
 
         /// Store `long` into local variable
         ///
         /// Format: `lstore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lstore_n)
-        (0x40) = lstore_1(),
+        (0x40) = lstore_1() => (2, 0),
 
         /// Store `long` into local variable
         ///
         /// Format: `lstore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lstore_n)
-        (0x41) = lstore_2(),
+        (0x41) = lstore_2() => (2, 0),
 
         /// Store `long` into local variable
         ///
         /// Format: `lstore_<n>`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lstore_n)
-        (0x42) = lstore_3(),
+        (0x42) = lstore_3() => (2, 0),
 
         /// Subtract `long`
         ///
         /// Format: `lsub`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lsub)
-        (0x65) = lsub(),
+        (0x65) = lsub() => (4, 2),
 
         /// Logical shift right `long`
         ///
         /// Format: `lushr`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lushr)
-        (0x7d) = lushr(),
+        (0x7d) = lushr() => (3, 2),
 
         /// Boolean XOR `long`
         ///
         /// Format: `lxor`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.lxor)
-        (0x83) = lxor(),
+        (0x83) = lxor() => (4, 2),
 
         /// Enter monitor for object
         ///
         /// Format: `monitorenter`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.monitorenter)
-        (0xc2) = monitorenter(),
+        (0xc2) = monitorenter() => (1, 0),
 
         /// Exit monitor for object
         ///
         /// Format: `monitorexit`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.monitorexit)
-        (0xc3) = monitorexit(),
+        (0xc3) = monitorexit() => (1, 0),
 
         /// Create new multidimensional array
         ///
@@ -1850,35 +2214,35 @@ def_opcode! {
         /// Format: `new indexbyte1 indexbyte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.new)
-        (0xbb) = new(u16),
+        (0xbb) = new(u16) => (0, 1),
 
         /// Create new array
         ///
         /// Format: `newarray atype`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.newarray)
-        (0xbc) = newarray(ArrayTypeCode),
+        (0xbc) = newarray(ArrayTypeCode) => (1, 1),
 
         /// Do nothing
         ///
         /// Format: `nop`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.nop)
-        (0x0) = nop(),
+        (0x0) = nop() => (0, 0),
 
         /// Pop the top operand stack value
         ///
         /// Format: `pop`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.pop)
-        (0x57) = pop(),
+        (0x57) = pop() => (1, 0),
 
         /// Pop the top one or two operand stack values
         ///
         /// Format: `pop2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.pop2)
-        (0x58) = pop2(),
+        (0x58) = pop2() => (2, 0),
 
         /// Set field in object
         ///
@@ -1899,42 +2263,496 @@ def_opcode! {
         /// Format: `ret index`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.ret)
-        (0xa9) = ret(u8),
+        (0xa9) = ret(u8) => (0, 0),
 
         /// Return `void` from method
         ///
         /// Format: `return`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.return)
-        (0xb1) = r#return(),
+        (0xb1) = r#return() => (0, 0),
 
         /// Load `short` from array
         ///
         /// Format: `saload`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.saload)
-        (0x35) = saload(),
+        (0x35) = saload() => (2, 1),
 
         /// Store into `short` array
         ///
         /// Format: `sastore`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.sastore)
-        (0x56) = sastore(),
+        (0x56) = sastore() => (3, 0),
 
         /// Push `short`
         ///
         /// Format: `sipush byte1 byte2`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.sipush)
-        (0x11) = sipush(u16),
+        (0x11) = sipush(u16) => (0, 1),
 
         /// Swap the top two operand stack values
         ///
         /// Format: `swap`
         ///
         /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.5.swap)
-        (0x5f) = swap()
+        (0x5f) = swap() => (2, 2),
+
+        /// Reserved for breakpoints in debuggers, must not appear in a class file
+        ///
+        /// Format: `breakpoint`
+        ///
+        /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.2)
+        (0xca) = breakpoint(),
+
+        /// Reserved for implementation-dependent use, must not appear in a class file
+        ///
+        /// Format: `impdep1`
+        ///
+        /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.2)
+        (0xfe) = impdep1(),
+
+        /// Reserved for implementation-dependent use, must not appear in a class file
+        ///
+        /// Format: `impdep2`
+        ///
+        /// Details: [Java SE 8 Specification](https://docs.oracle.com/javase/specs/jvms/se8/html/jvms-6.html#jvms-6.2)
+        (0xff) = impdep2()
+
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::{boxed::Box, string::ToString, vec, vec::Vec};
+
+    use crate::{error::ClassFileError, io::Cursor, item::{attribute_info::{attrtype, Attributes, CodeAttribute}, constant_pool::ConstantPoolEntry, file::ClassFile, ClassFileItem}, stream::{ClassFileStream, ParseOptions}};
+
+    use super::{ArrayTypeCode, CodeBuilder, CodeVerificationError, Edge, EdgeKind, InstructionList, Lexer, MethodDescriptor, OpcodeGroup, VMOpcode};
+
+    /// Parses `local/Test.class`, which the verification tests check code against.
+    fn test_class() -> ClassFile {
+        let file = include_bytes!("../../../../local/Test.class");
+        ClassFile::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(file)), None).unwrap()
+    }
+
+    /// Appends `entries` to the constant pool of `class_file`, returning the index of the last one.
+    fn push_entries(class_file: &mut ClassFile, entries: impl IntoIterator<Item = ConstantPoolEntry>) -> u16 {
+        class_file.constant_pool.entries.extend(entries);
+        class_file.constant_pool.entries.len() as u16
+    }
+
+    /// Appends a reference to the member `name` of `class_file`'s own class, built by `reference`
+    /// from the class index and `NameAndType` index. Returns the index of the descriptor and of the reference.
+    fn push_member_ref(
+        class_file: &mut ClassFile,
+        name: &str,
+        descriptor: &str,
+        reference: fn(u16, u16) -> ConstantPoolEntry,
+    ) -> (usize, u16) {
+        let class_index = class_file.this_class;
+        let descriptor_index = push_entries(class_file, [ConstantPoolEntry::utf8(name.to_string()), ConstantPoolEntry::utf8(descriptor.to_string())]);
+        let name_and_type_index = push_entries(class_file, [ConstantPoolEntry::NameAndType { name_index: descriptor_index - 1, descriptor_index }]);
+        (descriptor_index as usize, push_entries(class_file, [reference(class_index, name_and_type_index)]))
+    }
+
+    #[test]
+    fn wide_iinc_test() {
+        let class_file = test_class();
+
+        // wide iinc 300 -2; return
+        let code = [0xc4, 0x84, 0x01, 0x2c, 0xff, 0xfe, 0xb1];
+        let list = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+        assert!(matches!(list.opcodes[0], VMOpcode::wide_format2(_, 300, 0xfffe)));
+        assert!(matches!(list.opcodes[1], VMOpcode::r#return()));
+
+        assert!(matches!(list.static_verify(&class_file, 300), Err(CodeVerificationError::LocalIndexOutOfRange)));
+        assert!(list.static_verify(&class_file, 301).is_ok());
+    }
+
+    #[test]
+    fn jsr_test() {
+        let mut class_file = test_class();
+
+        // jsr +4; return; astore_1; ret 1; jsr_w -3
+        let code = [0xa8, 0x00, 0x04, 0xb1, 0x4c, 0xa9, 0x01, 0xc9, 0xff, 0xff, 0xff, 0xfd];
+        let list = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+        assert!(matches!(list.opcodes[..], [VMOpcode::jsr(4), VMOpcode::r#return(), VMOpcode::astore_1(), VMOpcode::ret(1), VMOpcode::jsr_w(0xfffffffd)]));
+        assert_eq!(list.opcodes[4].stack_delta(), Some((0, 1)));
+        assert_eq!(list.branch_targets(0).unwrap(), (vec![2], EdgeKind::Branch));
+        assert_eq!(list.branch_targets(4).unwrap(), (vec![2], EdgeKind::Branch));
+
+        // the subroutine returns to the instruction after the `jsr`
+        let cfg = list.build_cfg(&[]).unwrap();
+        assert_eq!(cfg.blocks[0].successors, vec![Edge { target: 2, kind: EdgeKind::Branch }, Edge { target: 1, kind: EdgeKind::Fallthrough }]);
+        assert!(cfg.blocks[2].successors.is_empty());
+
+        assert!(matches!(list.static_verify(&class_file, 2), Err(CodeVerificationError::JsrInModernClass)));
+        class_file.version = (50, 0);
+        assert!(list.static_verify(&class_file, 2).is_ok());
+    }
+
+    #[test]
+    fn reserved_opcode_test() {
+        let class_file = test_class();
+
+        // breakpoint; impdep2; return
+        let code = [0xca, 0xff, 0xb1];
+        let list = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+        assert!(matches!(list.opcodes[..], [VMOpcode::breakpoint(), VMOpcode::impdep2(), VMOpcode::r#return()]));
+        assert!(matches!(list.static_verify(&class_file, 0), Err(CodeVerificationError::ReservedOpcode)));
+    }
+
+    #[test]
+    fn return_type_test() {
+        let descriptor = |v: &str| Lexer::stream(Lexer::new(), v.to_string()).token::<MethodDescriptor>().unwrap().token;
+        let list = |code: &[u8]| InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+
+        // iconst_0; ireturn
+        let ints = list(&[0x03, 0xac]);
+        for v in ["()I", "()Z", "()B", "()C", "()S"] {
+            assert!(ints.verify_return_types(&descriptor(v)).is_ok());
+        }
+        assert!(matches!(ints.verify_return_types(&descriptor("()J")), Err(CodeVerificationError::ReturnTypeMismatch(1))));
+        // aconst_null; areturn
+        let null = list(&[0x01, 0xb0]);
+        assert!(null.verify_return_types(&descriptor("()[I")).is_ok());
+        assert!(null.verify_return_types(&descriptor("()Ljava/lang/String;")).is_ok());
+        assert!(matches!(null.verify_return_types(&descriptor("(I)I")), Err(CodeVerificationError::ReturnTypeMismatch(1))));
+        // return
+        assert!(list(&[0xb1]).verify_return_types(&descriptor("()V")).is_ok());
+        assert!(matches!(list(&[0xb1]).verify_return_types(&descriptor("()I")), Err(CodeVerificationError::ReturnTypeMismatch(0))));
+
+        // every method of a compiled class returns correctly
+        let class_file = test_class();
+        assert!(class_file.verify_code().is_ok());
+    }
+
+    #[test]
+    fn max_locals_test() {
+        let descriptor = |v: &str| Lexer::stream(Lexer::new(), v.to_string()).token::<MethodDescriptor>().unwrap().token;
+        let list = |code: &[u8]| InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+
+        // return
+        let empty = list(&[0xb1]);
+        assert_eq!(empty.implied_max_locals(), 0);
+        assert!(matches!(empty.verify_max_locals(&descriptor("(II)V"), true, 1), Err(CodeVerificationError::MaxLocalsTooSmall(2))));
+        assert!(empty.verify_max_locals(&descriptor("(II)V"), true, 2).is_ok());
+        assert!(matches!(empty.verify_max_locals(&descriptor("(JD)V"), false, 4), Err(CodeVerificationError::MaxLocalsTooSmall(5))));
+
+        // dload_3; dstore 5; wide lstore 300; return
+        let locals = list(&[0x29, 0x39, 0x05, 0xc4, 0x37, 0x01, 0x2c, 0xb1]);
+        assert_eq!(locals.implied_max_locals(), 302);
+        assert!(matches!(locals.verify_max_locals(&descriptor("()V"), true, 301), Err(CodeVerificationError::MaxLocalsTooSmall(302))));
+        assert!(locals.verify_max_locals(&descriptor("()V"), true, 302).is_ok());
+    }
+
+    #[test]
+    fn constant_index_bounds_test() {
+        let mut class_file = test_class();
+        let len = push_entries(&mut class_file, [
+            ConstantPoolEntry::Long { bytes: 1 },
+            ConstantPoolEntry::Unusable,
+            ConstantPoolEntry::Integer { bytes: 2 },
+        ]);
+
+        // ldc_w; pop; return
+        let verify = |index: u16| {
+            let [high, low] = index.to_be_bytes();
+            let code = [0x13, high, low, 0x57, 0xb1];
+            let list = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+            list.static_verify(&class_file, 0)
+        };
+        assert!(verify(len).is_ok());
+        assert!(matches!(verify(len + 1), Err(CodeVerificationError::BadConstantPoolIndex)));
+        assert!(matches!(verify(0), Err(CodeVerificationError::BadConstantPoolIndex)));
+        assert!(matches!(verify(len - 1), Err(CodeVerificationError::BadConstantPoolIndex)));
+        assert!(matches!(verify(len - 2), Err(CodeVerificationError::BadConstantPoolType)));
+    }
+
+    #[test]
+    fn field_descriptor_test() {
+        let mut class_file = test_class();
+        let (descriptor_index, fieldref) = push_member_ref(&mut class_file, "value", "Q", |class_index, name_and_type_index| {
+            ConstantPoolEntry::Fieldref { class_index, name_and_type_index }
+        });
+
+        // getstatic; pop; return
+        let [high, low] = fieldref.to_be_bytes();
+        let code = [0xb2, high, low, 0x57, 0xb1];
+        let list = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+        assert!(matches!(list.static_verify(&class_file, 1), Err(CodeVerificationError::BadFieldDescriptor)));
+
+        class_file.constant_pool.entries[descriptor_index - 1] = ConstantPoolEntry::utf8("II".to_string());
+        assert!(matches!(list.static_verify(&class_file, 1), Err(CodeVerificationError::BadFieldDescriptor)));
+        class_file.constant_pool.entries[descriptor_index - 1] = ConstantPoolEntry::utf8("[Ljava/lang/String;".to_string());
+        assert!(list.static_verify(&class_file, 1).is_ok());
+    }
+
+    #[test]
+    fn init_descriptor_test() {
+        let mut class_file = test_class();
+        let (descriptor_index, methodref) = push_member_ref(&mut class_file, "<init>", "()I", |class_index, name_and_type_index| {
+            ConstantPoolEntry::Methodref { class_index, name_and_type_index }
+        });
+
+        // aload_0; invokespecial; return
+        let [high, low] = methodref.to_be_bytes();
+        let code = [0x2a, 0xb7, high, low, 0xb1];
+        let list = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+        assert!(matches!(list.static_verify(&class_file, 1), Err(CodeVerificationError::BadInitDescriptor)));
+
+        class_file.constant_pool.entries[descriptor_index - 1] = ConstantPoolEntry::utf8("(I".to_string());
+        assert!(matches!(list.static_verify(&class_file, 1), Err(CodeVerificationError::BadInitDescriptor)));
+        class_file.constant_pool.entries[descriptor_index - 1] = ConstantPoolEntry::utf8("(I)V".to_string());
+        assert!(list.static_verify(&class_file, 1).is_ok());
+    }
+
+    #[test]
+    fn newarray_test() {
+        let class_file = test_class();
+
+        // iconst_1; newarray T_INT; areturn
+        let code = [0x04, 0xbc, 0x0a, 0xb0];
+        let list = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+        assert!(matches!(list.opcodes[1], VMOpcode::newarray(ArrayTypeCode::T_INT)));
+        assert_eq!(ArrayTypeCode::T_INT.value(), 10);
+        assert!(list.static_verify(&class_file, 1).is_ok());
+
+        // iconst_1; newarray <atype>; areturn
+        for atype in 0..=u8::MAX {
+            let code = [0x04, 0xbc, atype, 0xb0];
+            let result = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None);
+            if (ArrayTypeCode::T_BOOLEAN.value()..=ArrayTypeCode::T_LONG.value()).contains(&atype) {
+                assert!(result.unwrap().static_verify(&class_file, 1).is_ok());
+            } else {
+                assert!(matches!(result, Err(ClassFileError::UnknownEnumVariant("ArrayTypeCode", v)) if v == atype as i32));
+            }
+        }
+
+        // iconst_1; newarray with its atype cut off
+        let code = [0x04, 0xbc];
+        let list = InstructionList::read_from_stream(
+            &mut ClassFileStream::with_options(&mut Cursor::new(code), ParseOptions::eof_tolerant()),
+            None,
+        )
+        .unwrap();
+        assert_eq!(list.opcodes.len(), 1);
+
+        // iconst_1; newarray 12
+        let code = [0x04, 0xbc, 0x0c];
+        let result = InstructionList::read_from_stream(
+            &mut ClassFileStream::with_options(&mut Cursor::new(code), ParseOptions::eof_tolerant()),
+            None,
+        );
+        assert!(matches!(result, Err(ClassFileError::UnknownEnumVariant("ArrayTypeCode", 12))));
+    }
+
+    #[test]
+    fn invoke_operands_test() {
+        let read = |code: &[u8], options| InstructionList::read_from_stream(&mut ClassFileStream::with_options(&mut Cursor::new(code), options), None);
+
+        // nop; invokedynamic #1 with nonzero trailing bytes
+        let code = [0x00, 0xba, 0x00, 0x01, 0x00, 0x01];
+        assert!(matches!(read(&code, ParseOptions::strict()), Err(ClassFileError::BadInstructionOperand(1))));
+        assert!(matches!(read(&code, ParseOptions::default()).unwrap().opcodes[1], VMOpcode::invokedynamic(1, 1)));
+
+        // invokeinterface #1 count 1 with a nonzero last byte
+        let code = [0xb9, 0x00, 0x01, 0x01, 0x02];
+        assert!(matches!(read(&code, ParseOptions::strict()), Err(ClassFileError::BadInstructionOperand(0))));
+        let options = ParseOptions { eof_tolerant: true, ..ParseOptions::strict() };
+        assert!(matches!(read(&code, options), Err(ClassFileError::BadInstructionOperand(0))));
+
+        let code = [0xba, 0x00, 0x01, 0x00, 0x00, 0xb9, 0x00, 0x01, 0x01, 0x00];
+        assert_eq!(read(&code, ParseOptions::strict()).unwrap().opcodes.len(), 2);
+    }
+
+    #[test]
+    fn recompute_offset_maps_test() {
+        let class_file = test_class();
+
+        for method in class_file.methods.iter() {
+            for code in method.attributes.get(attrtype::Code) {
+                let Attributes::Code(CodeAttribute { code, .. }) = code else {
+                    panic!("expected code attribute");
+                };
+                let rebuilt = InstructionList::from_opcodes(code.opcodes.clone());
+                assert_eq!(rebuilt.byte_to_code, code.byte_to_code);
+                assert_eq!(rebuilt.code_to_byte, code.code_to_byte);
+            }
+        }
+
+        // iconst_0; lookupswitch, padded; wide iinc
+        let code = [
+            0x03, 0xab, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x10,
+            0x00, 0x00, 0x00, 0x00,
+            0xc4, 0x84, 0x00, 0x01, 0x00, 0x01,
+        ];
+        let list = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+        let rebuilt = InstructionList::from_opcodes(list.opcodes.clone());
+        assert_eq!(rebuilt.byte_to_code, list.byte_to_code);
+        assert_eq!(rebuilt.code_to_byte, list.code_to_byte);
+    }
+
+    #[test]
+    fn insert_remove_instruction_test() {
+        // 0: nop; 1: iinc 0 1; 4: goto 1
+        let code = [0x00, 0x84, 0x00, 0x01, 0xa7, 0xff, 0xfd];
+        let mut list = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+
+        // nop before the backward branch
+        list.insert_instruction(2, VMOpcode::nop()).unwrap();
+        assert!(matches!(list.opcodes[3], VMOpcode::goto(-4)));
+        assert_eq!(list.code_to_byte[&3], 5);
+
+        // nop before the branch target, which the branch now reaches first
+        list.insert_instruction(1, VMOpcode::nop()).unwrap();
+        assert!(matches!(list.opcodes[4], VMOpcode::goto(-5)));
+
+        list.remove_instruction(1).unwrap();
+        list.remove_instruction(2).unwrap();
+        assert!(matches!(list.opcodes[2], VMOpcode::goto(-3)));
+        assert_eq!(list.byte_to_code.len(), code.len());
+
+        // a tableswitch's padding changes as it moves
+        // 0: iconst_0; 1: tableswitch, padded, default => 20, 0 => 20; 20: return
+        let code = [
+            0x03, 0xaa, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x13,
+            0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x00,
+            0x00, 0x00, 0x00, 0x13,
+            0xb1,
+        ];
+        let mut list = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+        list.insert_instruction(0, VMOpcode::nop()).unwrap();
+        assert_eq!(list.code_to_byte[&3], 20);
+        assert!(matches!(list.opcodes[2], VMOpcode::tableswitch(18, 0, 0, ref v) if v == &[18]));
+
+        assert!(matches!(list.remove_instruction(3), Err(CodeVerificationError::BranchLocOutOfBounds)));
+    }
+
+    #[test]
+    fn opcode_metadata_test() {
+        assert_eq!(VMOpcode::r#return().mnemonic(), "return");
+        assert_eq!(VMOpcode::invokeinterface(1, 1, 0).mnemonic(), "invokeinterface");
+        assert_eq!(VMOpcode::invokeinterface(1, 1, 0).operand_count(), 3);
+        assert_eq!(VMOpcode::invokeinterface(1, 1, 0).stack_delta(), None);
+        assert_eq!(VMOpcode::nop().operand_count(), 0);
+        assert_eq!(VMOpcode::dup2_x1().stack_delta(), Some((3, 5)));
+        assert_eq!(VMOpcode::ladd().stack_delta(), Some((4, 2)));
+        assert_eq!(VMOpcode::tableswitch(0, 0, 1, vec![0, 0]).operand_count(), 5);
+
+        let wide = VMOpcode::wide_format1(Box::new(VMOpcode::lload(0)), 300);
+        assert_eq!(wide.mnemonic(), "wide");
+        assert_eq!(wide.stack_delta(), Some((0, 2)));
+    }
+
+    #[test]
+    fn opcode_group_test() {
+        assert_eq!(VMOpcode::nop().group(), OpcodeGroup::Other);
+        assert_eq!(VMOpcode::ldc2_w(1).group(), OpcodeGroup::Constant);
+        assert_eq!(VMOpcode::aload_3().group(), OpcodeGroup::Load);
+        assert_eq!(VMOpcode::saload().group(), OpcodeGroup::Load);
+        assert_eq!(VMOpcode::sastore().group(), OpcodeGroup::Store);
+        assert_eq!(VMOpcode::swap().group(), OpcodeGroup::Stack);
+        assert_eq!(VMOpcode::iinc(0, 1).group(), OpcodeGroup::Arithmetic);
+        assert_eq!(VMOpcode::i2s().group(), OpcodeGroup::Conversion);
+        assert_eq!(VMOpcode::dcmpg().group(), OpcodeGroup::Comparison);
+        assert_eq!(VMOpcode::lookupswitch(0, vec![]).group(), OpcodeGroup::Branch);
+        assert_eq!(VMOpcode::ifnonnull(0).group(), OpcodeGroup::Branch);
+        assert_eq!(VMOpcode::jsr(0).group(), OpcodeGroup::Branch);
+        assert_eq!(VMOpcode::jsr_w(0).group(), OpcodeGroup::Branch);
+        assert_eq!(VMOpcode::ret(0).group(), OpcodeGroup::Branch);
+        assert_eq!(VMOpcode::r#return().group(), OpcodeGroup::Return);
+        assert_eq!(VMOpcode::putstatic(1).group(), OpcodeGroup::FieldAccess);
+        assert_eq!(VMOpcode::invokedynamic(1, 0).group(), OpcodeGroup::Invoke);
+        assert_eq!(VMOpcode::multianewarray(1, 1).group(), OpcodeGroup::Object);
+        assert_eq!(VMOpcode::athrow().group(), OpcodeGroup::Other);
+
+        let wide = VMOpcode::wide_format1(Box::new(VMOpcode::astore(0)), 300);
+        assert_eq!(wide.opcode(), 0xc4);
+        assert_eq!(wide.group(), OpcodeGroup::Store);
+        assert_eq!(VMOpcode::athrow().opcode(), 0xbf);
+        assert_eq!(VMOpcode::jsr(0).opcode(), 0xa8);
+        assert_eq!(VMOpcode::jsr_w(0).opcode(), 0xc9);
+    }
+
+    #[test]
+    fn cfg_test() {
+        let code = [
+            0x03, // 0: iconst_0
+            0xaa, 0x00, 0x00, // 1: tableswitch, padded
+            0x00, 0x00, 0x00, 0x1b, // default => 28
+            0x00, 0x00, 0x00, 0x00, // low
+            0x00, 0x00, 0x00, 0x01, // high
+            0x00, 0x00, 0x00, 0x1a, // 0 => 27
+            0x00, 0x00, 0x00, 0x1b, // 1 => 28
+            0x04, // 24: iconst_1
+            0x57, // 25: pop
+            0xb1, // 26: return
+            0x03, // 27: iconst_0
+            0xac, // 28: ireturn
+        ];
+        let list = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+        assert!(matches!(&list.opcodes[1], VMOpcode::tableswitch(27, 0, 1, v) if v.len() == 2));
+
+        let cfg = list.build_cfg(&[]).unwrap();
+        let blocks: Vec<_> = cfg.blocks.iter().map(|b| (b.start, b.end)).collect();
+        assert_eq!(blocks, vec![(0, 2), (2, 5), (5, 6), (6, 7)]);
+        let targets: Vec<_> = cfg.blocks[0].successors.iter().map(|e| (e.target, e.kind)).collect();
+        assert_eq!(targets, vec![(3, EdgeKind::Switch), (2, EdgeKind::Switch), (3, EdgeKind::Switch)]);
+        assert!(cfg.blocks[1].successors.is_empty());
+        assert_eq!(cfg.blocks[2].successors, vec![Edge { target: 3, kind: EdgeKind::Fallthrough }]);
+        assert_eq!(cfg.block_of(4), Some(1));
+    }
 
+    #[test]
+    fn code_builder_test() {
+        // for (int i = 0; i < 10; i++) {}
+        let mut code = CodeBuilder::new();
+        let (top, end) = (code.new_label(), code.new_label());
+        code.push(VMOpcode::iconst_0())
+            .push(VMOpcode::istore_0())
+            .bind(top)
+            .push(VMOpcode::iload_0())
+            .push(VMOpcode::bipush(10))
+            .branch(VMOpcode::if_icmpge, end)
+            .push(VMOpcode::iinc(0, 1))
+            .branch(VMOpcode::goto, top)
+            .bind(end)
+            .push(VMOpcode::r#return());
+        let list = code.build().unwrap();
+        assert!(matches!(list.opcodes[4], VMOpcode::if_icmpge(9)));
+        assert!(matches!(list.opcodes[6], VMOpcode::goto(-9)));
+        assert_eq!(list.code_to_byte[&6], 11);
+        assert_eq!(list.byte_to_code[&13], 6);
+        let targets: Vec<_> = list.build_cfg(&[]).unwrap().blocks.iter().map(|b| (b.start, b.end)).collect();
+        assert_eq!(targets, vec![(0, 2), (2, 5), (5, 7), (7, 8)]);
+
+        // the switch operands are padded to a multiple of 4
+        let mut code = CodeBuilder::new();
+        let (zero, default) = (code.new_label(), code.new_label());
+        code.push(VMOpcode::iconst_0())
+            .tableswitch(0, default, &[zero, default])
+            .bind(zero)
+            .push(VMOpcode::iconst_1())
+            .lookupswitch(default, &[(5, default), (-1, zero)])
+            .bind(default)
+            .push(VMOpcode::r#return());
+        let list = code.build().unwrap();
+        assert!(matches!(&list.opcodes[1], VMOpcode::tableswitch(51, 0, 1, v) if v == &[23, 51]));
+        assert_eq!(list.code_to_byte[&2], 24);
+        assert_eq!(list.code_to_byte[&3], 25);
+        assert!(matches!(&list.opcodes[3], VMOpcode::lookupswitch(27, v) if v == &[(-1, -1), (5, 27)]));
+        assert_eq!(list.code_to_byte[&4], 52);
+
+        let mut code = CodeBuilder::new();
+        let unbound = code.new_label();
+        code.branch(VMOpcode::goto, unbound);
+        assert!(matches!(code.build(), Err(CodeVerificationError::UnboundLabel)));
     }
 }
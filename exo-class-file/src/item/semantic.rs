@@ -0,0 +1,342 @@
+//! Comparing class files by what they mean rather than how they're laid out.
+
+use alloc::{borrow::Cow, collections::BTreeSet, vec::Vec};
+
+use super::{
+    attribute_info::{attrtype, Annotation, Attributes, AttributesCollection, CodeAttribute, ElementValue, ElementValuePairElement, TypeAnnotation},
+    constant_pool::{ConstantPoolEntry, FieldRefParts, MethodHandleParts, MethodRefParts},
+    fields::FieldInfo,
+    file::{ClassFile, ResolvedIndy},
+    methods::MethodInfo,
+    opcodes::VMOpcode,
+};
+
+/// Attributes left out of the comparison: stack maps are derived from the
+/// code, and bootstrap methods are compared through the `invokedynamic`
+/// constants that use them.
+const IGNORED_ATTRIBUTES: [&str; 2] = [attrtype::StackMapTable, attrtype::BootstrapMethods];
+
+/// A constant pool entry with every index it holds resolved.
+#[derive(Debug, PartialEq)]
+enum Constant<'a> {
+    /// The modified UTF-8 bytes, so strings differing only in
+    /// their unpaired surrogates aren't equal.
+    Utf8(Cow<'a, [u8]>),
+    Class(&'a str),
+    String(Cow<'a, [u8]>),
+    Integer(i32),
+    /// The bits of the float, so `NaN`s compare equal to themselves.
+    Float(u32),
+    Long(i64),
+    Double(u64),
+    NameAndType(&'a str, &'a str),
+    Field(FieldRefParts<'a>),
+    Method(MethodRefParts<'a>),
+    MethodHandle(MethodHandleParts<'a>),
+    MethodType(&'a str),
+    InvokeDynamic(ResolvedIndy<'a>),
+}
+
+fn resolve(file: &ClassFile, index: u16) -> Option<Constant<'_>> {
+    let cp = &file.constant_pool;
+    let i = index as usize;
+    Some(match cp.get_constant(i).ok()? {
+        ConstantPoolEntry::Utf8 { .. } => Constant::Utf8(cp.get_utf8_bytes(i).ok()?),
+        ConstantPoolEntry::Class { .. } => Constant::Class(cp.get_class_name(i).ok()?),
+        ConstantPoolEntry::String { string_index } => Constant::String(cp.get_utf8_bytes(*string_index as usize).ok()?),
+        ConstantPoolEntry::Integer { bytes } => Constant::Integer(*bytes),
+        ConstantPoolEntry::Float { float } => Constant::Float(*float),
+        ConstantPoolEntry::Long { bytes } => Constant::Long(*bytes),
+        ConstantPoolEntry::Double { bytes } => Constant::Double(*bytes),
+        ConstantPoolEntry::NameAndType { .. } => {
+            let (name, descriptor) = cp.get_name_and_type(i).ok()?;
+            Constant::NameAndType(name, descriptor)
+        }
+        ConstantPoolEntry::Fieldref { .. } => Constant::Field(cp.resolve_fieldref(i).ok()?),
+        ConstantPoolEntry::Methodref { .. } | ConstantPoolEntry::InterfaceMethodref { .. } => {
+            Constant::Method(cp.resolve_methodref(i).ok()?)
+        }
+        ConstantPoolEntry::MethodHandle { .. } => Constant::MethodHandle(cp.resolve_method_handle(i).ok()?),
+        ConstantPoolEntry::MethodType { descriptor_index } => {
+            Constant::MethodType(cp.get_utf8_constant(*descriptor_index as usize).ok()?)
+        }
+        ConstantPoolEntry::InvokeDynamic { .. } => Constant::InvokeDynamic(file.resolve_invoke_dynamic(index).ok()?),
+        ConstantPoolEntry::Unusable => return None,
+    })
+}
+
+/// The constant pool index an instruction refers to, if any.
+fn constant_operand(op: &mut VMOpcode) -> Option<&mut u16> {
+    match op {
+        VMOpcode::ldc_w(v)
+        | VMOpcode::ldc2_w(v)
+        | VMOpcode::getstatic(v)
+        | VMOpcode::putstatic(v)
+        | VMOpcode::getfield(v)
+        | VMOpcode::putfield(v)
+        | VMOpcode::invokevirtual(v)
+        | VMOpcode::invokespecial(v)
+        | VMOpcode::invokestatic(v)
+        | VMOpcode::invokeinterface(v, _, _)
+        | VMOpcode::invokedynamic(v, _)
+        | VMOpcode::new(v)
+        | VMOpcode::anewarray(v)
+        | VMOpcode::checkcast(v)
+        | VMOpcode::instanceof(v)
+        | VMOpcode::multianewarray(v, _) => Some(v),
+        _ => None,
+    }
+}
+
+fn all_eq<T>(x: &[T], y: &[T], f: impl Fn(&T, &T) -> bool) -> bool {
+    x.len() == y.len() && x.iter().zip(y).all(|(x, y)| f(x, y))
+}
+
+/// Whether every item of `x` has an equal item in `y` and the other way around.
+fn unordered_eq<T>(x: &[T], y: &[T], f: impl Fn(&T, &T) -> bool) -> bool {
+    x.len() == y.len() && x.iter().all(|x| y.iter().any(|y| f(x, y))) && y.iter().all(|y| x.iter().any(|x| f(x, y)))
+}
+
+/// The names of the attributes that are compared.
+fn attribute_names(attributes: &AttributesCollection) -> BTreeSet<&str> {
+    attributes.names().filter(|k| !IGNORED_ATTRIBUTES.contains(k)).collect()
+}
+
+/// The index of the instruction starting at byte `offset`, where the end
+/// of the code is one past the last instruction.
+fn instruction_index(code: &CodeAttribute, offset: u16) -> Option<usize> {
+    let offset = offset as usize;
+    if offset == code.raw_code.len() {
+        return Some(code.code.opcodes.len());
+    }
+    let index = *code.code.byte_to_code.get(&offset)?;
+    (code.code.code_to_byte.get(&index) == Some(&offset)).then_some(index)
+}
+
+/// Two class files being compared, `a` on the left of every comparison.
+#[derive(Clone, Copy)]
+struct Comparison<'a> {
+    a: &'a ClassFile,
+    b: &'a ClassFile,
+}
+
+type CodePair<'a> = (&'a CodeAttribute, &'a CodeAttribute);
+
+impl Comparison<'_> {
+    fn constant(&self, x: u16, y: u16) -> bool {
+        if x == 0 || y == 0 {
+            return x == y;
+        }
+        matches!((resolve(self.a, x), resolve(self.b, y)), (Some(x), Some(y)) if x == y)
+    }
+
+    fn constants(&self, x: &[u16], y: &[u16]) -> bool {
+        all_eq(x, y, |x, y| self.constant(*x, *y))
+    }
+
+    fn field(&self, x: &FieldInfo, y: &FieldInfo) -> bool {
+        x.access_flags == y.access_flags
+            && self.constant(x.name_index, y.name_index)
+            && self.constant(x.descriptor_index, y.descriptor_index)
+            && self.attributes(&x.attributes, &y.attributes, None)
+    }
+
+    fn method(&self, x: &MethodInfo, y: &MethodInfo) -> bool {
+        x.access_flags == y.access_flags
+            && self.constant(x.name_index, y.name_index)
+            && self.constant(x.descriptor_index, y.descriptor_index)
+            && self.attributes(&x.attributes, &y.attributes, None)
+    }
+
+    fn attributes(&self, x: &AttributesCollection, y: &AttributesCollection, code: Option<CodePair<'_>>) -> bool {
+        let x_names = attribute_names(x);
+        x_names == attribute_names(y)
+            && x_names
+                .iter()
+                .all(|name| {
+                    let (x, y): (Vec<_>, Vec<_>) = (x.get(name).collect(), y.get(name).collect());
+                    all_eq(&x, &y, |x, y| self.attribute(x, y, code))
+                })
+    }
+
+    fn attribute(&self, x: &Attributes, y: &Attributes, code: Option<CodePair<'_>>) -> bool {
+        use Attributes::*;
+        match (x, y) {
+            (ConstantValue { constantvalue_index: x }, ConstantValue { constantvalue_index: y })
+            | (Signature { signature_index: x }, Signature { signature_index: y })
+            | (SourceFile { sourcefile_index: x }, SourceFile { sourcefile_index: y }) => self.constant(*x, *y),
+            (Code(x), Code(y)) => self.code(x, y),
+            (Exceptions { exception_index_table: x }, Exceptions { exception_index_table: y }) => self.constants(x, y),
+            (InnerClasses { classes: x }, InnerClasses { classes: y }) => all_eq(x, y, |x, y| {
+                self.constant(x.inner_class_info_index, y.inner_class_info_index)
+                    && self.constant(x.outer_class_info_index, y.outer_class_info_index)
+                    && self.constant(x.inner_name_index, y.inner_name_index)
+                    && x.inner_class_access_flags == y.inner_class_access_flags
+            }),
+            (
+                EnclosingMethod { class_index: xc, method_index: xm },
+                EnclosingMethod { class_index: yc, method_index: ym },
+            ) => self.constant(*xc, *yc) && self.constant(*xm, *ym),
+            (Synthetic, Synthetic) | (Deprecated, Deprecated) => true,
+            (RuntimeVisibleAnnotations { annotations: x }, RuntimeVisibleAnnotations { annotations: y })
+            | (RuntimeInvisibleAnnotations { annotations: x }, RuntimeInvisibleAnnotations { annotations: y }) => {
+                all_eq(x, y, |x, y| self.annotation(x, y))
+            }
+            (
+                RuntimeVisibleParameterAnnotations { parameter_annotations: x },
+                RuntimeVisibleParameterAnnotations { parameter_annotations: y },
+            )
+            | (
+                RuntimeInvisibleParameterAnnotations { parameter_annotations: x },
+                RuntimeInvisibleParameterAnnotations { parameter_annotations: y },
+            ) => all_eq(x, y, |x, y| all_eq(&x.annotations, &y.annotations, |x, y| self.annotation(x, y))),
+            (RuntimeVisibleTypeAnnotations { annotations: x }, RuntimeVisibleTypeAnnotations { annotations: y })
+            | (RuntimeInvisibleTypeAnnotations { annotations: x }, RuntimeInvisibleTypeAnnotations { annotations: y }) => {
+                all_eq(x, y, |x, y| self.type_annotation(x, y))
+            }
+            (AnnotationDefault { default_value: x }, AnnotationDefault { default_value: y }) => self.element_value(x, y),
+            (MethodParameters { parameters: x }, MethodParameters { parameters: y }) => {
+                all_eq(x, y, |x, y| self.constant(x.name_index, y.name_index) && x.access_flags == y.access_flags)
+            }
+            (Record { components: x }, Record { components: y }) => all_eq(x, y, |x, y| {
+                self.constant(x.name_index, y.name_index)
+                    && self.constant(x.descriptor_index, y.descriptor_index)
+                    && self.attributes(&x.attributes, &y.attributes, None)
+            }),
+            (SourceDebugExtension { debug_extension: x }, SourceDebugExtension { debug_extension: y }) => x == y,
+            (LineNumberTable { line_number_table: x }, LineNumberTable { line_number_table: y }) => code.is_some_and(|code| {
+                all_eq(x, y, |x, y| x.line_number == y.line_number && self.offset(code, x.start_pc, y.start_pc))
+            }),
+            (LocalVariableTable { local_variable_table: x }, LocalVariableTable { local_variable_table: y }) => {
+                code.is_some_and(|code| {
+                    all_eq(x, y, |x, y| {
+                        x.index == y.index
+                            && self.offset(code, x.pc_range.start, y.pc_range.start)
+                            && self.offset(code, x.pc_range.end, y.pc_range.end)
+                            && self.constant(x.name_index, y.name_index)
+                            && self.constant(x.descriptor_index, y.descriptor_index)
+                    })
+                })
+            }
+            (
+                LocalVariableTypeTable { local_variable_type_table: x },
+                LocalVariableTypeTable { local_variable_type_table: y },
+            ) => code.is_some_and(|code| {
+                all_eq(x, y, |x, y| {
+                    x.index == y.index
+                        && self.offset(code, x.pc_range.start, y.pc_range.start)
+                        && self.offset(code, x.pc_range.end, y.pc_range.end)
+                        && self.constant(x.name_index, y.name_index)
+                        && self.constant(x.signature_index, y.signature_index)
+                })
+            }),
+            (Unknown { name: xn, data: xd }, Unknown { name: yn, data: yd }) => xn == yn && xd == yd,
+            _ => false,
+        }
+    }
+
+    /// Whether two byte offsets into code point at the same instruction.
+    fn offset(&self, (x_code, y_code): CodePair<'_>, x: u16, y: u16) -> bool {
+        matches!((instruction_index(x_code, x), instruction_index(y_code, y)), (Some(x), Some(y)) if x == y)
+    }
+
+    fn code(&self, x: &CodeAttribute, y: &CodeAttribute) -> bool {
+        let code = (x, y);
+        x.max_stack == y.max_stack
+            && x.max_locals == y.max_locals
+            && x.code.opcodes.len() == y.code.opcodes.len()
+            && (0..x.code.opcodes.len()).all(|i| self.instruction(x, y, i))
+            && all_eq(&x.exception_table, &y.exception_table, |x, y| {
+                self.offset(code, *x.pc_range.start(), *y.pc_range.start())
+                    && self.offset(code, *x.pc_range.end(), *y.pc_range.end())
+                    && self.offset(code, x.handler_pc, y.handler_pc)
+                    && self.constant(x.catch_type, y.catch_type)
+            })
+            && self.attributes(&x.attributes, &y.attributes, Some(code))
+    }
+
+    /// Compare the instructions at `index`, by the instructions their branches
+    /// land on and the constants they refer to rather than by raw operands.
+    fn instruction(&self, x: &CodeAttribute, y: &CodeAttribute, index: usize) -> bool {
+        let (Ok((mut x, x_targets)), Ok((mut y, y_targets))) =
+            (x.code.normalized_instruction(index), y.code.normalized_instruction(index))
+        else {
+            return false;
+        };
+        for op in [&mut x, &mut y] {
+            if let VMOpcode::ldc(v) = op {
+                *op = VMOpcode::ldc_w(*v as u16);
+            }
+        }
+        let x_constant = constant_operand(&mut x).map(core::mem::take);
+        let y_constant = constant_operand(&mut y).map(core::mem::take);
+        let constants = match (x_constant, y_constant) {
+            (Some(x), Some(y)) => self.constant(x, y),
+            (x, y) => x == y,
+        };
+        constants && x == y && x_targets == y_targets
+    }
+
+    fn annotation(&self, x: &Annotation, y: &Annotation) -> bool {
+        self.constant(x.type_index, y.type_index) && self.element_value_pairs(&x.element_value_pairs, &y.element_value_pairs)
+    }
+
+    fn type_annotation(&self, x: &TypeAnnotation, y: &TypeAnnotation) -> bool {
+        x.target_type == y.target_type
+            && x.target_info == y.target_info
+            && x.target_path == y.target_path
+            && self.constant(x.type_index, y.type_index)
+            && self.element_value_pairs(&x.element_value_pairs, &y.element_value_pairs)
+    }
+
+    fn element_value_pairs(&self, x: &[ElementValuePairElement], y: &[ElementValuePairElement]) -> bool {
+        all_eq(x, y, |x, y| {
+            self.constant(x.element_name_index, y.element_name_index) && self.element_value(&x.value, &y.value)
+        })
+    }
+
+    fn element_value(&self, x: &ElementValue, y: &ElementValue) -> bool {
+        match (x, y) {
+            (ElementValue::Const { tag: xt, index: xi }, ElementValue::Const { tag: yt, index: yi }) => {
+                xt == yt && self.constant(*xi, *yi)
+            }
+            (
+                ElementValue::EnumConstValue { type_name_index: xt, const_name_index: xc },
+                ElementValue::EnumConstValue { type_name_index: yt, const_name_index: yc },
+            ) => self.constant(*xt, *yt) && self.constant(*xc, *yc),
+            (ElementValue::ClassInfoIndex { class_info_index: x }, ElementValue::ClassInfoIndex { class_info_index: y }) => {
+                self.constant(*x, *y)
+            }
+            (ElementValue::AnnotationValue { annotation_value: x }, ElementValue::AnnotationValue { annotation_value: y }) => {
+                self.annotation(x, y)
+            }
+            (ElementValue::ArrayValue { values: x }, ElementValue::ArrayValue { values: y }) => {
+                all_eq(x, y, |x, y| self.element_value(x, y))
+            }
+            _ => false,
+        }
+    }
+}
+
+impl ClassFile {
+    /// Whether this class file means the same as `other`, regardless of how
+    /// either lays out its constant pool.
+    ///
+    /// Constant pool references are compared by what they resolve to, and
+    /// instructions by the instructions their branches land on, so `ldc` and
+    /// `ldc_w` of the same constant are equal. Fields and methods may be in
+    /// any order. `StackMapTable` attributes are ignored, and bootstrap
+    /// methods are only compared through the `invokedynamic` instructions
+    /// using them. A reference that can't be resolved is never equal.
+    pub fn semantically_eq(&self, other: &ClassFile) -> bool {
+        let c = Comparison { a: self, b: other };
+        self.version == other.version
+            && self.access_flags == other.access_flags
+            && c.constant(self.this_class, other.this_class)
+            && c.constant(self.super_class, other.super_class)
+            && c.constants(&self.interfaces, &other.interfaces)
+            && unordered_eq(&self.fields, &other.fields, |x, y| c.field(x, y))
+            && unordered_eq(&self.methods, &other.methods, |x, y| c.method(x, y))
+            && c.attributes(&self.attributes, &other.attributes, None)
+    }
+}
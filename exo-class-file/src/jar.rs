@@ -0,0 +1,181 @@
+//! Random access to the classes in a jar.
+//!
+//! Only available with the `zip` feature.
+
+use std::io::{ErrorKind, Read, Seek, SeekFrom};
+
+use flate2::read::DeflateDecoder;
+use zip::{CompressionMethod, ZipArchive};
+
+use crate::{
+    error,
+    item::file::ClassFile,
+    Map,
+};
+
+/// The most bytes reserved for a class before reading it, whatever size the jar declares.
+const MAX_INITIAL_CAPACITY: u64 = 1 << 20;
+
+/// Where a class is stored in a jar.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct JarEntry {
+    /// Offset of the entry's data from the start of the jar.
+    pub data_start: u64,
+    /// Size of the entry's data as stored.
+    pub compressed_size: u64,
+    /// Size of the class file once decompressed.
+    pub size: u64,
+    /// Whether the data is deflated, rather than stored.
+    pub deflated: bool,
+}
+
+/// An index of the classes in a jar, keyed by internal class name.
+///
+/// The index is built once by reading the jar's central directory; classes
+/// are then only read and parsed when asked for with [JarIndex::load_class].
+#[derive(Clone, Debug, Default)]
+pub struct JarIndex {
+    entries: Map<String, JarEntry>,
+}
+
+impl JarIndex {
+    /// Index the `.class` entries of the jar in `reader`.
+    pub fn new<R: Read + Seek>(reader: R) -> error::Result<Self> {
+        let mut archive = ZipArchive::new(reader).map_err(std::io::Error::from)?;
+        let mut entries = Map::new();
+        for i in 0..archive.len() {
+            let file = archive.by_index_raw(i).map_err(std::io::Error::from)?;
+            let name = match file.name().strip_suffix(".class") {
+                Some(v) if file.is_file() => v,
+                _ => continue,
+            };
+            let deflated = match file.compression() {
+                CompressionMethod::Stored => false,
+                CompressionMethod::Deflated => true,
+                _ => continue,
+            };
+            entries.insert(
+                name.to_string(),
+                JarEntry {
+                    data_start: file.data_start(),
+                    compressed_size: file.compressed_size(),
+                    size: file.size(),
+                    deflated,
+                },
+            );
+        }
+        Ok(Self { entries })
+    }
+
+    /// The number of classes in the jar.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Whether the jar holds no classes.
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The internal names of the classes in the jar, in no particular order.
+    pub fn class_names(&self) -> impl Iterator<Item = &str> {
+        self.entries.keys().map(|v| v.as_str())
+    }
+
+    /// Get where the class `name` is stored, if it is in the jar.
+    pub fn entry(&self, name: &str) -> Option<&JarEntry> {
+        self.entries.get(name)
+    }
+
+    /// Read and parse the class `name` from `reader`, which must hold the
+    /// same jar this index was built from. Returns `None` if the class is
+    /// not in the jar.
+    pub fn load_class<R: Read + Seek>(&self, name: &str, mut reader: R) -> Option<error::Result<ClassFile>> {
+        let entry = self.entries.get(name)?;
+        Some(Self::read_entry(entry, &mut reader).and_then(|bytes| ClassFile::read_from_bytes(&bytes)))
+    }
+
+    /// Read the decompressed data of `entry`, failing if it is longer than
+    /// the size the jar declares for it.
+    fn read_entry<R: Read + Seek>(entry: &JarEntry, reader: &mut R) -> error::Result<Vec<u8>> {
+        reader.seek(SeekFrom::Start(entry.data_start))?;
+        let data = reader.take(entry.compressed_size);
+        let data: Box<dyn Read + '_> = match entry.deflated {
+            true => Box::new(DeflateDecoder::new(data)),
+            false => Box::new(data),
+        };
+        // the declared size is untrusted, so only let it reserve so much up front,
+        // and read one byte past it to tell whether the data is longer
+        let mut bytes = Vec::with_capacity(entry.size.min(MAX_INITIAL_CAPACITY) as usize);
+        data.take(entry.size.saturating_add(1)).read_to_end(&mut bytes)?;
+        if bytes.len() as u64 > entry.size {
+            return Err(std::io::Error::new(ErrorKind::InvalidData, "jar entry is larger than its declared size").into());
+        }
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, ErrorKind, Write};
+
+    use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+    use crate::{error::ClassFileError, item::file::ClassFile};
+
+    use super::JarIndex;
+
+    #[test]
+    fn load_single_class_test() {
+        let class = include_bytes!("../../local/Test.class");
+        let mut writer = ZipWriter::new(Cursor::new(vec![]));
+        writer.start_file("META-INF/MANIFEST.MF", FileOptions::default()).unwrap();
+        writer.write_all(b"Manifest-Version: 1.0\r\n").unwrap();
+        writer.start_file("pkg/Test.class", FileOptions::default()).unwrap();
+        writer.write_all(class).unwrap();
+        let stored = FileOptions::default().compression_method(CompressionMethod::Stored);
+        writer.start_file("pkg/Stored.class", stored).unwrap();
+        writer.write_all(class).unwrap();
+        // never parsed, as only the classes asked for are read
+        writer.start_file("pkg/Broken.class", FileOptions::default()).unwrap();
+        writer.write_all(b"not a class").unwrap();
+        let jar = writer.finish().unwrap().into_inner();
+
+        let index = JarIndex::new(Cursor::new(&jar)).unwrap();
+        assert_eq!(index.len(), 3);
+        assert!(index.entry("pkg/Test").unwrap().deflated);
+        assert!(!index.entry("pkg/Stored").unwrap().deflated);
+
+        let mut reader = Cursor::new(&jar);
+        let loaded = index.load_class("pkg/Test", &mut reader).unwrap().unwrap();
+        assert_eq!(loaded.class_name().unwrap(), "Test");
+        assert_eq!(loaded.methods.len(), ClassFile::read_from_bytes(class).unwrap().methods.len());
+        let stored = index.load_class("pkg/Stored", &mut reader).unwrap().unwrap();
+        assert_eq!(stored.class_name().unwrap(), loaded.class_name().unwrap());
+        assert!(index.load_class("pkg/Missing", &mut reader).is_none());
+        assert!(index.load_class("pkg/Broken", &mut reader).unwrap().is_err());
+    }
+
+    #[test]
+    fn declared_size_test() {
+        let class = include_bytes!("../../local/Test.class");
+        let mut writer = ZipWriter::new(Cursor::new(vec![]));
+        writer.start_file("pkg/Test.class", FileOptions::default()).unwrap();
+        writer.write_all(class).unwrap();
+        let jar = writer.finish().unwrap().into_inner();
+        let mut index = JarIndex::new(Cursor::new(&jar)).unwrap();
+
+        // a size larger than the data is only a capacity hint
+        index.entries.get_mut("pkg/Test").unwrap().size = u64::MAX;
+        let loaded = index.load_class("pkg/Test", Cursor::new(&jar)).unwrap().unwrap();
+        assert_eq!(loaded.class_name().unwrap(), "Test");
+        assert_eq!(loaded.methods.len(), ClassFile::read_from_bytes(class).unwrap().methods.len());
+
+        // data past the declared size is rejected rather than read
+        index.entries.get_mut("pkg/Test").unwrap().size = class.len() as u64 - 1;
+        let Some(Err(ClassFileError::IoError(e))) = index.load_class("pkg/Test", Cursor::new(&jar)) else {
+            panic!("expected an I/O error");
+        };
+        assert_eq!(e.kind(), ErrorKind::InvalidData);
+    }
+}
@@ -1,14 +1,41 @@
 //! Class file parser.
 //! 
 //! Loads class files into an easily usable data structure.
+//!
+//! Without the default `std` feature, the crate only depends on `core` and `alloc`.
+//! The `zip` feature adds [jar], for reading classes out of jars.
+//! The `hex-dump` feature adds [hexdump], for inspecting the raw bytes of
+//! attributes the parser doesn't understand.
 
+#![cfg_attr(not(feature = "std"), no_std)]
+
+extern crate alloc;
 
 pub mod item;
 pub mod stream;
 pub mod error;
+pub mod io;
+#[cfg(feature = "zip")]
+pub mod jar;
+#[cfg(feature = "hex-dump")]
+pub mod hexdump;
 
 pub use exo_parser;
 
+/// String-keyed map used by parsed structures.
+#[cfg(feature = "std")]
+pub type Map<K, V> = std::collections::HashMap<K, V>;
+/// String-keyed map used by parsed structures.
+#[cfg(not(feature = "std"))]
+pub type Map<K, V> = alloc::collections::BTreeMap<K, V>;
+
+/// Integer-keyed map used by parsed structures.
+#[cfg(feature = "std")]
+pub type IntMap<K, V> = fnv::FnvHashMap<K, V>;
+/// Integer-keyed map used by parsed structures.
+#[cfg(not(feature = "std"))]
+pub type IntMap<K, V> = alloc::collections::BTreeMap<K, V>;
+
 
 // pub struct Object {
 //     pub identity_hashcode: JInt,
@@ -1,26 +1,230 @@
-use std::io::Read;
+use alloc::{string::String, vec::Vec};
+use crate::io::Read;
 
-use crate::{error, item::{ClassFileItem, ConstantPool}};
+use crate::{error, item::{attribute_info::AttributeContext, ClassFileItem, ConstantPool}};
+
+/// Largest number of bytes or items allocated up front for a length read from the stream.
+const READ_CHUNK_SIZE: usize = 4096;
+
+/// The largest `code_length` the specification allows in a `Code` attribute.
+pub const MAX_CODE_LENGTH: u32 = 65535;
+
+/// The default limit on how deeply annotations and element values may nest.
+pub const MAX_ANNOTATION_DEPTH: u32 = 255;
+
+/// Options controlling how strictly a class file is parsed.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    /// When set, attributes with unrecognized names are stored as
+    /// [Attributes::Unknown](crate::item::attribute_info::Attributes::Unknown)
+    /// instead of returning [ClassFileError::UnknownAttribute](error::ClassFileError::UnknownAttribute).
+    pub tolerant: bool,
+    /// When set, a `Code` attribute whose last instruction is truncated
+    /// keeps the instructions decoded before it, instead of returning the
+    /// error. Malformed instructions are still rejected.
+    pub eof_tolerant: bool,
+    /// The largest `code_length` accepted in a `Code` attribute, in bytes.
+    /// When `None`, the limit of [MAX_CODE_LENGTH] from the specification applies.
+    pub max_code_length: Option<u32>,
+    /// When set, any [ParseDiagnostic] is returned as
+    /// [ClassFileError::Diagnostic](error::ClassFileError::Diagnostic)
+    /// instead of being recorded.
+    pub diagnostics_as_errors: bool,
+    /// How deeply annotations and array element values may nest.
+    /// When `None`, the limit is [MAX_ANNOTATION_DEPTH].
+    pub max_annotation_depth: Option<u32>,
+    /// The most bytes a stream may read in total. When `None`, there is no limit.
+    pub max_bytes: Option<usize>,
+    /// When set, a malformed field, method or attribute is skipped and its
+    /// error recorded as a [RecoveredError], rather than failing the whole
+    /// class file. See [ClassFile::partial](crate::item::file::ClassFile::partial).
+    pub recover: bool,
+    /// When set, the operand bytes of `invokeinterface` and `invokedynamic`
+    /// which must be zero are checked while parsing, returning
+    /// [ClassFileError::BadInstructionOperand](error::ClassFileError::BadInstructionOperand)
+    /// rather than waiting for verification.
+    pub check_operands: bool,
+    /// When set, a class file keeps a copy of the bytes it was parsed from,
+    /// available from [ClassFile::source_bytes](crate::item::file::ClassFile::source_bytes).
+    pub capture_source: bool,
+}
+
+/// A suspicious but not fatal condition found while parsing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseDiagnostic {
+    /// Recorded when an attribute the specification allows at most once
+    /// appears again in the same attribute table.
+    DuplicateAttribute {
+        name: String,
+        /// The structure the attribute table belongs to, if known.
+        context: Option<AttributeContext>,
+    },
+    /// Recorded when a `LineNumberTable` entry starts at or after the end of its code.
+    LineNumberOutOfRange { start_pc: u16, code_length: u32 },
+}
+
+/// An item skipped while parsing with [ParseOptions::recover].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SkippedItem {
+    /// The field at this index in the class file's fields table.
+    Field(usize),
+    /// The method at this index in the class file's methods table.
+    Method(usize),
+    /// An attribute, with the index of its name in the constant pool.
+    Attribute {
+        name_index: u16,
+        /// The structure the attribute table belongs to, if known.
+        context: Option<AttributeContext>,
+    },
+}
+
+/// The error which caused an item to be skipped while parsing with [ParseOptions::recover].
+#[derive(Debug)]
+pub struct RecoveredError {
+    pub item: SkippedItem,
+    pub error: error::ClassFileError,
+}
+
+impl ParseOptions {
+    /// The default options, with instruction operands also checked while parsing.
+    pub fn strict() -> Self {
+        Self {
+            check_operands: true,
+            ..Self::default()
+        }
+    }
+
+    /// Options which preserve unrecognized attributes rather than rejecting them.
+    pub fn tolerant() -> Self {
+        Self {
+            tolerant: true,
+            ..Self::default()
+        }
+    }
+
+    /// The largest `code_length` accepted in a `Code` attribute.
+    pub fn code_length_limit(&self) -> u32 {
+        self.max_code_length.unwrap_or(MAX_CODE_LENGTH)
+    }
+
+    /// How deeply annotations and array element values may nest.
+    pub fn annotation_depth_limit(&self) -> u32 {
+        self.max_annotation_depth.unwrap_or(MAX_ANNOTATION_DEPTH)
+    }
+
+    /// Options which stop decoding code at a truncated instruction
+    /// rather than rejecting the class file.
+    pub fn eof_tolerant() -> Self {
+        Self {
+            eof_tolerant: true,
+            ..Self::default()
+        }
+    }
+}
 
 /// A utility wrapper to allow easily reading class file types from a [Reader](std::io::Read).
-pub struct ClassFileStream<'a, R: Read>(pub &'a mut R, pub usize);
+///
+/// The last two fields hold the diagnostics recorded while reading from the
+/// stream, and the errors of items skipped with [ParseOptions::recover].
+pub struct ClassFileStream<'a, R: Read>(
+    pub &'a mut R,
+    pub usize,
+    pub ParseOptions,
+    pub Vec<ParseDiagnostic>,
+    pub Vec<RecoveredError>,
+);
 
 impl<'a, R: Read> ClassFileStream<'a, R> {
 
     /// Create a new stream from a reader.
     pub fn new(r: &'a mut R) -> Self {
-        Self(r, 0)
+        Self(r, 0, ParseOptions::default(), Vec::new(), Vec::new())
+    }
+
+    /// Create a new stream from a reader with the given parse options.
+    pub fn with_options(r: &'a mut R, options: ParseOptions) -> Self {
+        Self(r, 0, options, Vec::new(), Vec::new())
+    }
+
+    /// Create a new stream from a reader which fails with
+    /// [ClassFileError::ByteLimitExceeded](error::ClassFileError::ByteLimitExceeded)
+    /// rather than read more than `max_bytes` bytes.
+    pub fn with_limit(r: &'a mut R, max_bytes: usize) -> Self {
+        Self::with_options(r, ParseOptions { max_bytes: Some(max_bytes), ..ParseOptions::default() })
+    }
+
+    /// The parse options of this stream.
+    pub fn options(&self) -> ParseOptions {
+        self.2
+    }
+
+    /// The number of bytes read from the stream so far.
+    ///
+    /// After reading a [ClassFile](crate::item::file::ClassFile), this is
+    /// where any data following it in the underlying reader begins.
+    pub fn position(&self) -> usize {
+        self.1
+    }
+
+    /// The diagnostics recorded so far.
+    pub fn diagnostics(&self) -> &[ParseDiagnostic] {
+        &self.3
+    }
+
+    /// The errors of the items skipped so far.
+    pub fn recovered_errors(&self) -> &[RecoveredError] {
+        &self.4
+    }
+
+    /// Record that `item` was skipped because of `error` if [ParseOptions::recover]
+    /// is set, or otherwise return the error.
+    pub fn recover(&mut self, item: SkippedItem, error: error::ClassFileError) -> error::Result<()> {
+        if !self.2.recover {
+            return Err(error);
+        }
+        self.4.push(RecoveredError { item, error });
+        Ok(())
+    }
+
+    /// Move the diagnostics and recovered errors of `inner`, a stream
+    /// over part of this one, into this stream.
+    pub fn absorb<I: Read>(&mut self, inner: &mut ClassFileStream<I>) {
+        self.3.append(&mut inner.3);
+        self.4.append(&mut inner.4);
+    }
+
+    /// Record a non-fatal issue, or return it as an error if
+    /// [ParseOptions::diagnostics_as_errors] is set.
+    pub fn diagnose(&mut self, diagnostic: ParseDiagnostic) -> error::Result<()> {
+        if self.2.diagnostics_as_errors {
+            return Err(error::ClassFileError::Diagnostic(diagnostic));
+        }
+        self.3.push(diagnostic);
+        Ok(())
     }
 
     /// Read a sequence of `length` `T`s from this stream.
     pub fn read_sequence<T: ClassFileItem>(&mut self, constant_pool: Option<&ConstantPool>, length: usize) -> error::Result<Vec<T>> {
-        let mut v = Vec::with_capacity(length);
+        let mut v = Vec::with_capacity(length.min(READ_CHUNK_SIZE));
         for _ in 0..length {
             v.push(T::read_from_stream(self, constant_pool)?);
         }
         Ok(v)
     }
 
+    /// Read a sequence of `T`s preceded by its length as an unsigned 2-byte integer,
+    /// the layout of most tables in a class file.
+    pub fn read_u2_prefixed_sequence<T: ClassFileItem>(&mut self, constant_pool: Option<&ConstantPool>) -> error::Result<Vec<T>> {
+        let length = self.read_u2()?;
+        self.read_sequence(constant_pool, length as usize)
+    }
+
+    /// Read a sequence of `T`s preceded by its length as an unsigned 4-byte integer.
+    pub fn read_u4_prefixed_sequence<T: ClassFileItem>(&mut self, constant_pool: Option<&ConstantPool>) -> error::Result<Vec<T>> {
+        let length = self.read_u4()?;
+        self.read_sequence(constant_pool, length as usize)
+    }
+
     /// Read an unsigned 4-byte integer from the stream.
     pub fn read_u4(&mut self) -> error::Result<u32> {
         Ok(u32::from_be_bytes(self.read::<4>()?))
@@ -36,31 +240,94 @@ impl<'a, R: Read> ClassFileStream<'a, R> {
         Ok(self.read::<1>()?[0])
     }
 
+    /// The position after reading `n` more bytes, or an error if it
+    /// doesn't fit in a `usize` or would pass [ParseOptions::max_bytes].
+    ///
+    /// Every read goes through here, so a length which claims more data
+    /// than the limit allows fails before anything is read or allocated.
+    fn position_after(&self, n: usize) -> error::Result<usize> {
+        let end = self.1.checked_add(n).ok_or(error::ClassFileError::ArithmeticError)?;
+        match self.2.max_bytes {
+            Some(max) if end > max => Err(error::ClassFileError::ByteLimitExceeded(max)),
+            _ => Ok(end),
+        }
+    }
+
+    /// Move the position forward by `n` bytes read from the underlying reader
+    /// by other means.
+    pub(crate) fn advance(&mut self, n: usize) -> error::Result<()> {
+        self.1 = self.position_after(n)?;
+        Ok(())
+    }
+
     /// Utility method to read `S` bytes from the stream.
     pub fn read<const S: usize>(&mut self) -> error::Result<[u8; S]> {
+        let end = self.position_after(S)?;
         let mut w = [0; S];
-        self.0
-            .read_exact(&mut w)
-            .map_err(error::ClassFileError::IoError)?;
-        self.1 += S;
+        self.0.read_exact(&mut w)?;
+        self.1 = end;
         Ok(w)
     }
 
     /// Utility method to read `S` bytes from the stream with runtime length.
+    ///
+    /// The buffer grows as data is read, so an untrusted length
+    /// can't force a large allocation for data that isn't there.
     pub fn read_dynamic(&mut self, l: usize) -> error::Result<Vec<u8>> {
-        let mut w = vec![0; l];
-        self.0
-            .read_exact(&mut w)
-            .map_err(error::ClassFileError::IoError)?;
-        self.1 += l;
+        let end = self.position_after(l)?;
+        let mut w = Vec::with_capacity(l.min(READ_CHUNK_SIZE));
+        let mut chunk = [0; READ_CHUNK_SIZE];
+        let mut remaining = l;
+        while remaining > 0 {
+            let n = remaining.min(READ_CHUNK_SIZE);
+            self.0.read_exact(&mut chunk[..n])?;
+            w.extend_from_slice(&chunk[..n]);
+            remaining -= n;
+        }
+        self.1 = end;
         Ok(w)
     }
 
 }
+/// A reader which keeps a copy of every byte read through it.
+pub(crate) struct RecordingReader<'a, R: Read> {
+    inner: &'a mut R,
+    bytes: Vec<u8>,
+}
+
+impl<'a, R: Read> RecordingReader<'a, R> {
+    pub(crate) fn new(inner: &'a mut R) -> Self {
+        Self { inner, bytes: Vec::new() }
+    }
+
+    /// The bytes read so far.
+    pub(crate) fn into_bytes(self) -> Vec<u8> {
+        self.bytes
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'a, R: Read> Read for RecordingReader<'a, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        let n = self.inner.read(buf)?;
+        self.bytes.extend_from_slice(&buf[..n]);
+        Ok(n)
+    }
+}
+
+#[cfg(not(feature = "std"))]
+impl<'a, R: Read> Read for RecordingReader<'a, R> {
+    fn read_exact(&mut self, buf: &mut [u8]) -> core::result::Result<(), crate::io::Error> {
+        self.inner.read_exact(buf)?;
+        self.bytes.extend_from_slice(buf);
+        Ok(())
+    }
+}
+
 impl ClassFileItem for u8 {
     fn read_from_stream<R: Read>(s: &mut ClassFileStream<R>, cp: Option<&ConstantPool>) -> error::Result<Self>
     where
-        Self: std::marker::Sized {
+        Self: core::marker::Sized {
         s.read_u1()
     }
 }
@@ -68,7 +335,7 @@ impl ClassFileItem for u8 {
 impl ClassFileItem for u16 {
     fn read_from_stream<R: Read>(s: &mut ClassFileStream<R>, cp: Option<&ConstantPool>) -> error::Result<Self>
     where
-        Self: std::marker::Sized {
+        Self: core::marker::Sized {
         s.read_u2()
     }
 }
@@ -76,7 +343,7 @@ impl ClassFileItem for u16 {
 impl ClassFileItem for i16 {
     fn read_from_stream<R: Read>(s: &mut ClassFileStream<R>, cp: Option<&ConstantPool>) -> error::Result<Self>
     where
-        Self: std::marker::Sized {
+        Self: core::marker::Sized {
         Ok(s.read_u2()? as i16)
     }
 }
@@ -84,7 +351,97 @@ impl ClassFileItem for i16 {
 impl ClassFileItem for u32 {
     fn read_from_stream<R: Read>(s: &mut ClassFileStream<R>, cp: Option<&ConstantPool>) -> error::Result<Self>
     where
-        Self: std::marker::Sized {
+        Self: core::marker::Sized {
         s.read_u4()
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec;
+
+    use crate::{error::ClassFileError, io::Cursor, item::{file::ClassFile, ClassFileItem}};
+
+    use super::ClassFileStream;
+
+    #[test]
+    fn read_dynamic_truncated_test() {
+        let data = [1u8, 2, 3];
+        let mut cursor = Cursor::new(&data[..]);
+        let mut s = ClassFileStream::new(&mut cursor);
+        assert!(matches!(s.read_dynamic(u32::MAX as usize), Err(ClassFileError::IoError(_))));
+
+        let data = [0u8; 10000];
+        let mut cursor = Cursor::new(&data[..]);
+        let mut s = ClassFileStream::new(&mut cursor);
+        assert_eq!(s.read_dynamic(9000).unwrap().len(), 9000);
+        assert_eq!(s.1, 9000);
+    }
+
+    #[test]
+    fn position_overflow_test() {
+        let data = [0u8; 16];
+        let mut cursor = Cursor::new(&data[..]);
+        let mut s = ClassFileStream::new(&mut cursor);
+        s.1 = usize::MAX - 1;
+        assert_eq!(s.read_u1().unwrap(), 0);
+        assert!(matches!(s.read_u2(), Err(ClassFileError::ArithmeticError)));
+        assert!(matches!(s.read_dynamic(4), Err(ClassFileError::ArithmeticError)));
+        assert!(matches!(s.advance(1), Err(ClassFileError::ArithmeticError)));
+        assert_eq!(s.1, usize::MAX);
+        // nothing is consumed by a read which would overflow
+        assert_eq!(cursor.position(), 1);
+    }
+
+    #[test]
+    fn prefixed_sequence_test() {
+        let data = [0u8, 2, 7, 8, 0, 0, 0, 1, 9, 0, 5];
+        let mut cursor = Cursor::new(&data[..]);
+        let mut s = ClassFileStream::new(&mut cursor);
+        assert_eq!(s.read_u2_prefixed_sequence::<u8>(None).unwrap(), [7, 8]);
+        assert_eq!(s.read_u4_prefixed_sequence::<u8>(None).unwrap(), [9]);
+        assert!(matches!(s.read_u2_prefixed_sequence::<u8>(None), Err(ClassFileError::IoError(_))));
+    }
+
+    #[test]
+    #[cfg(feature = "std")]
+    fn truncated_class_test() {
+        use std::error::Error;
+
+        let file = include_bytes!("../../local/Test.class");
+        for len in 0..file.len() {
+            match ClassFile::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(&file[..len])), None) {
+                Err(ClassFileError::IoError(e)) => assert_eq!(e.kind(), std::io::ErrorKind::UnexpectedEof),
+                other => panic!("truncated to {} bytes: {:?}", len, other.map(|_| ())),
+            }
+        }
+
+        let e = ClassFile::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(&file[..4])), None).unwrap_err();
+        assert!(e.to_string().starts_with("I/O error"));
+        assert!(e.source().unwrap().downcast_ref::<std::io::Error>().is_some());
+    }
+
+    #[test]
+    fn byte_limit_test() {
+        // an otherwise empty class with a SourceFile attribute claiming to be 1MB long
+        let mut bytes = vec![0xca, 0xfe, 0xba, 0xbe, 0x00, 0x00, 0x00, 0x34, 0x00, 0x02, 0x01, 0x00, 0x0a];
+        bytes.extend(b"SourceFile");
+        bytes.extend([0x00, 0x21, 0x00, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x01]);
+        bytes.extend([0x00, 0x01, 0x00, 0x10, 0x00, 0x00]);
+        bytes.extend([0; 2048]);
+        let result = ClassFile::read_from_stream(&mut ClassFileStream::with_limit(&mut Cursor::new(&bytes), 1024), None);
+        assert!(matches!(result, Err(ClassFileError::ByteLimitExceeded(1024))));
+
+        let file = include_bytes!("../../local/Test.class");
+        assert!(ClassFile::read_from_stream(&mut ClassFileStream::with_limit(&mut Cursor::new(file), file.len()), None).is_ok());
+        let short = ClassFile::read_from_stream(&mut ClassFileStream::with_limit(&mut Cursor::new(file), file.len() - 1), None);
+        assert!(matches!(short, Err(ClassFileError::ByteLimitExceeded(_))));
+
+        // the limit covers everything read from the stream, not just one class
+        let twice = [&file[..], &file[..]].concat();
+        let mut cursor = Cursor::new(&twice);
+        let mut s = ClassFileStream::with_limit(&mut cursor, file.len() + 16);
+        assert!(ClassFile::read_from_stream(&mut s, None).is_ok());
+        assert!(matches!(ClassFile::read_from_stream(&mut s, None), Err(ClassFileError::ByteLimitExceeded(_))));
+    }
+}
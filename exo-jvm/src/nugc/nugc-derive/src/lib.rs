@@ -62,7 +62,12 @@ pub fn generate_write_barriers(f: TokenStream, a: TokenStream) -> TokenStream {
 
 
 
-/// Generates a `Trace` implementation for the struct.
+/// Generates a `Trace` implementation for the struct, for use inside `exo-jvm`.
+///
+/// Every field is visited with `visit_noref`, so fields holding `GcRef`s,
+/// directly or in a `Vec`, are traced. A matching `WriteBarrier`
+/// implementation checks the same fields. Fields marked
+/// `#[unsafe_ignore_trace]` are skipped by both.
 #[proc_macro_derive(Trace, attributes(unsafe_ignore_trace))]
 pub fn object_impl(f: TokenStream) -> TokenStream {
     let v: syn::DeriveInput = syn::parse(f).unwrap();
@@ -92,32 +97,36 @@ pub fn object_impl(f: TokenStream) -> TokenStream {
             let mut paths: Vec<syn::Member> = fields.iter().map(|v| v.ident.clone().map(|v| syn::Member::Named(v)).unwrap_or_else(|| {n+=1; syn::Member::Unnamed(syn::Index { index: n-1, span: Span::call_site() })})).collect();
 
             let i = v.ident;
-            let needs_traced = syn::LitBool {
-                value: fields.len() > 0,
-                span: Span::call_site()
-            };
             quote! {
-        
-                unsafe impl Trace for #i {
-                    const NEEDS_TRACED: bool = #(<#types>::NEEDS_TRACED) &&* && #needs_traced;
-                    fn trace<V: Visitor>(
+
+                unsafe impl crate::vm::collector::object::Trace for #i {
+                    const NEEDS_TRACED: bool = false #(|| <#types as crate::vm::collector::object::Trace>::NEEDS_TRACED)*;
+                    fn trace(
                         &mut self,
-                        gc: &GarbageCollector,
-                        visitor: &mut V,
+                        gc: &mut crate::vm::VMGcState,
+                        visitor: &mut crate::vm::collector::object::VisitorImpl,
                     ) {
                         #(
                             visitor.visit_noref(gc, &mut self.#paths);
                         )*
                     }
                 }
-        
+
+                impl crate::vm::collector::object::WriteBarrier for #i {
+                    fn write_barrier(&self, collector_id: u8) {
+                        #(
+                            crate::vm::collector::object::WriteBarrier::write_barrier(&self.#paths, collector_id);
+                        )*
+                    }
+                }
+
                 const _: fn() = || {
-                    fn check_impl<T: ?Sized + Trace>() {}
+                    fn check_impl<T: ?Sized + crate::vm::collector::object::GcObject>() {}
                     #(
                         check_impl::<#types>();
                     )*
                 };
-        
+
             }.into()
         }
         _ => todo!()
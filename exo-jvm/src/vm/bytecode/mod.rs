@@ -26,7 +26,7 @@ pub fn process(v: ClassFile) {
             .get_utf8_constant(method.descriptor_index as usize)
             .unwrap();
         if name == "doThing" {
-            let data = method.attributes.get("Code").first().unwrap();
+            let data = method.attributes.first("Code").unwrap();
 
             let mut byte_to_code = FnvHashMap::default();
             for i in 0..100 {
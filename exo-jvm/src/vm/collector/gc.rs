@@ -1,5 +1,7 @@
 use std::{
     alloc::Layout,
+    marker::PhantomData,
+    mem::MaybeUninit,
     num::NonZeroUsize,
     sync::{
         atomic::{AtomicBool, AtomicU8, Ordering},
@@ -7,6 +9,7 @@ use std::{
     }, ptr::NonNull,
 };
 
+use exo_class_file::item::constant_pool::encode_modified_utf8;
 use fnv::FnvHashMap;
 use parking_lot::{Condvar, Mutex};
 
@@ -16,7 +19,12 @@ use crate::vm::{
     VM,
 };
 
-use super::{object::{GcObject, VisitorImpl}, structures::{GcRef, Structure, StructureDef, StructureMetadata, structure_vtable}, LinkedListAllocator, GcRootVTable};
+use super::{object::{GcObject, VisitorImpl}, structures::{AllocationError, GcArray, GcRef, GcStr, Structure, StructureDef, StructureMetadata, WeakGcRef, structure_vtable}, LinkedListAllocator, GcRootVTable};
+
+/// A type-erased pointer to an allocated object.
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct GcRootPtr(NonNull<GcRootMeta>);
+unsafe impl Send for GcRootPtr {}
 
 pub struct VMGcState {
     collector: LinkedListAllocator,
@@ -24,6 +32,12 @@ pub struct VMGcState {
     gc_condvar: Arc<GcLockState>,
     thread_id: usize,
     freed_ids: Vec<usize>,
+    /// Explicitly registered roots.
+    roots: Vec<GcRootPtr>,
+    /// The object each weak reference points to, cleared once it's freed.
+    weak_slots: Vec<Option<GcRootPtr>>,
+    /// Slots released by [release_weak](Self::release_weak), to be reused.
+    free_weak_slots: Vec<usize>,
     collector_id: u8,
     collection_index: u8,
 }
@@ -42,6 +56,9 @@ impl VMGcState {
             threads: FnvHashMap::default(),
             thread_id: 0,
             freed_ids: vec![],
+            roots: vec![],
+            weak_slots: vec![],
+            free_weak_slots: vec![],
             gc_condvar: Arc::new(GcLockState {
                 condvar: Condvar::new(),
                 mutex: Mutex::new(()),
@@ -66,21 +83,28 @@ impl VMGcState {
             v.def = structure;
             v.offset = offset;
         }
-        Structure::new(ptr)
+        Structure::new(ptr, self.collector_id)
 
     }
 
     fn allocate_memory(&mut self, thread: &mut ThreadState, l: Layout, vtable: GcRootVTable) -> NonNull<GcRootMeta> {
+        self.try_allocate_memory(thread, l, vtable)
+            .expect("could not allocate object")
+    }
+
+    /// Allocate memory, running collections if the heap is full.
+    /// Returns `None` if there is still no space after collecting.
+    fn try_allocate_memory(&mut self, thread: &mut ThreadState, l: Layout, vtable: GcRootVTable) -> Option<NonNull<GcRootMeta>> {
         let mut tries = 0;
         loop {
             unsafe {
                 let data = self.collector.alloc(l, vtable);
                 if let Some(data) = data {
-                    return data;
+                    return Some(data);
                 } else {
                     self.collection_run(thread);
                     if tries > 1 {
-                        panic!("could not allocate object");
+                        return None;
                     }
                     tries += 1;
                     continue;
@@ -89,7 +113,7 @@ impl VMGcState {
         }
     }
 
-    pub fn allocate_object<T: GcObject>(&mut self, thread: &mut ThreadState, object: T) -> GcRef<T> {
+    pub fn allocate_object<T: GcObject + 'static>(&mut self, thread: &mut ThreadState, object: T) -> GcRef<T> {
         let mut data = self.allocate_memory(thread, Layout::new::<T>(), GcRootVTable::new::<T>());
         unsafe {
             std::ptr::write(data.as_mut().data_ptr_mut(), object);
@@ -99,13 +123,43 @@ impl VMGcState {
         return ptr;
     }
 
+    /// Allocate a string, stored as modified UTF-8.
+    pub fn allocate_str(&mut self, thread: &mut ThreadState, s: &str) -> GcRef<GcStr> {
+        let bytes = encode_modified_utf8(s);
+        let (layout, _) = GcStr::layout(bytes.len());
+        let mut data = self.allocate_memory(thread, layout, GcRootVTable::new::<GcStr>());
+        unsafe {
+            GcStr::write(data.as_mut().data_ptr_mut(), &bytes);
+        }
+        GcRef::new(data, self.collection_index, self.collector_id)
+    }
+
+    /// Allocate an array of `len` uninitialized elements.
+    ///
+    /// Once every element is written, [assume_init](GcRef::assume_init)
+    /// gives the initialized array.
+    pub fn allocate_array<T: GcObject + Copy + 'static>(
+        &mut self,
+        thread: &mut ThreadState,
+        len: usize,
+    ) -> Result<GcRef<GcArray<MaybeUninit<T>>>, AllocationError> {
+        let (layout, _) = GcArray::<MaybeUninit<T>>::layout(len).ok_or(AllocationError::TooLarge)?;
+        let mut data = self
+            .try_allocate_memory(thread, layout, GcRootVTable::new::<GcArray<MaybeUninit<T>>>())
+            .ok_or(AllocationError::OutOfMemory)?;
+        unsafe {
+            GcArray::<MaybeUninit<T>>::write_header(data.as_mut().data_ptr_mut(), len);
+        }
+        Ok(GcRef::new(data, self.collection_index, self.collector_id))
+    }
+
     fn trace_thread(&mut self, t: &mut ThreadState) {
         let mut visitor = VisitorImpl;
         visitor.visit_noref(self, t);
     }
 
-    pub fn collection_run(&mut self, owning_thread: &mut ThreadState) {
-        self.gc_condvar.is_waiting.store(true, Ordering::SeqCst);
+    /// Mark everything reachable from the threads and the registered roots.
+    fn trace_roots(&mut self, owning_thread: &mut ThreadState) {
         self.trace_thread(owning_thread);
 
         let id = owning_thread.id;
@@ -115,6 +169,64 @@ impl VMGcState {
             }
         }
 
+        let mut visitor = VisitorImpl;
+        for mut root in self.roots.clone() {
+            unsafe {
+                visitor.visit(self, root.0.as_mut());
+            }
+        }
+    }
+
+    /// Register `object` as a root, keeping it alive until
+    /// a matching call to [remove_root](Self::remove_root).
+    pub fn add_root<T: ?Sized + GcObject>(&mut self, object: &mut GcRef<T>) {
+        self.roots.push(GcRootPtr(NonNull::from(object.root())));
+    }
+
+    /// Unregister a root added by [add_root](Self::add_root).
+    pub fn remove_root<T: ?Sized + GcObject>(&mut self, object: &mut GcRef<T>) {
+        let root = GcRootPtr(NonNull::from(object.root()));
+        if let Some(i) = self.roots.iter().position(|v| *v == root) {
+            self.roots.swap_remove(i);
+        }
+    }
+
+    /// Create a weak reference to `object`.
+    pub fn downgrade<T: ?Sized + GcObject>(&mut self, object: &mut GcRef<T>) -> WeakGcRef<T> {
+        let target = Some(GcRootPtr(NonNull::from(object.root())));
+        let slot = match self.free_weak_slots.pop() {
+            Some(slot) => {
+                self.weak_slots[slot] = target;
+                slot
+            }
+            None => {
+                self.weak_slots.push(target);
+                self.weak_slots.len() - 1
+            }
+        };
+        WeakGcRef { slot, collector_id: self.collector_id, _m: PhantomData }
+    }
+
+    /// The object `weak` refers to, or `None` if it has been collected.
+    ///
+    /// Panics if `weak` was created by a different collector.
+    pub fn upgrade<T: ?Sized + GcObject>(&self, weak: &WeakGcRef<T>) -> Option<GcRef<T>> {
+        assert_eq!(weak.collector_id, self.collector_id, "collector id mismatch");
+        let target = self.weak_slots[weak.slot]?;
+        Some(GcRef::new(target.0, self.collection_index, self.collector_id))
+    }
+
+    /// Free the slot held by `weak`.
+    pub fn release_weak<T: ?Sized + GcObject>(&mut self, weak: WeakGcRef<T>) {
+        assert_eq!(weak.collector_id, self.collector_id, "collector id mismatch");
+        self.weak_slots[weak.slot] = None;
+        self.free_weak_slots.push(weak.slot);
+    }
+
+    pub fn collection_run(&mut self, owning_thread: &mut ThreadState) {
+        self.gc_condvar.is_waiting.store(true, Ordering::SeqCst);
+        self.trace_roots(owning_thread);
+
         println!("Sweeping");
 
         // sweep
@@ -133,16 +245,25 @@ impl VMGcState {
         // TODO: use the write barrier instead
         let mut freed_objects = vec![];
 
-        self.trace_thread(owning_thread);
-
-        let id = owning_thread.id;
-        for (&k, thread) in &self.threads.clone() {
-            if k != id {
-                self.trace_thread(&mut *thread.lock());
+        // finalizers may have stored unmarked objects in marked ones, which
+        // wouldn't be traced again, so everything is traced from scratch
+        let mut o = self.collector.object_head;
+        while let Some(mut obj) = o {
+            unsafe {
+                o = obj.as_mut().list.next;
+                obj.as_mut().mark = Mark::White;
             }
         }
+        self.trace_roots(owning_thread);
         println!("Swept 2");
 
+        // everything still white is about to be freed
+        for slot in self.weak_slots.iter_mut() {
+            if slot.is_some_and(|v| unsafe { v.0.as_ref().mark == Mark::White }) {
+                *slot = None;
+            }
+        }
+
         let mut o = self.collector.object_head;
         while let Some(mut obj) = o {
             unsafe {
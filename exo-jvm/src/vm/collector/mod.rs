@@ -1,11 +1,12 @@
 pub mod structures;
 
 use std::{
+    any::TypeId,
     alloc::{self, Layout, dealloc},
     mem::{self, size_of}, num::NonZeroUsize, ptr::NonNull,
 };
 
-use parking_lot::Mutex;
+use parking_lot::RwLock;
 
 use self::{gc::VMGcState, object::{VisitorImpl, GcObject}, structures::GcRef};
 
@@ -43,14 +44,17 @@ pub struct GcRootMetaCopyable {
 #[repr(C)]
 #[derive(Clone, Copy)]
 pub struct GcRootVTable {
+    /// Type tag of the allocated object.
+    type_id: TypeId,
     needs_traced: bool,
     tracer: fn(&mut VisitorImpl, &mut VMGcState, *mut ()),
     finalizer: fn(NonNull<GcRootMeta>, u8, u8, VM, &mut VMGcState),
     dropper: fn(NonNull<GcRootMeta>)
 }
 impl GcRootVTable {
-    pub fn new<T: GcObject>() -> Self {
+    pub fn new<T: GcObject + 'static>() -> Self {
         Self {
+            type_id: TypeId::of::<T>(),
             needs_traced: T::NEEDS_TRACED,
             dropper: |mut p| {
                 unsafe {
@@ -78,10 +82,14 @@ pub struct GcRootMeta {
     layout: Layout,
     list: GcRootList,
     vtable: GcRootVTable,
-    lock: Mutex<()>,
+    lock: RwLock<()>,
 }
 
 impl GcRootMeta {
+    /// Type tag of the object stored in this root.
+    pub fn type_id(&self) -> TypeId {
+        self.vtable.type_id
+    }
     pub unsafe fn data_ptr<T>(&self) -> *const T {
         let off = self.data_offset;
         (self as *const Self as *const u8).add(off) as *const T
@@ -194,7 +202,7 @@ impl LinkedListAllocator {
             (*v_ptr).data_offset = off;
             (*v_ptr).vtable = vtable;
             (*v_ptr).layout = layout;
-            std::ptr::write(&mut (*v_ptr).lock, Mutex::new(()));
+            std::ptr::write(&mut (*v_ptr).lock, RwLock::new(()));
 
             let list = if let Some(v) = &mut self.object_head {
                 let mut cache = *v;
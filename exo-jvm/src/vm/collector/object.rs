@@ -45,10 +45,24 @@ pub unsafe trait Trace {
     );
     // fn finalize(this: NonNullGcPtr<Self>, j: JVM); todo
 }
-pub unsafe trait GcObject: Trace {
+pub unsafe trait GcObject: Trace + WriteBarrier {
     fn finalize(_this: GcRef<Self>, _vm: VM, _gc: &mut VMGcState) {}
 }
 
+/// Checks run on a value as it is stored in to a GC-managed object.
+pub trait WriteBarrier {
+    /// Called with the ID of the collector that owns the object being stored in to.
+    fn write_barrier(&self, _collector_id: u8) {}
+}
+
+impl<T: WriteBarrier> WriteBarrier for Option<T> {
+    fn write_barrier(&self, collector_id: u8) {
+        if let Some(v) = self {
+            v.write_barrier(collector_id);
+        }
+    }
+}
+
 
 macro_rules! bare_impl {
     ($($ty:ty),*) => {
@@ -68,10 +82,44 @@ macro_rules! bare_impl {
             unsafe impl GcObject for $ty {
 
             }
+
+            impl crate::vm::collector::object::WriteBarrier for $ty {}
         )*
 
     };
 }
 pub(crate) use bare_impl;
 
-bare_impl!((), u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, usize, isize);
\ No newline at end of file
+bare_impl!((), u8, u16, u32, u64, u128, i8, i16, i32, i64, i128, usize, isize);
+
+/// Traces each element, so a `Vec<GcRef<T>>` field keeps every referenced object alive.
+unsafe impl<T: GcObject> Trace for Vec<T> {
+    const NEEDS_TRACED: bool = T::NEEDS_TRACED;
+
+    fn trace(&mut self, gc: &mut VMGcState, visitor: &mut VisitorImpl) {
+        for v in self.iter_mut() {
+            visitor.visit_noref(gc, v);
+        }
+    }
+}
+unsafe impl<T: GcObject> GcObject for Vec<T> {}
+
+impl<T: WriteBarrier> WriteBarrier for Vec<T> {
+    fn write_barrier(&self, collector_id: u8) {
+        for v in self {
+            v.write_barrier(collector_id);
+        }
+    }
+}
+
+/// Traces the value if there is one, so a nullable `Option<GcRef<T>>` field keeps its object alive.
+unsafe impl<T: GcObject> Trace for Option<T> {
+    const NEEDS_TRACED: bool = T::NEEDS_TRACED;
+
+    fn trace(&mut self, gc: &mut VMGcState, visitor: &mut VisitorImpl) {
+        if let Some(v) = self {
+            visitor.visit_noref(gc, v);
+        }
+    }
+}
+unsafe impl<T: GcObject> GcObject for Option<T> {}
@@ -0,0 +1,128 @@
+use std::{alloc::Layout, marker::PhantomData, mem::MaybeUninit};
+
+use crate::vm::{
+    collector::{
+        gc::VMGcState,
+        object::{GcObject, Trace, VisitorImpl, WriteBarrier},
+        GcRootVTable,
+    },
+    thread::ThreadLocalHandle,
+};
+
+use super::GcRef;
+
+/// A GC-managed array.
+///
+/// This header is directly followed by the array's
+/// elements in the same allocation.
+#[repr(C)]
+pub struct GcArray<T> {
+    len: usize,
+    _m: PhantomData<T>,
+}
+
+impl<T> GcArray<T> {
+    /// Layout of an array with `len` elements, and the offset of the elements.
+    pub(crate) fn layout(len: usize) -> Option<(Layout, usize)> {
+        let (layout, offset) = Layout::new::<GcArray<T>>()
+            .extend(Layout::array::<T>(len).ok()?)
+            .ok()?;
+        Some((layout.pad_to_align(), offset))
+    }
+
+    /// Initialize the header of an array at `ptr`, which
+    /// must be allocated with [GcArray::layout].
+    pub(crate) unsafe fn write_header(ptr: *mut GcArray<T>, len: usize) {
+        std::ptr::write(ptr, GcArray { len, _m: PhantomData });
+    }
+
+    /// The elements following this header, borrowed immutably.
+    fn elements_ref(&self) -> &[T] {
+        let (_, offset) = Self::layout(self.len).unwrap();
+        unsafe {
+            let data = (self as *const Self as *const u8).add(offset) as *const T;
+            std::slice::from_raw_parts(data, self.len)
+        }
+    }
+
+    /// The elements following this header.
+    fn elements(&mut self) -> &mut [T] {
+        let (_, offset) = Self::layout(self.len).unwrap();
+        unsafe {
+            let data = (self as *mut Self as *mut u8).add(offset) as *mut T;
+            std::slice::from_raw_parts_mut(data, self.len)
+        }
+    }
+}
+
+unsafe impl<T: GcObject + Copy> Trace for GcArray<T> {
+    const NEEDS_TRACED: bool = T::NEEDS_TRACED;
+
+    fn trace(&mut self, gc: &mut VMGcState, visitor: &mut VisitorImpl) {
+        for v in self.elements() {
+            visitor.visit_noref(gc, v);
+        }
+    }
+}
+unsafe impl<T: GcObject + Copy> GcObject for GcArray<T> {}
+
+impl<T: GcObject + Copy> WriteBarrier for GcArray<T> {
+    fn write_barrier(&self, collector_id: u8) {
+        for v in self.elements_ref() {
+            v.write_barrier(collector_id);
+        }
+    }
+}
+
+/// Elements are never read while uninitialized, so there is nothing to trace.
+unsafe impl<T: GcObject + Copy> Trace for GcArray<MaybeUninit<T>> {
+    const NEEDS_TRACED: bool = false;
+
+    fn trace(&mut self, _gc: &mut VMGcState, _visitor: &mut VisitorImpl) {}
+}
+unsafe impl<T: GcObject + Copy> GcObject for GcArray<MaybeUninit<T>> {}
+
+/// Uninitialized elements aren't checked; they are checked as they are written.
+impl<T: GcObject + Copy> WriteBarrier for GcArray<MaybeUninit<T>> {}
+
+/// Returned when an array can't be allocated.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AllocationError {
+    /// The size of the array overflows.
+    TooLarge,
+    /// The heap has no space for the array, even after a collection.
+    OutOfMemory,
+}
+
+impl<T> GcRef<GcArray<T>>
+where
+    GcArray<T>: GcObject,
+{
+    /// The number of elements in this array.
+    pub fn len(&self, thread: &ThreadLocalHandle<'_>) -> usize {
+        self.get(thread, |v| v.len)
+    }
+
+    /// Borrows the elements for the duration of `f`, holding the array's lock.
+    pub fn with_slice<R, F: FnOnce(&[T]) -> R>(&self, thread: &ThreadLocalHandle<'_>, f: F) -> R {
+        self.get(thread, |v| f(v.elements_ref()))
+    }
+
+    /// Mutably borrows the elements for the duration of `f`, holding the array's lock.
+    pub fn update_slice<R, F: FnOnce(&mut [T]) -> R>(&mut self, thread: &ThreadLocalHandle<'_>, f: F) -> R {
+        self.check_same_thread(thread);
+        self.update(|v| f(v.elements()))
+    }
+}
+
+impl<T: GcObject + Copy + 'static> GcRef<GcArray<MaybeUninit<T>>> {
+    /// Treat the array as initialized.
+    ///
+    /// # Safety
+    /// Every element must have been written.
+    pub unsafe fn assume_init(self) -> GcRef<GcArray<T>> {
+        // the collector must now trace the elements, and see the initialized type
+        (*self.ptr()).vtable = GcRootVTable::new::<GcArray<T>>();
+        self.cast()
+    }
+}
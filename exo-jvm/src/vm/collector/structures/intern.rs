@@ -0,0 +1,79 @@
+use fnv::FnvHashMap;
+
+use crate::vm::thread::ThreadLocalHandle;
+
+use super::{GcRef, GcStr, WeakGcRef};
+
+/// A string pool, mapping each string to a single GC-managed copy, as
+/// `String.intern` does.
+///
+/// The table only holds weak references, so an interned string no longer
+/// used anywhere else is still collected. Its entry is dropped the next
+/// time the string is interned, or by [prune](Self::prune).
+///
+/// Each entry holds a weak reference slot in the collector, which can only
+/// be freed through a thread handle. Call [clear](Self::clear) before
+/// dropping the table, or those slots are never reused.
+#[derive(Debug, Default)]
+pub struct GcInternTable {
+    entries: FnvHashMap<String, WeakGcRef<GcStr>>,
+}
+
+impl GcInternTable {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The number of entries, including any whose string has been collected
+    /// but not yet pruned.
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// The interned copy of `s`, allocating one if there is no live copy.
+    pub fn intern(&mut self, thread: &mut ThreadLocalHandle<'_>, s: &str) -> GcRef<GcStr> {
+        if let Some(weak) = self.entries.get(s) {
+            if let Some(v) = thread.upgrade(weak) {
+                return v;
+            }
+            if let Some(weak) = self.entries.remove(s) {
+                thread.release_weak(weak);
+            }
+        }
+        let mut v = thread.allocate_str(s);
+        let weak = thread.downgrade(&mut v);
+        self.entries.insert(s.to_string(), weak);
+        v
+    }
+
+    /// The interned copy of `s`, if there is a live one.
+    pub fn lookup(&self, thread: &mut ThreadLocalHandle<'_>, s: &str) -> Option<GcRef<GcStr>> {
+        thread.upgrade(self.entries.get(s)?)
+    }
+
+    /// Drop every entry, releasing their weak references.
+    pub fn clear(&mut self, thread: &mut ThreadLocalHandle<'_>) {
+        for (_, weak) in self.entries.drain() {
+            thread.release_weak(weak);
+        }
+    }
+
+    /// Drop every entry whose string has been collected.
+    pub fn prune(&mut self, thread: &mut ThreadLocalHandle<'_>) {
+        let dead: Vec<String> = self
+            .entries
+            .iter()
+            .filter(|(_, weak)| thread.upgrade(weak).is_none())
+            .map(|(k, _)| k.clone())
+            .collect();
+        for k in dead {
+            if let Some(weak) = self.entries.remove(&k) {
+                thread.release_weak(weak);
+            }
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use std::{borrow::Borrow, cell::UnsafeCell, hash::{Hash, Hasher}};
+
+use fnv::FnvHashMap;
+
+use crate::vm::{
+    collector::{
+        gc::VMGcState,
+        object::{GcObject, Trace, VisitorImpl, WriteBarrier},
+    },
+    thread::ThreadLocalHandle,
+};
+
+use super::GcRef;
+
+/// A GC-managed hash map.
+///
+/// Both keys and values are traced, so a map of references keeps
+/// everything it holds alive. Once allocated, entries should be stored
+/// with [insert](GcRef::insert), which runs the write barrier on both.
+pub struct GcMap<K, V> {
+    entries: FnvHashMap<Key<K>, V>,
+}
+
+/// A key which the collector can trace in place.
+///
+/// Tracing only changes the tag bits of the references in a key, which
+/// [GcRef]'s `Hash` and `Eq` ignore, so a traced key stays in its bucket.
+struct Key<K>(UnsafeCell<K>);
+
+impl<K> Key<K> {
+    fn new(key: K) -> Self {
+        Self(UnsafeCell::new(key))
+    }
+
+    fn get(&self) -> &K {
+        // only written through by `trace`, which has the map mutably borrowed
+        unsafe { &*self.0.get() }
+    }
+}
+
+impl<K: Hash> Hash for Key<K> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.get().hash(state)
+    }
+}
+
+impl<K: PartialEq> PartialEq for Key<K> {
+    fn eq(&self, other: &Self) -> bool {
+        self.get() == other.get()
+    }
+}
+
+impl<K: Eq> Eq for Key<K> {}
+
+impl<K> Borrow<K> for Key<K> {
+    fn borrow(&self) -> &K {
+        self.get()
+    }
+}
+
+impl<K: Eq + Hash, V> GcMap<K, V> {
+    pub fn new() -> Self {
+        Self { entries: FnvHashMap::default() }
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.entries.get(key)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Iterate over the entries, in no particular order.
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &V)> {
+        self.entries.iter().map(|(k, v)| (k.get(), v))
+    }
+}
+
+impl<K: Eq + Hash, V> Default for GcMap<K, V> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl<K: GcObject + Eq + Hash, V: GcObject> Trace for GcMap<K, V> {
+    const NEEDS_TRACED: bool = K::NEEDS_TRACED || V::NEEDS_TRACED;
+
+    fn trace(&mut self, gc: &mut VMGcState, visitor: &mut VisitorImpl) {
+        for (k, v) in self.entries.iter_mut() {
+            visitor.visit_noref(gc, unsafe { &mut *k.0.get() });
+            visitor.visit_noref(gc, v);
+        }
+    }
+}
+unsafe impl<K: GcObject + Eq + Hash, V: GcObject> GcObject for GcMap<K, V> {}
+
+impl<K: GcObject + Eq + Hash, V: GcObject> WriteBarrier for GcMap<K, V> {
+    fn write_barrier(&self, collector_id: u8) {
+        for (k, v) in &self.entries {
+            k.get().write_barrier(collector_id);
+            v.write_barrier(collector_id);
+        }
+    }
+}
+
+impl<K, V> GcRef<GcMap<K, V>>
+where
+    K: GcObject + Eq + Hash,
+    V: GcObject,
+{
+    /// Insert an entry, returning the value previously stored under `key`.
+    ///
+    /// Panics in debug builds if `key` or `value` was allocated by a different collector.
+    pub fn insert(&mut self, thread: &ThreadLocalHandle<'_>, key: K, value: V) -> Option<V> {
+        self.barrier(thread, &key);
+        self.barrier(thread, &value);
+        self.update(|v| v.entries.insert(Key::new(key), value))
+    }
+
+    /// Remove the entry for `key`, returning its value.
+    pub fn remove(&mut self, thread: &ThreadLocalHandle<'_>, key: &K) -> Option<V> {
+        self.check_same_thread(thread);
+        self.update(|v| v.entries.remove(key))
+    }
+
+    /// Copy out the value stored under `key`.
+    pub fn lookup(&self, thread: &ThreadLocalHandle<'_>, key: &K) -> Option<V>
+    where
+        V: Copy,
+    {
+        self.get(thread, |v| v.entries.get(key).copied())
+    }
+
+    /// The number of entries in this map.
+    pub fn len(&self, thread: &ThreadLocalHandle<'_>) -> usize {
+        self.get(thread, |v| v.entries.len())
+    }
+}
@@ -1,4 +1,14 @@
+mod array;
+mod intern;
+mod map;
 mod reference;
 mod structure;
-pub use reference::GcRef;
-pub use structure::*;
\ No newline at end of file
+mod string;
+mod weak;
+pub use array::{AllocationError, GcArray};
+pub use intern::GcInternTable;
+pub use map::GcMap;
+pub use reference::{GcRef, NullableGcRef};
+pub use structure::*;
+pub use string::GcStr;
+pub use weak::WeakGcRef;
\ No newline at end of file
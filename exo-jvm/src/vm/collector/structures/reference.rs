@@ -1,9 +1,9 @@
-use std::{marker::PhantomData, num::NonZeroU64, ptr::NonNull, sync::atomic::Ordering};
+use std::{any::TypeId, marker::PhantomData, num::NonZeroU64, ptr::NonNull, sync::atomic::Ordering};
 
 use crate::vm::{
     collector::{
         gc::VMGcState,
-        object::{GcObject, Trace, VisitorImpl},
+        object::{GcObject, Trace, VisitorImpl, WriteBarrier},
         GcRootMeta, GcRootMetaCopyable,
     },
     thread::ThreadLocalHandle,
@@ -43,21 +43,47 @@ impl<T: ?Sized + GcObject> GcRef<T> {
         }
     }
 
+    /// Mutably borrows the referenced object for the duration of `f`,
+    /// holding the object's lock exclusively.
+    ///
+    /// Panics in debug builds if `f` leaves a reference allocated by a
+    /// different collector in the object.
     pub fn update<R, F: FnOnce(&mut T) -> R>(&mut self, f: F) -> R
     where
         T: Sized,
     {
+        let collector_id = self.collector_id();
         unsafe {
             let root = self.root();
             let ptr = root.data_ptr_mut::<T>();
-            let v = root.lock.lock();
+            let v = root.lock.write();
             let return_v = f(ptr.as_mut().unwrap());
+            if cfg!(debug_assertions) {
+                (*ptr).write_barrier(collector_id);
+            }
             drop(v);
             return return_v;
         }
     }
 
-    fn ptr(&self) -> *mut GcRootMeta {
+    /// Borrows the referenced object for the duration of `f`,
+    /// holding the object's lock shared. `f` may read the same object
+    /// again, since the shared lock is taken recursively.
+    pub fn get<R, F: FnOnce(&T) -> R>(&self, thread: &ThreadLocalHandle<'_>, f: F) -> R
+    where
+        T: Sized,
+    {
+        self.check_same_thread(thread);
+        unsafe {
+            let root = &*self.ptr();
+            let v = root.lock.read_recursive();
+            let return_v = f(&*root.data_ptr::<T>());
+            drop(v);
+            return_v
+        }
+    }
+
+    pub(super) fn ptr(&self) -> *mut GcRootMeta {
         let mut v = self.ptr.get();
         v &= !Self::COLLECTOR_ID;
         v &= !Self::COLLECTION_INDEX;
@@ -73,6 +99,51 @@ impl<T: ?Sized + GcObject> GcRef<T> {
         v
     }
 
+    /// Reinterprets this reference as a reference to `U`,
+    /// returning `None` if the object is not a `U`.
+    pub fn downcast<U: GcObject + 'static>(&self, thread: &ThreadLocalHandle<'_>) -> Option<GcRef<U>> {
+        self.check_same_thread(thread);
+        let type_id = unsafe { (*self.ptr()).type_id() };
+        if type_id != TypeId::of::<U>() {
+            return None;
+        }
+        Some(GcRef {
+            ptr: self.ptr,
+            _m: PhantomData,
+        })
+    }
+
+    /// Forgets the type of the referenced object.
+    /// The type can be recovered with [GcRef::downcast].
+    pub fn erase(&self) -> GcRef<()> {
+        GcRef {
+            ptr: self.ptr,
+            _m: PhantomData,
+        }
+    }
+
+    /// Reinterprets this reference as a reference to `U`.
+    ///
+    /// # Safety
+    /// The object must be valid as a `U`.
+    pub(super) unsafe fn cast<U: ?Sized + GcObject>(&self) -> GcRef<U> {
+        GcRef {
+            ptr: self.ptr,
+            _m: PhantomData,
+        }
+    }
+
+    /// Address of the root this reference points to, without the
+    /// collector ID and collection index bits.
+    ///
+    /// Objects are never moved, so the address is stable for the lifetime
+    /// of the object and can serve as the basis of `System.identityHashCode`.
+    /// It is only meaningful within one collector; references from different
+    /// collectors may share an address.
+    pub fn as_raw_addr(&self) -> usize {
+        self.ptr() as usize
+    }
+
     pub fn root(&mut self) -> &mut GcRootMeta {
         unsafe { self.ptr().as_mut().unwrap() }
     }
@@ -94,7 +165,7 @@ impl<T: ?Sized + GcObject> GcRef<T> {
         self.ptr |= (v as u64) << Self::COLLECTION_INDEX_SHIFT;
     }
 
-    fn check_same_thread(&self, t: &ThreadLocalHandle<'_>) {
+    pub(super) fn check_same_thread(&self, t: &ThreadLocalHandle<'_>) {
         if self.collection_index() < t.state().collection_index {
             panic!("collection index mismatch");
         }
@@ -103,8 +174,61 @@ impl<T: ?Sized + GcObject> GcRef<T> {
         }
     }
 
+    /// Debug-asserts that this reference was allocated by the collector `collector_id`.
+    fn debug_assert_same_collector(&self, collector_id: u8) {
+        debug_assert!(
+            self.collector_id() == collector_id,
+            "collector id mismatch: reference from collector {} used with collector {}",
+            self.collector_id(),
+            collector_id
+        );
+    }
+
+    /// Runs the write barrier for storing `value` in to this object.
+    pub(super) fn barrier<U: WriteBarrier>(&self, thread: &ThreadLocalHandle<'_>, value: &U) {
+        self.check_same_thread(thread);
+        value.write_barrier(self.collector_id());
+    }
+
+    /// Stores `value` in to the reference field of this object returned by `field`.
+    ///
+    /// Panics in debug builds if `value` was allocated by a different collector.
+    pub fn store_ref<U: ?Sized + GcObject, F: FnOnce(&mut T) -> &mut GcRef<U>>(
+        &mut self,
+        thread: &ThreadLocalHandle<'_>,
+        value: GcRef<U>,
+        field: F,
+    ) where
+        T: Sized,
+    {
+        self.barrier(thread, &value);
+        self.update(|v| *field(v) = value);
+    }
+
+}
+
+
+/// Null-safe access through references which may be null,
+/// as Java references are represented by `Option<GcRef<T>>`.
+pub trait NullableGcRef<T: GcObject> {
+    /// Applies `f` to the referenced object,
+    /// returning `None` if the reference is null.
+    fn map_ref<U, F: FnOnce(&T) -> U>(&self, thread: &ThreadLocalHandle<'_>, f: F) -> Option<U>;
+
+    /// Applies `f` to the referenced object, returning `None` if the
+    /// reference is null. Useful for following a chain of references.
+    fn and_then_ref<U, F: FnOnce(&T) -> Option<U>>(&self, thread: &ThreadLocalHandle<'_>, f: F) -> Option<U>;
 }
 
+impl<T: GcObject> NullableGcRef<T> for Option<GcRef<T>> {
+    fn map_ref<U, F: FnOnce(&T) -> U>(&self, thread: &ThreadLocalHandle<'_>, f: F) -> Option<U> {
+        self.as_ref().map(|v| v.get(thread, f))
+    }
+
+    fn and_then_ref<U, F: FnOnce(&T) -> Option<U>>(&self, thread: &ThreadLocalHandle<'_>, f: F) -> Option<U> {
+        self.as_ref().and_then(|v| v.get(thread, f))
+    }
+}
 
 pub(crate) unsafe fn load_raw<L: Copy>(ptr: *const L, ordering: Ordering) -> L {
     match ordering {
@@ -130,26 +254,65 @@ impl<T: ?Sized + GcObject + Copy> GcRef<T> {
             false => Ordering::Relaxed,
         };
         unsafe {
-            let offset = load_raw(self.ptr.get() as *const usize, ordering);
-            let data = (self.ptr.get() as *const u8).add(offset) as *const T;
+            let offset = load_raw(self.ptr() as *const usize, ordering);
+            let data = (self.ptr() as *const u8).add(offset) as *const T;
             load_raw(data, ordering)
         }
     }
 
+    /// Stores `v` in to the referenced object.
+    ///
+    /// Panics in debug builds if `v` holds a reference allocated by a different collector.
     pub fn store(&self, thread: &ThreadLocalHandle<'_>, volatile: bool, v: T) {
-        self.check_same_thread(thread);
+        self.barrier(thread, &v);
         let ordering = match volatile {
             true => Ordering::SeqCst,
             false => Ordering::Relaxed,
         };
         unsafe {
-            let offset = load_raw(self.ptr.get() as *const usize, ordering);
-            let data = (self.ptr.get() as *const u8).add(offset) as *mut T;
+            let offset = load_raw(self.ptr() as *const usize, ordering);
+            let data = (self.ptr() as *const u8).add(offset) as *mut T;
             store_raw(data, ordering, v)
         }
     }
 }
 
+/// Copying a reference doesn't copy the referenced object.
+impl<T: ?Sized + GcObject> Clone for GcRef<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T: ?Sized + GcObject> Copy for GcRef<T> {}
+
+/// References are equal when they refer to the same object.
+impl<T: ?Sized + GcObject> PartialEq for GcRef<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.ptr() == other.ptr()
+    }
+}
+
+impl<T: ?Sized + GcObject> Eq for GcRef<T> {}
+
+/// Hashes the identity of the referenced object, consistently with [PartialEq].
+impl<T: ?Sized + GcObject> std::hash::Hash for GcRef<T> {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.ptr().hash(state)
+    }
+}
+
+/// Prints the root address and collector ID without touching the
+/// referenced object, so this is safe even once it has been freed.
+impl<T: ?Sized + GcObject> std::fmt::Debug for GcRef<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("GcRef")
+            .field("addr", &self.ptr())
+            .field("collector", &self.collector_id())
+            .finish()
+    }
+}
+
 unsafe impl<T: ?Sized + GcObject> Trace for GcRef<T> {
     const NEEDS_TRACED: bool = true;
 
@@ -160,4 +323,11 @@ unsafe impl<T: ?Sized + GcObject> Trace for GcRef<T> {
 }
 unsafe impl<T: ?Sized + GcObject> GcObject for GcRef<T> {}
 
+/// Debug-asserts the referenced object belongs to the collector being stored in to.
+impl<T: ?Sized + GcObject> WriteBarrier for GcRef<T> {
+    fn write_barrier(&self, collector_id: u8) {
+        self.debug_assert_same_collector(collector_id);
+    }
+}
+
 static_assertions::assert_eq_size!(GcRef<()>, u64);
@@ -0,0 +1,61 @@
+use std::{alloc::Layout, borrow::Cow, mem::size_of};
+
+use exo_class_file::item::constant_pool::decode_modified_utf8;
+
+use crate::vm::{
+    collector::object::{bare_impl, GcObject, Trace},
+    thread::ThreadLocalHandle,
+};
+
+use super::GcRef;
+
+/// A GC-managed string.
+///
+/// This header is directly followed by the string's
+/// modified UTF-8 bytes in the same allocation.
+#[repr(C)]
+pub struct GcStr {
+    len: usize,
+}
+bare_impl!(GcStr);
+
+impl GcStr {
+    /// Layout of a string with `len` bytes, and the offset of the bytes.
+    pub(crate) fn layout(len: usize) -> (Layout, usize) {
+        let (layout, offset) = Layout::new::<GcStr>()
+            .extend(Layout::array::<u8>(len).unwrap())
+            .unwrap();
+        (layout.pad_to_align(), offset)
+    }
+
+    /// Initialize a string at `ptr`, which must be allocated with [GcStr::layout].
+    pub(crate) unsafe fn write(ptr: *mut GcStr, bytes: &[u8]) {
+        std::ptr::write(ptr, GcStr { len: bytes.len() });
+        let data = (ptr as *mut u8).add(size_of::<GcStr>());
+        std::ptr::copy_nonoverlapping(bytes.as_ptr(), data, bytes.len());
+    }
+}
+
+impl GcRef<GcStr> {
+    /// The modified UTF-8 bytes of this string.
+    pub fn as_bytes<'a>(&'a self, thread: &'a ThreadLocalHandle<'_>) -> &'a [u8] {
+        self.check_same_thread(thread);
+        unsafe {
+            let header = (*self.ptr()).data_ptr::<GcStr>();
+            let data = (header as *const u8).add(size_of::<GcStr>());
+            std::slice::from_raw_parts(data, (*header).len)
+        }
+    }
+
+    /// This string, decoded from modified UTF-8.
+    ///
+    /// Borrows the bytes when they are also valid standard UTF-8.
+    pub fn as_str<'a>(&'a self, thread: &'a ThreadLocalHandle<'_>) -> Cow<'a, str> {
+        let bytes = self.as_bytes(thread);
+        match std::str::from_utf8(bytes) {
+            Ok(s) => Cow::Borrowed(s),
+            // written by `encode_modified_utf8`, so always valid
+            Err(_) => Cow::Owned(decode_modified_utf8(bytes).expect("GcStr holds invalid modified UTF-8")),
+        }
+    }
+}
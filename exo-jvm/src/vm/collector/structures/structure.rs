@@ -6,7 +6,7 @@ use exo_class_file::item::ids::{
 };
 use nonmax::NonMaxU8;
 
-use crate::vm::collector::{GcRootVTable, object::{GcObject, Trace}, GcRootMeta};
+use crate::vm::collector::{GcRootVTable, object::{GcObject, Trace, WriteBarrier}, GcRootMeta};
 
 use super::{GcRef, reference::{store_raw, load_raw}};
 
@@ -142,7 +142,7 @@ pub struct StructureMetadata {
 }
 
 pub fn structure_vtable() -> GcRootVTable {
-    GcRootVTable { needs_traced: true, tracer: |visitor, gc, obj| {
+    GcRootVTable { type_id: TypeId::of::<StructureMetadata>(), needs_traced: true, tracer: |visitor, gc, obj| {
         unsafe {
             let data: *mut StructureMetadata = obj as *mut StructureMetadata;
             visitor.visit_noref(gc, &mut (*data).def);
@@ -170,6 +170,8 @@ pub fn structure_vtable() -> GcRootVTable {
 
 pub struct Structure {
     meta: NonNull<GcRootMeta>,
+    /// ID of the collector which allocated this structure.
+    collector_id: u8,
 }
 unsafe impl Trace for Structure {
     const NEEDS_TRACED: bool = true;
@@ -188,10 +190,23 @@ unsafe impl GcObject for Structure {
     
 }
 
+/// Debug-asserts the structure belongs to the collector being stored in to.
+impl WriteBarrier for Structure {
+    fn write_barrier(&self, collector_id: u8) {
+        debug_assert!(
+            self.collector_id == collector_id,
+            "collector id mismatch: structure from collector {} used with collector {}",
+            self.collector_id,
+            collector_id
+        );
+    }
+}
+
 impl Structure {
-    pub fn new(meta: NonNull<GcRootMeta>) -> Self {
+    pub fn new(meta: NonNull<GcRootMeta>, collector_id: u8) -> Self {
         Self {
-            meta
+            meta,
+            collector_id
         }
     }
     pub fn field_offset(&mut self, name: &str) -> usize {
@@ -204,7 +219,11 @@ impl Structure {
             (*meta).offset + field_loc
         }
     }
+    /// Stores `v` at offset `off` in to this structure.
+    ///
+    /// Panics in debug builds if `v` holds a reference allocated by a different collector.
     pub unsafe fn store<T: GcObject + Copy + 'static>(&mut self, off: usize, volatile: bool, v: T) {
+        v.write_barrier(self.collector_id);
         let ordering = match volatile {
             true => Ordering::SeqCst,
             false => Ordering::Relaxed,
@@ -0,0 +1,47 @@
+use std::marker::PhantomData;
+
+use crate::vm::collector::object::{GcObject, Trace, VisitorImpl, WriteBarrier};
+use crate::vm::VMGcState;
+
+/// A reference which doesn't keep its object alive.
+///
+/// Created with [downgrade](crate::vm::thread::ThreadLocalHandle::downgrade)
+/// and read back with [upgrade](crate::vm::thread::ThreadLocalHandle::upgrade),
+/// which returns `None` once the object has been collected. The collector
+/// holds a slot for every weak reference until it is passed to
+/// [release_weak](crate::vm::thread::ThreadLocalHandle::release_weak).
+pub struct WeakGcRef<T: ?Sized + GcObject> {
+    /// Index of this reference's slot in the collector's weak reference table.
+    pub(in crate::vm::collector) slot: usize,
+    pub(in crate::vm::collector) collector_id: u8,
+    pub(in crate::vm::collector) _m: PhantomData<T>,
+}
+
+impl<T: ?Sized + GcObject> std::fmt::Debug for WeakGcRef<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WeakGcRef")
+            .field("slot", &self.slot)
+            .field("collector", &self.collector_id)
+            .finish()
+    }
+}
+
+/// Not traced, so holding a weak reference in an object doesn't keep its target alive.
+unsafe impl<T: ?Sized + GcObject> Trace for WeakGcRef<T> {
+    const NEEDS_TRACED: bool = false;
+
+    fn trace(&mut self, _gc: &mut VMGcState, _visitor: &mut VisitorImpl) {}
+}
+unsafe impl<T: ?Sized + GcObject> GcObject for WeakGcRef<T> {}
+
+/// Debug-asserts the reference was created by the collector being stored in to.
+impl<T: ?Sized + GcObject> WriteBarrier for WeakGcRef<T> {
+    fn write_barrier(&self, collector_id: u8) {
+        debug_assert!(
+            self.collector_id == collector_id,
+            "collector id mismatch: weak reference from collector {} used with collector {}",
+            self.collector_id,
+            collector_id
+        );
+    }
+}
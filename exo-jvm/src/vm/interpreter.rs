@@ -0,0 +1,919 @@
+//! A simple frame-based bytecode interpreter.
+
+use exo_class_file::{
+    error::ClassFileError,
+    item::{
+        constant_pool::ConstantPool,
+        file::{is_assignable, ClassFile},
+        methods::MethodInfo,
+        opcodes::{InstructionList, VMOpcode},
+    },
+};
+
+use super::collector::structures::GcRef;
+
+/// A value on the operand stack or in a local variable.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum JVMValue {
+    Int(i32),
+    Float(f32),
+    Long(i64),
+    Double(f64),
+    /// A reference to an object of any type, or `None` for `null`.
+    Reference(Option<GcRef<()>>),
+}
+
+impl JVMValue {
+    /// The number of stack or local variable slots this value takes.
+    pub fn size(&self) -> usize {
+        match self {
+            Self::Long(_) | Self::Double(_) => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// The type of value a load or store instruction operates on.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum ValueKind {
+    Int,
+    Float,
+    Long,
+    Double,
+    Reference,
+}
+
+impl ValueKind {
+    fn matches(&self, v: &JVMValue) -> bool {
+        matches!(
+            (self, v),
+            (Self::Int, JVMValue::Int(_))
+                | (Self::Float, JVMValue::Float(_))
+                | (Self::Long, JVMValue::Long(_))
+                | (Self::Double, JVMValue::Double(_))
+                | (Self::Reference, JVMValue::Reference(_))
+        )
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum LocalAccess {
+    Load(ValueKind),
+    Store(ValueKind),
+}
+
+/// The local variable index and access of a load or store instruction.
+fn local_access(op: &VMOpcode) -> Option<(usize, LocalAccess)> {
+    use LocalAccess::{Load, Store};
+    use ValueKind::*;
+    Some(match op {
+        VMOpcode::iload(v) => (*v as usize, Load(Int)),
+        VMOpcode::fload(v) => (*v as usize, Load(Float)),
+        VMOpcode::lload(v) => (*v as usize, Load(Long)),
+        VMOpcode::dload(v) => (*v as usize, Load(Double)),
+        VMOpcode::aload(v) => (*v as usize, Load(Reference)),
+        VMOpcode::istore(v) => (*v as usize, Store(Int)),
+        VMOpcode::fstore(v) => (*v as usize, Store(Float)),
+        VMOpcode::lstore(v) => (*v as usize, Store(Long)),
+        VMOpcode::dstore(v) => (*v as usize, Store(Double)),
+        VMOpcode::astore(v) => (*v as usize, Store(Reference)),
+        VMOpcode::iload_0() => (0, Load(Int)),
+        VMOpcode::iload_1() => (1, Load(Int)),
+        VMOpcode::iload_2() => (2, Load(Int)),
+        VMOpcode::iload_3() => (3, Load(Int)),
+        VMOpcode::fload_0() => (0, Load(Float)),
+        VMOpcode::fload_1() => (1, Load(Float)),
+        VMOpcode::fload_2() => (2, Load(Float)),
+        VMOpcode::fload_3() => (3, Load(Float)),
+        VMOpcode::lload_0() => (0, Load(Long)),
+        VMOpcode::lload_1() => (1, Load(Long)),
+        VMOpcode::lload_2() => (2, Load(Long)),
+        VMOpcode::lload_3() => (3, Load(Long)),
+        VMOpcode::dload_0() => (0, Load(Double)),
+        VMOpcode::dload_1() => (1, Load(Double)),
+        VMOpcode::dload_2() => (2, Load(Double)),
+        VMOpcode::dload_3() => (3, Load(Double)),
+        VMOpcode::aload_0() => (0, Load(Reference)),
+        VMOpcode::aload_1() => (1, Load(Reference)),
+        VMOpcode::aload_2() => (2, Load(Reference)),
+        VMOpcode::aload_3() => (3, Load(Reference)),
+        VMOpcode::istore_0() => (0, Store(Int)),
+        VMOpcode::istore_1() => (1, Store(Int)),
+        VMOpcode::istore_2() => (2, Store(Int)),
+        VMOpcode::istore_3() => (3, Store(Int)),
+        VMOpcode::fstore_0() => (0, Store(Float)),
+        VMOpcode::fstore_1() => (1, Store(Float)),
+        VMOpcode::fstore_2() => (2, Store(Float)),
+        VMOpcode::fstore_3() => (3, Store(Float)),
+        VMOpcode::lstore_0() => (0, Store(Long)),
+        VMOpcode::lstore_1() => (1, Store(Long)),
+        VMOpcode::lstore_2() => (2, Store(Long)),
+        VMOpcode::lstore_3() => (3, Store(Long)),
+        VMOpcode::dstore_0() => (0, Store(Double)),
+        VMOpcode::dstore_1() => (1, Store(Double)),
+        VMOpcode::dstore_2() => (2, Store(Double)),
+        VMOpcode::dstore_3() => (3, Store(Double)),
+        VMOpcode::astore_0() => (0, Store(Reference)),
+        VMOpcode::astore_1() => (1, Store(Reference)),
+        VMOpcode::astore_2() => (2, Store(Reference)),
+        VMOpcode::astore_3() => (3, Store(Reference)),
+        _ => return None,
+    })
+}
+
+/// Errors which can occur while interpreting bytecode.
+#[derive(Debug)]
+pub enum InterpreterError {
+    /// Returned when a value is popped from an empty operand stack.
+    StackUnderflow,
+
+    /// Returned when pushing a value would take the operand stack past `max_stack` slots.
+    StackOverflow,
+
+    /// Returned when a local variable index is out of range.
+    LocalIndexOutOfRange(usize),
+
+    /// Returned when a local variable is read before being written.
+    UninitializedLocal(usize),
+
+    /// Returned when a value has the wrong type for an instruction.
+    TypeMismatch,
+
+    /// Returned when a branch target is not the start of an instruction.
+    BadBranchTarget,
+
+    /// Returned when execution runs past the end of the code.
+    FellOffCode,
+
+    /// Returned when the interpreter does not support an instruction.
+    UnsupportedOpcode(VMOpcode),
+
+    /// Returned when an instruction refers to classes but the
+    /// frame was created without a [ClassContext].
+    MissingClassContext,
+
+    /// Returned when an instruction's constant pool operand can't be resolved.
+    BadConstant(ClassFileError),
+
+    /// Returned when `checkcast` fails, where Java code would
+    /// see a `java/lang/ClassCastException`.
+    ClassCast { class: String, target: String },
+
+    /// Returned when a method without a `Code` attribute,
+    /// such as an abstract or native method, is executed.
+    MissingCode,
+}
+
+/// Class information the interpreter needs to run instructions
+/// which refer to classes, supplied by the embedding VM.
+pub trait ClassContext {
+    /// The constant pool of the class declaring the running method.
+    fn constant_pool(&self) -> &ConstantPool;
+
+    /// The internal name of the class of `object`, or an
+    /// array descriptor such as `[I` for arrays.
+    fn class_of(&self, object: GcRef<()>) -> String;
+
+    /// Load the class named `name`, used to find supertypes.
+    fn load_class(&self, name: &str) -> Option<ClassFile>;
+}
+
+pub type InterpreterResult<T> = std::result::Result<T, InterpreterError>;
+
+/// A single method activation.
+///
+/// Each value on the operand stack is a single [JVMValue], so a `long` or
+/// `double` is one entry which counts as two slots against `max_stack` and
+/// can't be split by slot-based instructions such as `pop` or `dup_x1`.
+pub struct Frame<'a> {
+    code: &'a InstructionList,
+    context: Option<&'a dyn ClassContext>,
+    /// Local variables of this frame.
+    pub locals: Vec<Option<JVMValue>>,
+    /// The operand stack.
+    pub stack: Vec<JVMValue>,
+    /// The largest number of slots the operand stack may take.
+    pub max_stack: usize,
+    /// Index of the next instruction to execute.
+    pub pc: usize,
+}
+
+macro_rules! pop {
+    ($frame:expr, $ty:ident) => {
+        match $frame.pop()? {
+            JVMValue::$ty(v) => v,
+            _ => return Err(InterpreterError::TypeMismatch),
+        }
+    };
+}
+
+macro_rules! branch_if {
+    ($frame:expr, $off:expr, $cond:expr) => {
+        if $cond {
+            $frame.branch(*$off as isize)?;
+        }
+    };
+}
+
+impl<'a> Frame<'a> {
+    pub fn new(code: &'a InstructionList, max_stack: usize, max_locals: usize) -> Self {
+        Self {
+            code,
+            context: None,
+            locals: vec![None; max_locals],
+            stack: vec![],
+            max_stack,
+            pc: 0,
+        }
+    }
+
+    /// Use `context` to run instructions which refer to classes.
+    pub fn with_context(mut self, context: &'a dyn ClassContext) -> Self {
+        self.context = Some(context);
+        self
+    }
+
+    /// The number of slots taken by the operand stack.
+    pub fn stack_depth(&self) -> usize {
+        self.stack.iter().map(JVMValue::size).sum()
+    }
+
+    fn push(&mut self, v: JVMValue) -> InterpreterResult<()> {
+        if self.stack_depth() + v.size() > self.max_stack {
+            return Err(InterpreterError::StackOverflow);
+        }
+        self.stack.push(v);
+        Ok(())
+    }
+
+    fn pop(&mut self) -> InterpreterResult<JVMValue> {
+        self.stack.pop().ok_or(InterpreterError::StackUnderflow)
+    }
+
+    /// Pop values taking exactly `slots` slots, returned bottom first.
+    fn pop_slots(&mut self, slots: usize) -> InterpreterResult<Vec<JVMValue>> {
+        let mut values = vec![];
+        let mut taken = 0;
+        while taken < slots {
+            let v = self.pop()?;
+            taken += v.size();
+            values.push(v);
+        }
+        // a two-slot value can't be split
+        if taken != slots {
+            return Err(InterpreterError::TypeMismatch);
+        }
+        values.reverse();
+        Ok(values)
+    }
+
+    /// Duplicate the top `slots` slots, inserting the copy `under` slots down.
+    fn dup(&mut self, slots: usize, under: usize) -> InterpreterResult<()> {
+        let top = self.pop_slots(slots)?;
+        let below = self.pop_slots(under)?;
+        for v in top.iter().chain(below.iter()).chain(top.iter()) {
+            self.push(*v)?;
+        }
+        Ok(())
+    }
+
+    fn load(&self, index: usize) -> InterpreterResult<JVMValue> {
+        self.locals
+            .get(index)
+            .ok_or(InterpreterError::LocalIndexOutOfRange(index))?
+            .ok_or(InterpreterError::UninitializedLocal(index))
+    }
+
+    /// Store `v` at `index`, taking the local after it too for a `long` or `double`.
+    fn store(&mut self, index: usize, v: JVMValue) -> InterpreterResult<()> {
+        let last = index + v.size() - 1;
+        if last >= self.locals.len() {
+            return Err(InterpreterError::LocalIndexOutOfRange(last));
+        }
+        // storing into the second local of a long or double invalidates it
+        if index > 0 && matches!(self.locals[index - 1], Some(JVMValue::Long(_) | JVMValue::Double(_))) {
+            self.locals[index - 1] = None;
+        }
+        self.locals[index] = Some(v);
+        if v.size() == 2 {
+            self.locals[index + 1] = None;
+        }
+        Ok(())
+    }
+
+    /// Load a local onto the stack or store the top of the stack into a
+    /// local, checking the value has the type the instruction expects.
+    fn access_local(&mut self, index: usize, access: LocalAccess) -> InterpreterResult<()> {
+        match access {
+            LocalAccess::Load(kind) => {
+                let v = self.load(index)?;
+                if !kind.matches(&v) {
+                    return Err(InterpreterError::TypeMismatch);
+                }
+                self.push(v)
+            }
+            LocalAccess::Store(kind) => {
+                let v = self.pop()?;
+                if !kind.matches(&v) {
+                    return Err(InterpreterError::TypeMismatch);
+                }
+                self.store(index, v)
+            }
+        }
+    }
+
+    fn iinc(&mut self, index: usize, constant: i32) -> InterpreterResult<()> {
+        match self.load(index)? {
+            JVMValue::Int(v) => self.store(index, JVMValue::Int(v.wrapping_add(constant))),
+            _ => Err(InterpreterError::TypeMismatch),
+        }
+    }
+
+    fn context(&self) -> InterpreterResult<&'a dyn ClassContext> {
+        self.context.ok_or(InterpreterError::MissingClassContext)
+    }
+
+    /// Get the name of the class constant at `index`.
+    fn class_name(&self, index: u16) -> InterpreterResult<&'a str> {
+        self.context()?
+            .constant_pool()
+            .get_class_name(index as usize)
+            .map_err(InterpreterError::BadConstant)
+    }
+
+    /// Whether `object` is an instance of the class constant at `index`.
+    fn is_instance(&self, object: GcRef<()>, index: u16) -> InterpreterResult<bool> {
+        let context = self.context()?;
+        let target = self.class_name(index)?;
+        Ok(is_assignable(&context.class_of(object), target, |v| context.load_class(v)))
+    }
+
+    /// Jump by a byte offset relative to the current instruction.
+    fn branch(&mut self, offset: isize) -> InterpreterResult<()> {
+        let current = *self
+            .code
+            .code_to_byte
+            .get(&(self.pc - 1))
+            .ok_or(InterpreterError::BadBranchTarget)?;
+        let target = current
+            .checked_add_signed(offset)
+            .ok_or(InterpreterError::BadBranchTarget)?;
+        let index = *self
+            .code
+            .byte_to_code
+            .get(&target)
+            .ok_or(InterpreterError::BadBranchTarget)?;
+        if self.code.code_to_byte.get(&index) != Some(&target) {
+            return Err(InterpreterError::BadBranchTarget);
+        }
+        self.pc = index;
+        Ok(())
+    }
+
+    /// Execute a single instruction.
+    ///
+    /// Returns `Some` once the method returns, holding
+    /// the returned value if there is one.
+    pub fn step(&mut self) -> InterpreterResult<Option<Option<JVMValue>>> {
+        let inst = self
+            .code
+            .opcodes
+            .get(self.pc)
+            .ok_or(InterpreterError::FellOffCode)?;
+        self.pc += 1;
+        match inst {
+            VMOpcode::nop() => (),
+            VMOpcode::iconst_m1() => self.push(JVMValue::Int(-1))?,
+            VMOpcode::iconst_0() => self.push(JVMValue::Int(0))?,
+            VMOpcode::iconst_1() => self.push(JVMValue::Int(1))?,
+            VMOpcode::iconst_2() => self.push(JVMValue::Int(2))?,
+            VMOpcode::iconst_3() => self.push(JVMValue::Int(3))?,
+            VMOpcode::iconst_4() => self.push(JVMValue::Int(4))?,
+            VMOpcode::iconst_5() => self.push(JVMValue::Int(5))?,
+            VMOpcode::bipush(v) => self.push(JVMValue::Int(*v as i8 as i32))?,
+            VMOpcode::sipush(v) => self.push(JVMValue::Int(*v as i16 as i32))?,
+            VMOpcode::aconst_null() => self.push(JVMValue::Reference(None))?,
+            VMOpcode::iinc(index, constant) => self.iinc(*index as usize, *constant as i8 as i32)?,
+            VMOpcode::wide_format1(op, index) => match local_access(op) {
+                Some((_, access)) => self.access_local(*index as usize, access)?,
+                None => return Err(InterpreterError::UnsupportedOpcode(inst.clone())),
+            },
+            VMOpcode::wide_format2(_, index, constant) => {
+                self.iinc(*index as usize, *constant as i16 as i32)?
+            }
+            VMOpcode::pop() => {
+                self.pop_slots(1)?;
+            }
+            VMOpcode::pop2() => {
+                self.pop_slots(2)?;
+            }
+            VMOpcode::dup() => self.dup(1, 0)?,
+            VMOpcode::dup_x1() => self.dup(1, 1)?,
+            VMOpcode::dup_x2() => self.dup(1, 2)?,
+            VMOpcode::dup2() => self.dup(2, 0)?,
+            VMOpcode::dup2_x1() => self.dup(2, 1)?,
+            VMOpcode::dup2_x2() => self.dup(2, 2)?,
+            VMOpcode::swap() => {
+                let values = self.pop_slots(2)?;
+                let [a, b] = values[..] else {
+                    return Err(InterpreterError::TypeMismatch);
+                };
+                self.push(b)?;
+                self.push(a)?;
+            }
+            VMOpcode::iadd() => {
+                let (b, a) = (pop!(self, Int), pop!(self, Int));
+                self.push(JVMValue::Int(a.wrapping_add(b)))?;
+            }
+            VMOpcode::isub() => {
+                let (b, a) = (pop!(self, Int), pop!(self, Int));
+                self.push(JVMValue::Int(a.wrapping_sub(b)))?;
+            }
+            VMOpcode::imul() => {
+                let (b, a) = (pop!(self, Int), pop!(self, Int));
+                self.push(JVMValue::Int(a.wrapping_mul(b)))?;
+            }
+            VMOpcode::ineg() => {
+                let a = pop!(self, Int);
+                self.push(JVMValue::Int(a.wrapping_neg()))?;
+            }
+            VMOpcode::ladd() => {
+                let (b, a) = (pop!(self, Long), pop!(self, Long));
+                self.push(JVMValue::Long(a.wrapping_add(b)))?;
+            }
+            VMOpcode::lsub() => {
+                let (b, a) = (pop!(self, Long), pop!(self, Long));
+                self.push(JVMValue::Long(a.wrapping_sub(b)))?;
+            }
+            VMOpcode::lmul() => {
+                let (b, a) = (pop!(self, Long), pop!(self, Long));
+                self.push(JVMValue::Long(a.wrapping_mul(b)))?;
+            }
+            VMOpcode::lneg() => {
+                let a = pop!(self, Long);
+                self.push(JVMValue::Long(a.wrapping_neg()))?;
+            }
+            VMOpcode::i2l() => {
+                let v = pop!(self, Int);
+                self.push(JVMValue::Long(v as i64))?;
+            }
+            VMOpcode::i2f() => {
+                let v = pop!(self, Int);
+                self.push(JVMValue::Float(v as f32))?;
+            }
+            VMOpcode::i2d() => {
+                let v = pop!(self, Int);
+                self.push(JVMValue::Double(v as f64))?;
+            }
+            VMOpcode::l2i() => {
+                let v = pop!(self, Long);
+                self.push(JVMValue::Int(v as i32))?;
+            }
+            VMOpcode::l2f() => {
+                let v = pop!(self, Long);
+                self.push(JVMValue::Float(v as f32))?;
+            }
+            VMOpcode::l2d() => {
+                let v = pop!(self, Long);
+                self.push(JVMValue::Double(v as f64))?;
+            }
+            // float to integer casts saturate and map NaN to zero, as in Java
+            VMOpcode::f2i() => {
+                let v = pop!(self, Float);
+                self.push(JVMValue::Int(v as i32))?;
+            }
+            VMOpcode::f2l() => {
+                let v = pop!(self, Float);
+                self.push(JVMValue::Long(v as i64))?;
+            }
+            VMOpcode::f2d() => {
+                let v = pop!(self, Float);
+                self.push(JVMValue::Double(v as f64))?;
+            }
+            VMOpcode::d2i() => {
+                let v = pop!(self, Double);
+                self.push(JVMValue::Int(v as i32))?;
+            }
+            VMOpcode::d2l() => {
+                let v = pop!(self, Double);
+                self.push(JVMValue::Long(v as i64))?;
+            }
+            VMOpcode::d2f() => {
+                let v = pop!(self, Double);
+                self.push(JVMValue::Float(v as f32))?;
+            }
+            VMOpcode::i2b() => {
+                let v = pop!(self, Int);
+                self.push(JVMValue::Int(v as i8 as i32))?;
+            }
+            VMOpcode::i2c() => {
+                let v = pop!(self, Int);
+                self.push(JVMValue::Int(v as u16 as i32))?;
+            }
+            VMOpcode::i2s() => {
+                let v = pop!(self, Int);
+                self.push(JVMValue::Int(v as i16 as i32))?;
+            }
+            VMOpcode::goto(off) => self.branch(*off as isize)?,
+            VMOpcode::goto_w(off) => self.branch(*off as i32 as isize)?,
+            VMOpcode::ifeq(off) => branch_if!(self, off, pop!(self, Int) == 0),
+            VMOpcode::ifne(off) => branch_if!(self, off, pop!(self, Int) != 0),
+            VMOpcode::iflt(off) => branch_if!(self, off, pop!(self, Int) < 0),
+            VMOpcode::ifge(off) => branch_if!(self, off, pop!(self, Int) >= 0),
+            VMOpcode::ifgt(off) => branch_if!(self, off, pop!(self, Int) > 0),
+            VMOpcode::ifle(off) => branch_if!(self, off, pop!(self, Int) <= 0),
+            VMOpcode::if_icmpeq(off) => {
+                let (b, a) = (pop!(self, Int), pop!(self, Int));
+                branch_if!(self, off, a == b)
+            }
+            VMOpcode::if_icmpne(off) => {
+                let (b, a) = (pop!(self, Int), pop!(self, Int));
+                branch_if!(self, off, a != b)
+            }
+            VMOpcode::if_icmplt(off) => {
+                let (b, a) = (pop!(self, Int), pop!(self, Int));
+                branch_if!(self, off, a < b)
+            }
+            VMOpcode::if_icmpge(off) => {
+                let (b, a) = (pop!(self, Int), pop!(self, Int));
+                branch_if!(self, off, a >= b)
+            }
+            VMOpcode::if_icmpgt(off) => {
+                let (b, a) = (pop!(self, Int), pop!(self, Int));
+                branch_if!(self, off, a > b)
+            }
+            VMOpcode::if_icmple(off) => {
+                let (b, a) = (pop!(self, Int), pop!(self, Int));
+                branch_if!(self, off, a <= b)
+            }
+            VMOpcode::ireturn() => return Ok(Some(Some(JVMValue::Int(pop!(self, Int))))),
+            VMOpcode::freturn() => return Ok(Some(Some(JVMValue::Float(pop!(self, Float))))),
+            VMOpcode::lreturn() => return Ok(Some(Some(JVMValue::Long(pop!(self, Long))))),
+            VMOpcode::dreturn() => return Ok(Some(Some(JVMValue::Double(pop!(self, Double))))),
+            VMOpcode::areturn() => return Ok(Some(Some(JVMValue::Reference(pop!(self, Reference))))),
+            VMOpcode::r#return() => return Ok(Some(None)),
+            VMOpcode::checkcast(index) => {
+                let object = pop!(self, Reference);
+                // null can be cast to any type
+                if let Some(object) = object {
+                    if !self.is_instance(object, *index)? {
+                        return Err(InterpreterError::ClassCast {
+                            class: self.context()?.class_of(object),
+                            target: self.class_name(*index)?.to_string(),
+                        });
+                    }
+                }
+                self.push(JVMValue::Reference(object))?
+            }
+            VMOpcode::instanceof(index) => {
+                let object = pop!(self, Reference);
+                let instance = match object {
+                    Some(object) => self.is_instance(object, *index)?,
+                    None => false,
+                };
+                self.push(JVMValue::Int(instance as i32))?
+            }
+            // the many load and store forms are decoded by `local_access`
+            v => match local_access(v) {
+                Some((index, access)) => self.access_local(index, access)?,
+                None => return Err(InterpreterError::UnsupportedOpcode(v.clone())),
+            },
+        }
+        Ok(None)
+    }
+
+    /// Run this frame until it returns.
+    pub fn run(&mut self) -> InterpreterResult<Option<JVMValue>> {
+        loop {
+            if let Some(v) = self.step()? {
+                return Ok(v);
+            }
+        }
+    }
+}
+
+/// Entry point for running a whole method.
+///
+/// The interpreter currently implements:
+/// - `nop`, the `iconst_<i>` family, `bipush`, `sipush` and `aconst_null`
+/// - every load and store of a local, `iinc` and their `wide` forms
+/// - `pop`, `pop2`, `swap` and the `dup` family
+/// - `iadd`, `isub`, `imul`, `ineg` and their `long` equivalents
+/// - the primitive conversions, `i2l` through `i2s`
+/// - `goto`, `goto_w`, the `if<cond>` and `if_icmp<cond>` branches
+/// - the `return` family
+/// - `checkcast` and `instanceof`, given a [ClassContext]
+///
+/// Any other instruction stops execution with [InterpreterError::UnsupportedOpcode].
+pub struct Interpreter;
+
+impl Interpreter {
+    /// Run `method` with `args` in its first local variables until it returns,
+    /// giving the returned value if there is one.
+    ///
+    /// A `long` or `double` argument takes two local variables, as on invocation
+    /// by the JVM, and an instance method's receiver must be the first argument.
+    pub fn execute(
+        method: &MethodInfo,
+        args: &[JVMValue],
+        context: Option<&dyn ClassContext>,
+    ) -> InterpreterResult<Option<JVMValue>> {
+        let code = method.code().ok_or(InterpreterError::MissingCode)?;
+        let mut frame = Frame::new(&code.code, code.max_stack as usize, code.max_locals as usize);
+        frame.context = context;
+        let mut index = 0;
+        for arg in args {
+            frame.store(index, *arg)?;
+            index += arg.size();
+        }
+        frame.run()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use exo_class_file::{
+        item::{
+            constant_pool::{ConstantPool, ConstantPoolEntry},
+            file::ClassFile,
+            methods::MethodInfo,
+            opcodes::InstructionList,
+            ClassFileItem,
+        },
+        stream::ClassFileStream,
+    };
+
+    use crate::vm::{collector::structures::GcRef, thread::ThreadLocalHandle, VM};
+
+    use super::{ClassContext, Frame, Interpreter, InterpreterError, JVMValue};
+
+    #[test]
+    fn wide_iinc() {
+        // wide iinc 300 -2; wide iload 300; ireturn
+        let code = [0xc4, 0x84, 0x01, 0x2c, 0xff, 0xfe, 0xc4, 0x15, 0x01, 0x2c, 0xac];
+        let code = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+
+        let mut frame = Frame::new(&code, 1, 301);
+        frame.locals[300] = Some(JVMValue::Int(5));
+        assert_eq!(frame.run().unwrap(), Some(JVMValue::Int(3)));
+    }
+
+    struct Classes {
+        cp: ConstantPool,
+        objects: Vec<(GcRef<()>, &'static str)>,
+    }
+
+    impl ClassContext for Classes {
+        fn constant_pool(&self) -> &ConstantPool {
+            &self.cp
+        }
+
+        fn class_of(&self, object: GcRef<()>) -> String {
+            self.objects.iter().find(|v| v.0 == object).unwrap().1.to_string()
+        }
+
+        fn load_class(&self, _: &str) -> Option<ClassFile> {
+            None
+        }
+    }
+
+    #[test]
+    fn casts() {
+        let (_vm, thread) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread.lock());
+        let object = h.allocate_object(0i32).erase();
+
+        let utf8 = |v: &str| ConstantPoolEntry::utf8(v.to_string());
+        let classes = Classes {
+            cp: ConstantPool {
+                entries: vec![
+                    utf8("A"),
+                    ConstantPoolEntry::Class { name_index: 1 },
+                    utf8("B"),
+                    ConstantPoolEntry::Class { name_index: 3 },
+                ],
+            },
+            objects: vec![(object, "A")],
+        };
+        let run = |op: u8, class: u8, v: Option<GcRef<()>>| {
+            // aload_0; <op> <class>; areturn
+            let code = [0x2a, op, 0x00, class, 0xb0];
+            let code = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+            let mut frame = Frame::new(&code, 1, 1).with_context(&classes);
+            frame.locals[0] = Some(JVMValue::Reference(v));
+            frame.step().unwrap();
+            frame.step().map(|_| frame.stack.pop())
+        };
+
+        // instanceof
+        assert_eq!(run(0xc1, 2, Some(object)).unwrap(), Some(JVMValue::Int(1)));
+        assert_eq!(run(0xc1, 4, Some(object)).unwrap(), Some(JVMValue::Int(0)));
+        assert_eq!(run(0xc1, 2, None).unwrap(), Some(JVMValue::Int(0)));
+
+        // checkcast
+        assert_eq!(run(0xc0, 2, Some(object)).unwrap(), Some(JVMValue::Reference(Some(object))));
+        assert_eq!(run(0xc0, 4, None).unwrap(), Some(JVMValue::Reference(None)));
+        assert!(matches!(
+            run(0xc0, 4, Some(object)),
+            Err(InterpreterError::ClassCast { class, target }) if class == "A" && target == "B"
+        ));
+    }
+
+    #[test]
+    fn stack_manipulation() {
+        use JVMValue::*;
+
+        // lload 0; iload 1; iload 2; dup2_x2; swap; pop; pop; dup2; pop2; pop2; dup_x1; return
+        let code = [0x16, 0x00, 0x15, 0x01, 0x15, 0x02, 0x5e, 0x5f, 0x57, 0x57, 0x5c, 0x58, 0x58, 0x5a, 0xb1];
+        let code = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+
+        let mut frame = Frame::new(&code, 8, 3);
+        frame.locals = vec![Some(Long(7)), Some(Int(1)), Some(Int(2))];
+        let mut step = |n| {
+            for _ in 0..n {
+                assert_eq!(frame.step().unwrap(), None);
+            }
+            frame.stack.clone()
+        };
+        assert_eq!(step(4), [Int(1), Int(2), Long(7), Int(1), Int(2)]);
+        assert_eq!(step(1), [Int(1), Int(2), Long(7), Int(2), Int(1)]);
+        assert_eq!(step(2), [Int(1), Int(2), Long(7)]);
+        assert_eq!(step(1), [Int(1), Int(2), Long(7), Long(7)]);
+        assert_eq!(step(2), [Int(1), Int(2)]);
+        assert_eq!(step(1), [Int(2), Int(1), Int(2)]);
+
+        // dup2 on a single long, then pop on a long fails
+        let code = [0x16, 0x00, 0x5c, 0x57];
+        let code = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+        let mut frame = Frame::new(&code, 4, 1);
+        frame.locals[0] = Some(Long(3));
+        frame.step().unwrap();
+        frame.step().unwrap();
+        assert_eq!(frame.stack, [Long(3), Long(3)]);
+        assert!(frame.step().is_err());
+    }
+
+    fn run_conversion(op: u8, v: JVMValue) -> Option<JVMValue> {
+        // <load> 0; <op>; return
+        let load = match v {
+            JVMValue::Int(_) => 0x15,
+            JVMValue::Long(_) => 0x16,
+            JVMValue::Float(_) => 0x17,
+            _ => 0x18,
+        };
+        let code = [load, 0x00, op, 0xb1];
+        let code = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+
+        let mut frame = Frame::new(&code, 2, 1);
+        frame.locals[0] = Some(v);
+        frame.step().unwrap();
+        frame.step().unwrap();
+        frame.stack.pop()
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(run_conversion(0x91, JVMValue::Int(0x123456c8)), Some(JVMValue::Int(-56)));
+        assert_eq!(run_conversion(0x92, JVMValue::Int(-1)), Some(JVMValue::Int(0xffff)));
+        assert_eq!(run_conversion(0x93, JVMValue::Int(0x18000)), Some(JVMValue::Int(-32768)));
+        assert_eq!(run_conversion(0x85, JVMValue::Int(-7)), Some(JVMValue::Long(-7)));
+        assert_eq!(run_conversion(0x88, JVMValue::Long(0x1_0000_0005)), Some(JVMValue::Int(5)));
+        assert_eq!(run_conversion(0x8b, JVMValue::Float(f32::NAN)), Some(JVMValue::Int(0)));
+        assert_eq!(run_conversion(0x8e, JVMValue::Double(1e20)), Some(JVMValue::Int(i32::MAX)));
+    }
+
+    #[test]
+    fn long_slots() {
+        use JVMValue::*;
+
+        // lload 0; lload 2; ladd; dup2; ladd; lreturn
+        let code = [0x16, 0x00, 0x16, 0x02, 0x61, 0x5c, 0x61, 0xad];
+        let code = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+
+        let mut frame = Frame::new(&code, 4, 4);
+        frame.locals[0] = Some(Long(5));
+        frame.locals[2] = Some(Long(i64::MAX));
+        for _ in 0..4 {
+            frame.step().unwrap();
+        }
+        assert_eq!(frame.stack, [Long(i64::MIN + 4), Long(i64::MIN + 4)]);
+        assert_eq!(frame.stack_depth(), 4);
+        assert_eq!(frame.run().unwrap(), Some(Long(8)));
+
+        // the same code overflows a two slot stack
+        let mut frame = Frame::new(&code, 2, 4);
+        frame.locals[0] = Some(Long(5));
+        frame.locals[2] = Some(Long(1));
+        frame.step().unwrap();
+        assert!(matches!(frame.step(), Err(InterpreterError::StackOverflow)));
+    }
+
+    #[test]
+    fn typed_locals() {
+        use JVMValue::*;
+
+        // aconst_null; astore_1; aload_1; areturn
+        let code = [0x01, 0x4c, 0x2b, 0xb0];
+        let code = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+        let mut frame = Frame::new(&code, 1, 2);
+        assert_eq!(frame.run().unwrap(), Some(Reference(None)));
+
+        // iconst_1; astore_0
+        let code = [0x04, 0x4b];
+        let code = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+        let mut frame = Frame::new(&code, 1, 1);
+        frame.step().unwrap();
+        assert!(matches!(frame.step(), Err(InterpreterError::TypeMismatch)));
+        assert_eq!(frame.locals[0], None);
+
+        // aload_0; istore_0
+        let code = [0x2a, 0x3b];
+        let code = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+        let mut frame = Frame::new(&code, 1, 1);
+        frame.locals[0] = Some(Reference(None));
+        frame.step().unwrap();
+        assert!(matches!(frame.step(), Err(InterpreterError::TypeMismatch)));
+
+        // fload 0 of an int
+        let code = [0x17, 0x00];
+        let code = InstructionList::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(code)), None).unwrap();
+        let mut frame = Frame::new(&code, 1, 1);
+        frame.locals[0] = Some(Int(1));
+        assert!(matches!(frame.step(), Err(InterpreterError::TypeMismatch)));
+    }
+
+    /// A static method with a `Code` attribute holding `code`.
+    fn method(max_stack: u8, max_locals: u8, code: &[u8]) -> MethodInfo {
+        let mut bytes = vec![0x00, 0x08, 0x00, 0x02, 0x00, 0x03, 0x00, 0x01, 0x00, 0x01];
+        bytes.extend((12 + code.len() as u32).to_be_bytes());
+        bytes.extend([0x00, max_stack, 0x00, max_locals]);
+        bytes.extend((code.len() as u32).to_be_bytes());
+        bytes.extend(code);
+        bytes.extend([0x00, 0x00, 0x00, 0x00]);
+
+        let utf8 = |v: &str| ConstantPoolEntry::utf8(v.to_string());
+        let cp = ConstantPool {
+            entries: vec![utf8("Code"), utf8("m"), utf8("(JI)J")],
+        };
+        MethodInfo::read_from_stream(&mut ClassFileStream::new(&mut Cursor::new(bytes)), Some(&cp)).unwrap()
+    }
+
+    #[test]
+    fn execute() {
+        use JVMValue::*;
+
+        // lload_0; iload_2; i2l; ladd; lreturn
+        let add = method(4, 3, &[0x1e, 0x1c, 0x85, 0x61, 0xad]);
+        assert_eq!(Interpreter::execute(&add, &[Long(40), Int(2)], None).unwrap(), Some(Long(42)));
+        assert!(matches!(
+            Interpreter::execute(&add, &[Long(40), Int(2), Int(0)], None),
+            Err(InterpreterError::LocalIndexOutOfRange(3))
+        ));
+
+        // a long argument needs two locals
+        let ret = method(2, 2, &[0xb1]);
+        assert!(matches!(
+            Interpreter::execute(&ret, &[Int(0), Long(1)], None),
+            Err(InterpreterError::LocalIndexOutOfRange(2))
+        ));
+
+        // lload_0; lload_0; lcmp
+        let compare = method(4, 3, &[0x1e, 0x1e, 0x94]);
+        assert!(matches!(
+            Interpreter::execute(&compare, &[Long(1), Int(0)], None),
+            Err(InterpreterError::UnsupportedOpcode(_))
+        ));
+    }
+
+    #[test]
+    fn two_slot_locals() {
+        use JVMValue::*;
+
+        // lload_0; lstore_1; return
+        let store_last = method(2, 2, &[0x1e, 0x40, 0xb1]);
+        assert!(matches!(
+            Interpreter::execute(&store_last, &[Long(1)], None),
+            Err(InterpreterError::LocalIndexOutOfRange(2))
+        ));
+
+        // lload_0; lstore_1; iload_2; ireturn
+        let overwrite = method(2, 4, &[0x1e, 0x40, 0x1c, 0xac]);
+        assert!(matches!(
+            Interpreter::execute(&overwrite, &[Long(1), Int(2), Int(3)], None),
+            Err(InterpreterError::UninitializedLocal(2))
+        ));
+
+        // iconst_0; istore_1; lload_0; lreturn
+        let split = method(2, 2, &[0x03, 0x3c, 0x1e, 0xad]);
+        assert!(matches!(
+            Interpreter::execute(&split, &[Long(1)], None),
+            Err(InterpreterError::UninitializedLocal(0))
+        ));
+
+        // iconst_0; istore_2; dload_0; dreturn
+        let after = method(2, 3, &[0x03, 0x3d, 0x26, 0xaf]);
+        assert_eq!(Interpreter::execute(&after, &[Double(1.0)], None).unwrap(), Some(Double(1.0)));
+    }
+}
@@ -5,6 +5,7 @@ use std::{cell::RefCell, rc::Rc, num::NonZeroUsize};
 pub mod thread;
 pub mod bytecode;
 pub mod collector;
+pub mod interpreter;
 use std::{sync::{Arc, atomic::{AtomicBool, Ordering}}, time::Duration};
 
 use fnv::FnvHashMap;
@@ -68,12 +69,632 @@ impl Clone for VM {
 
 #[cfg(test)]
 mod tests {
-    use std::time::Duration;
+    use std::{sync::atomic::{AtomicUsize, Ordering}, time::Duration};
 
-    use crate::vm::collector::structures::{StructureBuilder, FieldDef};
+    use crate::vm::collector::object::{GcObject, Trace, VisitorImpl, WriteBarrier};
+    use crate::vm::collector::structures::{AllocationError, GcArray, GcInternTable, GcMap, GcRef, NullableGcRef, StructureBuilder, FieldDef};
 
     use super::thread::ThreadLocalHandle;
-    use super::VM;
+    use super::{VMGcState, VM};
+
+    #[test]
+    #[should_panic(expected = "collector id mismatch")]
+    fn cross_collector_store() {
+        let (_vm_a, thread_a) = VM::new();
+        let (_vm_b, thread_b) = VM::new();
+        let mut a = ThreadLocalHandle::new(thread_a.lock());
+        let mut b = ThreadLocalHandle::new(thread_b.lock());
+
+        let inner = a.allocate_object(1i32);
+        let mut holder = a.allocate_object(inner);
+        let foreign = b.allocate_object(2i32);
+        holder.store_ref(&a, foreign, |v| v);
+    }
+
+    #[test]
+    #[should_panic(expected = "collector id mismatch")]
+    fn cross_collector_update() {
+        let (_vm_a, thread_a) = VM::new();
+        let (_vm_b, thread_b) = VM::new();
+        let mut a = ThreadLocalHandle::new(thread_a.lock());
+        let mut b = ThreadLocalHandle::new(thread_b.lock());
+
+        let inner = a.allocate_object(1i32);
+        let mut holder = a.allocate_object(Some(inner));
+        let foreign = b.allocate_object(2i32);
+        holder.update(|v| *v = Some(foreign));
+    }
+
+    #[test]
+    #[should_panic(expected = "collector id mismatch")]
+    fn cross_collector_store_value() {
+        let (_vm_a, thread_a) = VM::new();
+        let (_vm_b, thread_b) = VM::new();
+        let mut a = ThreadLocalHandle::new(thread_a.lock());
+        let mut b = ThreadLocalHandle::new(thread_b.lock());
+
+        let inner = a.allocate_object(1i32);
+        let holder = a.allocate_object(inner);
+        let foreign = b.allocate_object(2i32);
+        holder.store(&a, false, foreign);
+    }
+
+    #[test]
+    #[should_panic(expected = "collector id mismatch")]
+    fn cross_collector_structure_store() {
+        let (_vm_a, thread_a) = VM::new();
+        let (_vm_b, thread_b) = VM::new();
+        let mut a = ThreadLocalHandle::new(thread_a.lock());
+        let mut b = ThreadLocalHandle::new(thread_b.lock());
+
+        let def = StructureBuilder::new().add_field(FieldDef::new::<GcRef<i32>>("value".to_string())).build();
+        let def = a.allocate_object(def);
+        let mut v = a.construct_structure(def);
+        let off = v.field_offset("value");
+        let foreign = b.allocate_object(2i32);
+        unsafe {
+            v.store(off, false, foreign);
+        }
+    }
+
+    #[test]
+    fn load_store_tagged_ref() {
+        // the second collector has a non-zero ID, so its references carry
+        // tag bits that must be masked off before the root is read
+        let (_vm_a, _thread_a) = VM::new();
+        let (_vm_b, thread_b) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread_b.lock());
+        assert_ne!(h.state().collector_id, 0);
+
+        let v = h.allocate_object(5i32);
+        assert_eq!(v.load(&h, false), 5);
+        v.store(&h, true, 7);
+        assert_eq!(v.load(&h, true), 7);
+    }
+
+    #[test]
+    fn downcast() {
+        let (_vm, thread) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread.lock());
+
+        let v = h.allocate_object(5i32);
+        assert!(v.downcast::<i64>(&h).is_none());
+        assert_eq!(v.downcast::<i32>(&h).unwrap().load(&h, false), 5);
+    }
+
+    #[test]
+    fn load_store() {
+        let (_vm, thread) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread.lock());
+
+        let v = h.allocate_object(1i32);
+        v.store(&h, false, 2);
+        assert_eq!(v.load(&h, false), 2);
+        assert_eq!(v.load(&h, true), 2);
+        v.store(&h, true, -3);
+        assert_eq!(v.load(&h, true), -3);
+        assert_eq!(v.load(&h, false), -3);
+    }
+
+    #[test]
+    #[should_panic(expected = "collector id mismatch")]
+    fn cross_collector_load() {
+        let (_vm_a, thread_a) = VM::new();
+        let (_vm_b, thread_b) = VM::new();
+        let mut a = ThreadLocalHandle::new(thread_a.lock());
+        let b = ThreadLocalHandle::new(thread_b.lock());
+
+        let v = a.allocate_object(1i32);
+        v.load(&b, true);
+    }
+
+    #[test]
+    fn raw_addr() {
+        let (_vm, thread) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread.lock());
+
+        let a = h.allocate_object(1i32);
+        let b = h.allocate_object(1i32);
+        assert_eq!(a.as_raw_addr(), a.erase().as_raw_addr());
+        assert_ne!(a.as_raw_addr(), b.as_raw_addr());
+        assert_eq!(a.as_raw_addr() as u64 >> 48, 0);
+    }
+
+    #[test]
+    fn erased_identity() {
+        let (_vm, thread) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread.lock());
+
+        let a = h.allocate_object(1i32);
+        let b = h.allocate_object(1i32);
+        let copy = a;
+        assert_eq!(copy, a);
+        assert_eq!(a.erase(), copy.erase());
+        assert_ne!(a.erase(), b.erase());
+        assert_eq!(a.erase().downcast::<i32>(&h), Some(a));
+        assert_eq!(a.erase().downcast::<i64>(&h), None);
+    }
+
+    #[test]
+    fn identity_key() {
+        let (_vm, thread) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread.lock());
+
+        let a = h.allocate_object(1i32);
+        let b = h.allocate_object(1i32);
+        let mut interned = std::collections::HashMap::new();
+        interned.insert(Some(a), "a");
+        interned.insert(Some(b), "b");
+        interned.insert(None, "null");
+        assert_eq!(interned[&Some(a)], "a");
+        assert_eq!(interned[&Some(b)], "b");
+        assert_eq!(interned[&None], "null");
+        assert_ne!(a, b);
+
+        let debug = format!("{:?}", a);
+        assert!(debug.contains("addr") && debug.contains("collector"));
+    }
+
+    #[test]
+    fn allocate_array() {
+        let (_vm, thread) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread.lock());
+
+        let mut uninit = h.allocate_array::<i32>(4).unwrap();
+        assert_eq!(uninit.len(&h), 4);
+        uninit.update_slice(&h, |v| {
+            for (i, v) in v.iter_mut().enumerate() {
+                v.write(i as i32 * 10);
+            }
+        });
+        let array = unsafe { uninit.assume_init() };
+        assert_eq!(array.with_slice(&h, |v| v.to_vec()), [0, 10, 20, 30]);
+        assert!(array.erase().downcast::<GcArray<i32>>(&h).is_some());
+
+        assert!(matches!(h.allocate_array::<i32>(usize::MAX), Err(AllocationError::TooLarge)));
+    }
+
+    #[test]
+    fn nullable_ref() {
+        let (_vm, thread) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread.lock());
+
+        let inner = h.allocate_object(1i32);
+        let holder = Some(h.allocate_object(inner));
+        assert_eq!(Some(inner).map_ref(&h, |v| *v + 1), Some(2));
+        assert_eq!(holder.and_then_ref(&h, |v| Some(*v)).map_ref(&h, |v| *v), Some(1));
+
+        let null: Option<GcRef<GcRef<i32>>> = None;
+        assert_eq!(null.and_then_ref(&h, |v| Some(*v)).map_ref(&h, |v| *v), None);
+    }
+
+    #[test]
+    fn nested_get() {
+        let (_vm, thread) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread.lock());
+
+        let value = h.allocate_object(7i32);
+        let sum = value.get(&h, |a| value.get(&h, |b| *a + *b));
+        assert_eq!(sum, 14);
+        assert_eq!(Some(value).map_ref(&h, |a| Some(value).map_ref(&h, |b| *a * *b)), Some(Some(49)));
+    }
+
+    #[test]
+    fn allocate_str() {
+        let (_vm, thread) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread.lock());
+
+        let ascii = h.allocate_str("hello");
+        assert_eq!(ascii.as_bytes(&h), b"hello");
+        assert_eq!(ascii.as_str(&h), "hello");
+
+        let s = "nul\0 and \u{1d11e}";
+        let special = h.allocate_str(s);
+        assert!(!special.as_bytes(&h).contains(&0));
+        assert_eq!(special.as_bytes(&h).len(), s.len() + 1 + 2);
+        assert_eq!(special.as_str(&h), s);
+    }
+
+    static FINALIZED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Finalized(i32);
+    unsafe impl Trace for Finalized {
+        const NEEDS_TRACED: bool = false;
+        fn trace(&mut self, _gc: &mut VMGcState, _visitor: &mut VisitorImpl) {}
+    }
+    unsafe impl GcObject for Finalized {
+        fn finalize(_this: GcRef<Self>, _vm: VM, _gc: &mut VMGcState) {
+            FINALIZED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+    impl WriteBarrier for Finalized {}
+
+    #[test]
+    fn registered_roots() {
+        let (_vm, thread) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread.lock());
+
+        let mut v = h.allocate_object(Finalized(3));
+        h.add_root(&mut v);
+        h.perform_collection();
+        assert_eq!(FINALIZED.load(Ordering::SeqCst), 0);
+        assert_eq!(v.update(|v| v.0), 3);
+
+        h.remove_root(&mut v);
+        h.perform_collection();
+        assert_eq!(FINALIZED.load(Ordering::SeqCst), 1);
+    }
+
+    static DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    struct DropCounter;
+    unsafe impl Trace for DropCounter {
+        const NEEDS_TRACED: bool = false;
+        fn trace(&mut self, _gc: &mut VMGcState, _visitor: &mut VisitorImpl) {}
+    }
+    unsafe impl GcObject for DropCounter {}
+    impl WriteBarrier for DropCounter {}
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            DROPPED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn droppers_run_once() {
+        let (_vm, thread) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread.lock());
+
+        let mut kept: Vec<_> = (0..3).map(|_| h.allocate_object(DropCounter)).collect();
+        for v in kept.iter_mut() {
+            h.add_root(v);
+        }
+        for _ in 0..5 {
+            h.allocate_object(DropCounter);
+        }
+
+        h.perform_collection();
+        assert_eq!(DROPPED.load(Ordering::SeqCst), 5);
+        h.perform_collection();
+        assert_eq!(DROPPED.load(Ordering::SeqCst), 5);
+
+        for v in kept.iter_mut() {
+            h.remove_root(v);
+        }
+        h.perform_collection();
+        assert_eq!(DROPPED.load(Ordering::SeqCst), 8);
+        h.perform_collection();
+        assert_eq!(DROPPED.load(Ordering::SeqCst), 8);
+    }
+
+    static ELEMENTS_FINALIZED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Element(i32);
+    unsafe impl Trace for Element {
+        const NEEDS_TRACED: bool = false;
+        fn trace(&mut self, _gc: &mut VMGcState, _visitor: &mut VisitorImpl) {}
+    }
+    unsafe impl GcObject for Element {
+        fn finalize(_this: GcRef<Self>, _vm: VM, _gc: &mut VMGcState) {
+            ELEMENTS_FINALIZED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+    impl WriteBarrier for Element {}
+
+    #[derive(nugc_derive::Trace)]
+    struct Holder {
+        elements: Vec<GcRef<Element>>,
+        count: i32,
+    }
+    unsafe impl GcObject for Holder {}
+
+    #[test]
+    fn traced_vec() {
+        assert!(<Holder as Trace>::NEEDS_TRACED);
+        assert!(!<Vec<i32> as Trace>::NEEDS_TRACED);
+
+        let (_vm, thread) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread.lock());
+
+        let elements = (0..3).map(|i| h.allocate_object(Element(i))).collect();
+        let mut holder = h.allocate_object(Holder { elements, count: 3 });
+        h.add_root(&mut holder);
+        h.perform_collection();
+        assert_eq!(ELEMENTS_FINALIZED.load(Ordering::SeqCst), 0);
+        let mut first = holder.update(|v| v.elements[0]);
+        assert_eq!(first.update(|v| v.0), 0);
+        assert_eq!(holder.update(|v| v.count), 3);
+
+        h.remove_root(&mut holder);
+        h.perform_collection();
+        assert_eq!(ELEMENTS_FINALIZED.load(Ordering::SeqCst), 3);
+    }
+
+    static MAPPED_FINALIZED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Mapped(i32);
+    unsafe impl Trace for Mapped {
+        const NEEDS_TRACED: bool = false;
+        fn trace(&mut self, _gc: &mut VMGcState, _visitor: &mut VisitorImpl) {}
+    }
+    unsafe impl GcObject for Mapped {
+        fn finalize(_this: GcRef<Self>, _vm: VM, _gc: &mut VMGcState) {
+            MAPPED_FINALIZED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+    impl WriteBarrier for Mapped {}
+
+    #[test]
+    fn traced_map() {
+        assert!(<GcMap<GcRef<Mapped>, i32> as Trace>::NEEDS_TRACED);
+        assert!(<GcMap<i32, GcRef<Mapped>> as Trace>::NEEDS_TRACED);
+        assert!(!<GcMap<i32, i32> as Trace>::NEEDS_TRACED);
+
+        let (_vm, thread) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread.lock());
+
+        let mut map = h.allocate_object(GcMap::<GcRef<Mapped>, GcRef<Mapped>>::new());
+        h.add_root(&mut map);
+        let key = h.allocate_object(Mapped(1));
+        let value = h.allocate_object(Mapped(2));
+        assert_eq!(map.insert(&h, key, value), None);
+        let replaced = h.allocate_object(Mapped(3));
+        assert_eq!(map.insert(&h, replaced, replaced), None);
+        assert_eq!(map.insert(&h, replaced, value), Some(replaced));
+
+        h.perform_collection();
+        assert_eq!(MAPPED_FINALIZED.load(Ordering::SeqCst), 0);
+        assert_eq!(map.len(&h), 2);
+        let mut found = map.lookup(&h, &key).unwrap();
+        assert_eq!(found.update(|v| v.0), 2);
+
+        assert_eq!(map.remove(&h, &replaced), Some(value));
+        h.perform_collection();
+        assert_eq!(MAPPED_FINALIZED.load(Ordering::SeqCst), 1);
+
+        h.remove_root(&mut map);
+        h.perform_collection();
+        assert_eq!(MAPPED_FINALIZED.load(Ordering::SeqCst), 3);
+    }
+
+    #[test]
+    #[should_panic(expected = "collector id mismatch")]
+    fn cross_collector_map_insert() {
+        let (_vm_a, thread_a) = VM::new();
+        let (_vm_b, thread_b) = VM::new();
+        let mut a = ThreadLocalHandle::new(thread_a.lock());
+        let mut b = ThreadLocalHandle::new(thread_b.lock());
+
+        let mut map = a.allocate_object(GcMap::<i32, GcRef<i32>>::new());
+        let foreign = b.allocate_object(2i32);
+        map.insert(&a, 1, foreign);
+    }
+
+    #[test]
+    fn weak_ref() {
+        let (_vm, thread) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread.lock());
+
+        let mut v = h.allocate_object(5i32);
+        let weak = h.downgrade(&mut v);
+        h.add_root(&mut v);
+        h.perform_collection();
+        assert_eq!(h.upgrade(&weak), Some(v));
+
+        h.remove_root(&mut v);
+        h.perform_collection();
+        assert_eq!(h.upgrade(&weak), None);
+        h.release_weak(weak);
+
+        // the released slot is reused
+        let mut other = h.allocate_object(6i32);
+        let weak = h.downgrade(&mut other);
+        assert_eq!(h.upgrade(&weak).map(|v| v.load(&h, false)), Some(6));
+    }
+
+    #[test]
+    fn intern_table() {
+        let (_vm, thread) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread.lock());
+        let mut table = GcInternTable::new();
+
+        let mut kept = table.intern(&mut h, "kept");
+        assert_eq!(table.intern(&mut h, "kept"), kept);
+        h.add_root(&mut kept);
+        let dropped = table.intern(&mut h, "dropped");
+        assert_ne!(dropped, kept);
+        assert_eq!(table.lookup(&mut h, "dropped"), Some(dropped));
+
+        h.perform_collection();
+        assert_eq!(table.intern(&mut h, "kept"), kept);
+        assert_eq!(table.lookup(&mut h, "dropped"), None);
+        assert_eq!(table.len(), 2);
+
+        // a collected string is interned again
+        let again = table.intern(&mut h, "dropped");
+        assert_eq!(again.as_str(&h), "dropped");
+        assert_eq!(table.lookup(&mut h, "dropped"), Some(again));
+
+        h.perform_collection();
+        table.prune(&mut h);
+        assert_eq!(table.len(), 1);
+        assert_eq!(table.lookup(&mut h, "kept"), Some(kept));
+
+        // clearing releases the weak slots for reuse
+        table.clear(&mut h);
+        assert!(table.is_empty());
+        assert_eq!(kept.as_str(&h), "kept");
+        for _ in 0..2 {
+            let weak = h.downgrade(&mut kept);
+            assert!(!format!("{:?}", weak).contains("slot: 2"));
+        }
+    }
+
+    static NODES_DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    #[derive(nugc_derive::Trace)]
+    struct Node {
+        value: i32,
+        next: Option<GcRef<Node>>,
+    }
+    unsafe impl GcObject for Node {}
+    impl Drop for Node {
+        fn drop(&mut self) {
+            NODES_DROPPED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn linked_nodes() {
+        let (_vm, thread) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread.lock());
+
+        // built from the rooted head, so every node is reachable
+        // if an allocation runs a collection
+        let mut head = h.allocate_object(Node { value: 0, next: None });
+        h.add_root(&mut head);
+        let mut tail = head;
+        for i in 1..50 {
+            let node = h.allocate_object(Node { value: i, next: None });
+            tail.update(|v| v.next = Some(node));
+            tail = node;
+            // garbage between every node, filling the heap many times over
+            for j in 0..20 {
+                let mut v = h.allocate_object(Node { value: -j, next: None });
+                let this = v;
+                v.update(|v| v.next = Some(this));
+            }
+        }
+
+        h.perform_collection();
+        assert_eq!(NODES_DROPPED.load(Ordering::SeqCst), 49 * 20);
+        let mut node = Some(head);
+        let mut values = vec![];
+        while let Some(v) = node {
+            values.push(v.get(&h, |v| v.value));
+            node = v.get(&h, |v| v.next);
+        }
+        assert_eq!(values, (0..50).collect::<Vec<_>>());
+
+        // a cycle back to the head is freed with it
+        tail.update(|v| v.next = Some(head));
+        h.remove_root(&mut head);
+        h.perform_collection();
+        assert_eq!(NODES_DROPPED.load(Ordering::SeqCst), 49 * 20 + 50);
+    }
+
+    #[derive(nugc_derive::Trace)]
+    struct Stack {
+        values: Vec<GcRef<i32>>,
+    }
+    unsafe impl GcObject for Stack {}
+
+    enum Instruction {
+        Push(i32),
+        Pop,
+        Add,
+        Sub,
+    }
+
+    /// A stack machine whose values are all allocated,
+    /// so evaluating runs collections as the heap fills.
+    fn eval(h: &mut ThreadLocalHandle<'_>, stack: &mut GcRef<Stack>, instructions: &[Instruction]) {
+        for inst in instructions {
+            let v = match inst {
+                Instruction::Push(v) => *v,
+                Instruction::Pop => {
+                    stack.update(|s| s.values.pop());
+                    continue;
+                }
+                Instruction::Add | Instruction::Sub => {
+                    let (v1, v2) = stack.update(|s| {
+                        let v2 = s.values.pop().unwrap();
+                        (s.values.pop().unwrap(), v2)
+                    });
+                    let (v1, v2) = (v1.load(h, false), v2.load(h, false));
+                    if let Instruction::Add = inst { v1 + v2 } else { v1 - v2 }
+                }
+            };
+            let v = h.allocate_object(v);
+            stack.update(|s| s.values.push(v));
+        }
+    }
+
+    #[test]
+    fn stack_machine() {
+        let (_vm, thread) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread.lock());
+
+        let mut stack = h.allocate_object(Stack { values: vec![] });
+        h.add_root(&mut stack);
+        eval(&mut h, &mut stack, &[Instruction::Push(0)]);
+        for i in 0..1000 {
+            eval(
+                &mut h,
+                &mut stack,
+                &[Instruction::Push(i), Instruction::Push(1), Instruction::Pop, Instruction::Push(2), Instruction::Sub, Instruction::Add],
+            );
+        }
+        h.perform_collection();
+
+        assert_eq!(stack.update(|s| s.values.len()), 1);
+        let sum = stack.update(|s| s.values[0]).load(&h, false);
+        assert_eq!(sum, (0..1000).map(|i| i - 2).sum::<i32>());
+    }
+
+    static REVIVED_DROPPED: AtomicUsize = AtomicUsize::new(0);
+
+    struct Revived(i32);
+    unsafe impl Trace for Revived {
+        const NEEDS_TRACED: bool = false;
+        fn trace(&mut self, _gc: &mut VMGcState, _visitor: &mut VisitorImpl) {}
+    }
+    unsafe impl GcObject for Revived {}
+    impl WriteBarrier for Revived {}
+    impl Drop for Revived {
+        fn drop(&mut self) {
+            REVIVED_DROPPED.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[derive(nugc_derive::Trace)]
+    struct Slot {
+        value: Option<GcRef<Revived>>,
+    }
+    unsafe impl GcObject for Slot {}
+
+    #[derive(nugc_derive::Trace)]
+    struct Reviver {
+        slot: GcRef<Slot>,
+        revived: GcRef<Revived>,
+    }
+    unsafe impl GcObject for Reviver {
+        fn finalize(mut this: GcRef<Self>, _vm: VM, _gc: &mut VMGcState) {
+            let (mut slot, revived) = this.update(|v| (v.slot, v.revived));
+            slot.update(|v| v.value = Some(revived));
+        }
+    }
+
+    #[test]
+    fn finalizer_revives() {
+        let (_vm, thread) = VM::new();
+        let mut h = ThreadLocalHandle::new(thread.lock());
+
+        // the slot is marked before the finalizer stores the revived object in it
+        let mut slot = h.allocate_object(Slot { value: None });
+        h.add_root(&mut slot);
+        let revived = h.allocate_object(Revived(7));
+        h.allocate_object(Reviver { slot, revived });
+
+        h.perform_collection();
+        assert_eq!(REVIVED_DROPPED.load(Ordering::SeqCst), 0);
+        let revived = slot.get(&h, |v| v.value).unwrap();
+        assert_eq!(revived.get(&h, |v| v.0), 7);
+
+        slot.update(|v| v.value = None);
+        h.perform_collection();
+        assert_eq!(REVIVED_DROPPED.load(Ordering::SeqCst), 1);
+    }
 
     #[test]
     fn epic_balls() {
@@ -1,9 +1,9 @@
 
-use std::{sync::{Arc, atomic::{AtomicBool, Ordering}}, time::Duration, alloc::Layout};
+use std::{sync::{Arc, atomic::{AtomicBool, Ordering}}, time::Duration, alloc::Layout, mem::MaybeUninit};
 
 use parking_lot::{Mutex, MutexGuard, RwLock, Condvar, lock_api::MutexGuard as LMutexGuard, lock_api::RawMutex};
 
-use super::{VM, GcLockState, VMGcState, collector::{structures::{GcRef, Structure, StructureDef}, object::{GcObject, Trace, VisitorImpl}}};
+use super::{VM, GcLockState, VMGcState, collector::{structures::{AllocationError, GcArray, GcRef, GcStr, Structure, StructureDef, WeakGcRef}, object::{GcObject, Trace, VisitorImpl, WriteBarrier}}};
 
 pub struct ThreadState {
     pub vm: VM,
@@ -28,6 +28,12 @@ unsafe impl Trace for ThreadState {
 }
 unsafe impl GcObject for ThreadState {}
 
+impl WriteBarrier for ThreadState {
+    fn write_barrier(&self, collector_id: u8) {
+        self.gamer_stack.write_barrier(collector_id);
+    }
+}
+
 impl ThreadState {
     pub fn new(c: VM, id: usize, collector_id: u8, collection_index: u8) -> Arc<Mutex<Self>> {
         let v = c.gc.lock().gc_condvar().clone();
@@ -66,12 +72,61 @@ impl<'a> ThreadLocalHandle<'a> {
     }
 
 
-    pub fn allocate_object<T: GcObject>(&mut self, object: T) -> GcRef<T> {
+    pub fn allocate_object<T: GcObject + 'static>(&mut self, object: T) -> GcRef<T> {
         let vm = self.state.vm.clone();
         let mut collector = self.collector_lock(&vm.gc);
         collector.allocate_object(&mut self.state, object)
     }
 
+    /// Register `object` as a root, so it survives collections
+    /// without being reachable from a thread.
+    pub fn add_root<T: ?Sized + GcObject>(&mut self, object: &mut GcRef<T>) {
+        let vm = self.state.vm.clone();
+        let mut collector = self.collector_lock(&vm.gc);
+        collector.add_root(object);
+    }
+
+    /// Unregister a root added by [add_root](Self::add_root).
+    pub fn remove_root<T: ?Sized + GcObject>(&mut self, object: &mut GcRef<T>) {
+        let vm = self.state.vm.clone();
+        let mut collector = self.collector_lock(&vm.gc);
+        collector.remove_root(object);
+    }
+
+    /// Create a weak reference to `object`, which doesn't keep it alive.
+    pub fn downgrade<T: ?Sized + GcObject>(&mut self, object: &mut GcRef<T>) -> WeakGcRef<T> {
+        let vm = self.state.vm.clone();
+        let mut collector = self.collector_lock(&vm.gc);
+        collector.downgrade(object)
+    }
+
+    /// The object `weak` refers to, or `None` if it has been collected.
+    pub fn upgrade<T: ?Sized + GcObject>(&mut self, weak: &WeakGcRef<T>) -> Option<GcRef<T>> {
+        let vm = self.state.vm.clone();
+        let collector = self.collector_lock(&vm.gc);
+        collector.upgrade(weak)
+    }
+
+    /// Free the collector's slot for `weak`.
+    pub fn release_weak<T: ?Sized + GcObject>(&mut self, weak: WeakGcRef<T>) {
+        let vm = self.state.vm.clone();
+        let mut collector = self.collector_lock(&vm.gc);
+        collector.release_weak(weak);
+    }
+
+    pub fn allocate_str(&mut self, s: &str) -> GcRef<GcStr> {
+        let vm = self.state.vm.clone();
+        let mut collector = self.collector_lock(&vm.gc);
+        collector.allocate_str(&mut self.state, s)
+    }
+
+    /// Allocate an array of `len` uninitialized elements.
+    pub fn allocate_array<T: GcObject + Copy + 'static>(&mut self, len: usize) -> Result<GcRef<GcArray<MaybeUninit<T>>>, AllocationError> {
+        let vm = self.state.vm.clone();
+        let mut collector = self.collector_lock(&vm.gc);
+        collector.allocate_array(&mut self.state, len)
+    }
+
 
 
     fn collector_lock<'b>(&self, vm: &'b Mutex<VMGcState>) -> MutexGuard<'b, VMGcState> {
@@ -110,6 +165,7 @@ impl<'a> ThreadLocalHandle<'a> {
     // }
 
 
+    /// Run a collection, tracing every thread and registered root.
     pub fn perform_collection(&mut self) {
         let vm = self.state.vm.clone();
         let mut collector = self.collector_lock(&vm.gc);
@@ -0,0 +1,20 @@
+import java.lang.annotation.Retention;
+import java.lang.annotation.RetentionPolicy;
+
+@Marker(kind = Kind.A, type = Annotated.class, nested = @Inner(Annotated[].class))
+public class Annotated {
+    void take(@Inner(Annotated.class) Object value) {}
+}
+
+@Retention(RetentionPolicy.RUNTIME)
+@interface Marker {
+    Kind kind();
+    Class<?> type();
+    Inner nested();
+}
+
+@interface Inner {
+    Class<?> value();
+}
+
+enum Kind { A }
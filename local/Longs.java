@@ -0,0 +1,5 @@
+public class Longs {
+    static long big() { return 123456789012L; }
+    static double ratio() { return 2.5; }
+    static String name() { return "after"; }
+}
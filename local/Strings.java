@@ -0,0 +1,7 @@
+public class Strings {
+    String nul = "nul\0byte";
+    String clef = "clef \uD834\uDD1E";
+    String plain = "plain";
+    String nulAgain = "nul\0byte";
+    String lone = "lone \uD834";
+}
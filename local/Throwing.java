@@ -0,0 +1,10 @@
+import java.io.IOException;
+import java.util.concurrent.TimeoutException;
+
+public class Throwing {
+    public void load() throws IOException, InterruptedException, TimeoutException {
+    }
+
+    public void quiet() {
+    }
+}